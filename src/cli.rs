@@ -0,0 +1,316 @@
+use vidyut::error::ErrorFormat;
+use vidyut::lex::TokenFormat;
+
+/// The parsed command line of `vid`.
+#[derive(Debug)]
+pub struct Cli {
+    pub color: Color,
+    pub error_format: ErrorFormat,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    /// `vid lex [--format=pretty|json|sexp] <file>`
+    Lex { file: String, format: TokenFormat },
+    /// `vid parse <file>`
+    Parse { file: String },
+    /// `vid check <file>`
+    Check { file: String },
+    /// `vid run [--vm] <file>`
+    Run { file: String, vm: bool },
+    /// `vid disasm <file>`
+    Disasm { file: String },
+    /// `vid build [-o <out>] <file>`, the output defaulting to the file with a `.vyc` extension
+    Build {
+        file: String,
+        output: Option<String>,
+    },
+    /// `vid fmt [--check] <files>...`
+    Fmt { files: Vec<String>, check: bool },
+    /// `vid help [command]`, `vid --help` or `vid <command> --help`
+    Help { command: Option<String> },
+    /// `vid --version`
+    Version,
+    /// `vid` without a command, printing the help as a usage error
+    Usage,
+}
+
+/// A command line `vid` does not understand.
+#[derive(Debug)]
+pub struct UsageError(pub String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+const COMMANDS: [&str; 8] = [
+    "lex", "parse", "check", "run", "disasm", "build", "fmt", "help",
+];
+
+/// Parses the arguments following the program name.
+///
+/// Options can come before or after the command, as `--name=value` or `--name value`.
+/// Everything after `--` is taken as a path.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, UsageError> {
+    let mut args = args.into_iter();
+    let mut color = Color::Auto;
+    let mut error_format = ErrorFormat::Human;
+    let mut help = false;
+    let mut version = false;
+    let mut check = false;
+    let mut vm = false;
+    let mut output = None;
+    let mut token_format = TokenFormat::Pretty;
+    let mut command: Option<String> = None;
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            paths.extend(args.by_ref());
+            break;
+        }
+        if arg == "-" || !arg.starts_with('-') {
+            match command {
+                None => command = Some(arg),
+                Some(_) => paths.push(arg),
+            }
+            continue;
+        }
+
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        match name {
+            "--color" => color = color_value(&option_value(name, value, &mut args)?)?,
+            "--error-format" => {
+                error_format = error_format_value(&option_value(name, value, &mut args)?)?
+            }
+            "-h" | "--help" => help = true,
+            "-V" | "--version" => version = true,
+            "--check" if command.as_deref() == Some("fmt") => check = true,
+            "--vm" if command.as_deref() == Some("run") => vm = true,
+            "-o" | "--output" if command.as_deref() == Some("build") => {
+                output = Some(option_value(name, value, &mut args)?)
+            }
+            "--format" if command.as_deref() == Some("lex") => {
+                token_format = token_format_value(&option_value(name, value, &mut args)?)?
+            }
+            _ => return Err(UsageError(format!("unknown option `{}`", arg))),
+        }
+    }
+
+    let command = if help {
+        Command::Help { command }
+    } else if version {
+        Command::Version
+    } else {
+        match command.as_deref() {
+            None => Command::Usage,
+            Some("lex") => Command::Lex {
+                file: single_path("lex", paths)?,
+                format: token_format,
+            },
+            Some("parse") => Command::Parse {
+                file: single_path("parse", paths)?,
+            },
+            Some("check") => Command::Check {
+                file: single_path("check", paths)?,
+            },
+            Some("run") => Command::Run {
+                file: single_path("run", paths)?,
+                vm,
+            },
+            Some("disasm") => Command::Disasm {
+                file: single_path("disasm", paths)?,
+            },
+            Some("build") => {
+                let file = single_path("build", paths)?;
+                if file == "-" && output.is_none() {
+                    return Err(UsageError(String::from(
+                        "`vid build -` expects an output file, as `-o out.vyc`",
+                    )));
+                }
+                Command::Build { file, output }
+            }
+            Some("fmt") => {
+                if paths.is_empty() {
+                    return Err(UsageError(String::from(
+                        "`vid fmt` expects at least one file",
+                    )));
+                }
+                Command::Fmt {
+                    files: paths,
+                    check,
+                }
+            }
+            Some("help") => Command::Help {
+                command: paths.into_iter().next(),
+            },
+            Some(other) if other.ends_with(".vy") || other == "-" => {
+                return Err(UsageError(format!(
+                    "unknown command `{}`, did you mean `vid run {}`?",
+                    other, other
+                )))
+            }
+            Some(other) => return Err(UsageError(format!("unknown command `{}`", other))),
+        }
+    };
+
+    Ok(Cli {
+        color,
+        error_format,
+        command,
+    })
+}
+
+/// The value of the option `name`, given after a `=` or as the next argument.
+fn option_value(
+    name: &str,
+    value: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, UsageError> {
+    value
+        .or_else(|| args.next())
+        .ok_or_else(|| UsageError(format!("`{}` expects a value", name)))
+}
+
+fn color_value(value: &str) -> Result<Color, UsageError> {
+    match value {
+        "auto" => Ok(Color::Auto),
+        "always" => Ok(Color::Always),
+        "never" => Ok(Color::Never),
+        _ => Err(UsageError(format!(
+            "invalid value `{}` for `--color`, expected `auto`, `always` or `never`",
+            value
+        ))),
+    }
+}
+
+fn error_format_value(value: &str) -> Result<ErrorFormat, UsageError> {
+    match value {
+        "human" => Ok(ErrorFormat::Human),
+        "short" => Ok(ErrorFormat::Short),
+        "json" => Ok(ErrorFormat::Json),
+        _ => Err(UsageError(format!(
+            "invalid value `{}` for `--error-format`, expected `human`, `short` or `json`",
+            value
+        ))),
+    }
+}
+
+fn token_format_value(value: &str) -> Result<TokenFormat, UsageError> {
+    match value {
+        "pretty" => Ok(TokenFormat::Pretty),
+        "json" => Ok(TokenFormat::Json),
+        "sexp" => Ok(TokenFormat::Sexp),
+        _ => Err(UsageError(format!(
+            "invalid value `{}` for `--format`, expected `pretty`, `json` or `sexp`",
+            value
+        ))),
+    }
+}
+
+/// The one path `command` takes.
+fn single_path(command: &str, paths: Vec<String>) -> Result<String, UsageError> {
+    let mut paths = paths.into_iter();
+    match (paths.next(), paths.next()) {
+        (Some(path), None) => Ok(path),
+        (None, _) => Err(UsageError(format!("`vid {}` expects a file", command))),
+        (Some(_), Some(extra)) => Err(UsageError(format!(
+            "unexpected argument `{}`, `vid {}` takes a single file",
+            extra, command
+        ))),
+    }
+}
+
+/// The help of `command`, or the general usage when it is `None` or not a command.
+pub fn help(command: Option<&str>) -> String {
+    let usage = match command.filter(|command| COMMANDS.contains(command)) {
+        Some("lex") => {
+            "Print the tokens of a file.\n\n\
+             Usage: vid lex [OPTIONS] <FILE>\n\n\
+             Options:\n  \
+             --format <FORMAT>  pretty, json or sexp [default: pretty]\n\n\
+             Every token is printed with its type, lexeme and span. In the span, `start` and `end`\n\
+             are byte offsets, `end` being exclusive, `line` and `column` are 1-based.\n\n\
+             json: a JSON array with a token per line\n  \
+             {\"type\":\"Identifier\",\"lexeme\":\"x\",\"span\":{\"start\":4,\"end\":5,\"line\":1,\"column\":5}}\n\
+             sexp: a token per line\n  \
+             (token Identifier \"x\" (span 4 5 1 5))\n"
+        }
+        Some("parse") => "Print the syntax tree of a file.\n\nUsage: vid parse [OPTIONS] <FILE>\n",
+        Some("check") => {
+            "Report the errors in a program without running it.\n\nUsage: vid check [OPTIONS] <FILE>\n"
+        }
+        Some("run") => {
+            "Run a program.\n\n\
+             Usage: vid run [OPTIONS] <FILE>\n\n\
+             Options:\n  \
+             --vm  Compile the program to bytecode and run it on the virtual machine\n\n\
+             A <FILE> ending in `.vyc` is bytecode written by `vid build`, run on the virtual\n\
+             machine.\n"
+        }
+        Some("disasm") => {
+            "Print the bytecode a program compiles to, as run by `vid run --vm`.\n\n\
+             Usage: vid disasm [OPTIONS] <FILE>\n\n\
+             A <FILE> ending in `.vyc` is bytecode written by `vid build`.\n"
+        }
+        Some("build") => {
+            "Compile a program to a bytecode file, run with `vid run <FILE>.vyc`.\n\n\
+             Usage: vid build [OPTIONS] <FILE>\n\n\
+             Options:\n  \
+             -o, --output <OUT>  Where to write the bytecode, `-` for stdout\n                      \
+             [default: <FILE> with the extension `.vyc`]\n"
+        }
+        Some("fmt") => {
+            "Format files in place in the canonical style.\n\n\
+             Usage: vid fmt [OPTIONS] <FILES>...\n\n\
+             Options:\n  \
+             --check  Write nothing, exit with 1 if any file is not formatted\n\n\
+             A <FILE> of `-` formats stdin to stdout.\n"
+        }
+        Some("help") => "Print the help of vid or of a command.\n\nUsage: vid help [COMMAND]\n",
+        _ => {
+            return format!(
+                "vid {}, the Vidyut language toolchain\n\n\
+                 Usage: vid [OPTIONS] <COMMAND> [ARGS]\n\n\
+                 Commands:\n  \
+                 lex <FILE>        Print the tokens of a file\n  \
+                 parse <FILE>      Print the syntax tree of a file\n  \
+                 check <FILE>      Report the errors in a program without running it\n  \
+                 run <FILE>        Run a program\n  \
+                 disasm <FILE>     Print the bytecode of a program\n  \
+                 build <FILE>      Compile a program to a `.vyc` bytecode file\n  \
+                 fmt <FILES>...    Format files in place\n  \
+                 help [COMMAND]    Print this message or the help of a command\n\n\
+                 {}\n\
+                 A <FILE> of `-` reads the program from stdin.\n\n\
+                 Exit status:\n  \
+                 0  success\n  \
+                 1  the program has errors, or `fmt --check` found unformatted files\n  \
+                 2  invalid command line\n  \
+                 3  a file could not be read or written\n",
+                env!("CARGO_PKG_VERSION"),
+                OPTIONS
+            )
+        }
+    };
+    format!("{}\n{}", usage, OPTIONS)
+}
+
+const OPTIONS: &str = "Global options:\n  \
+    --color <WHEN>          Color the output: auto, always or never [default: auto]\n  \
+    --error-format <FMT>    How errors are reported: human, short or json [default: human]\n  \
+    -h, --help              Print help\n  \
+    -V, --version           Print version\n";
@@ -1,12 +1,120 @@
+use crate::lex::Span;
 
-use crate::lex::Token;
+mod render;
 
-trait Error {
-    fn error(&self, message: &str);
+pub use render::{Emitter, ErrorFormat};
+
+/// How serious a `Diagnostic` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Help,
+    Note,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Severity::Help => write!(f, "help"),
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A piece of source pointed at by a `Diagnostic`.
+///
+/// The primary label is underlined with `^^^` and marks where the problem is, secondary
+/// labels are underlined with `---` and give context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+impl Label {
+    pub fn primary(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            primary: true,
+        }
+    }
+
+    pub fn secondary(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            primary: false,
+        }
+    }
+}
+
+/// A problem found in the source by any phase of the compiler.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the kind of problem, such as `E0001`.
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    /// `note` and `help` lines printed after the source snippet.
+    pub notes: Vec<(Severity, String)>,
 }
 
-impl Error for Token {
-    fn error(&self, message: &str) {
-        println!("Error: {} at line {}", message, self.line);
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push((Severity::Note, note.into()));
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.notes.push((Severity::Help, help.into()));
+        self
+    }
+
+    /// The label the diagnostic is reported at, the first primary one.
+    pub fn primary_label(&self) -> Option<&Label> {
+        self.labels.iter().find(|label| label.primary)
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
     }
-}
\ No newline at end of file
+}
+
+/// Implemented by the errors of every phase, so they are reported the same way.
+pub trait ToDiagnostic {
+    fn to_diagnostic(&self) -> Diagnostic;
+}
@@ -0,0 +1,267 @@
+use super::{Diagnostic, Label, Severity};
+use crate::json;
+use crate::lex::{SourceMap, Span};
+use colored::{ColoredString, Colorize};
+
+/// How an `Emitter` writes diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// The rustc style snippet, for people.
+    #[default]
+    Human,
+    /// A single `file:line:column: error[E0001]: message` line, for editors.
+    Short,
+    /// A JSON object per line, for tools.
+    Json,
+}
+
+/// Renders `Diagnostic`s in the style of rustc, with the offending source lines underlined.
+///
+/// ```text
+/// error[E0001]: unexpected character `$`
+///  --> main.vy:3:11
+///   |
+/// 3 |  let c <- $;
+///   |           ^ unexpected character
+/// ```
+///
+/// The `Short` and `Json` formats are available through `with_format`.
+#[derive(Debug)]
+pub struct Emitter<'a> {
+    name: &'a str,
+    source: &'a SourceMap,
+    format: ErrorFormat,
+    /// Whether the source lines are shown, `false` when the source is not at hand.
+    snippets: bool,
+}
+
+impl<'a> Emitter<'a> {
+    /// `name` is the file name shown next to the `-->`.
+    pub fn new(name: &'a str, source: &'a SourceMap) -> Self {
+        Self {
+            name,
+            source,
+            format: ErrorFormat::Human,
+            snippets: true,
+        }
+    }
+
+    pub fn with_format(mut self, format: ErrorFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// For a program whose source is not at hand, as one loaded from a `.vyc` file: the
+    /// locations come from the line and column stored in the spans and no source is shown.
+    pub fn without_source(mut self) -> Self {
+        self.snippets = false;
+        self
+    }
+
+    /// The 1-based line and column `span` starts at.
+    fn location(&self, span: Span) -> (usize, usize) {
+        if self.snippets {
+            self.source.location(span.start)
+        } else {
+            (span.line.max(1), span.column.max(1))
+        }
+    }
+
+    /// Prints the rendered `diagnostic` to stderr.
+    pub fn emit(&self, diagnostic: &Diagnostic) {
+        eprint!("{}", self.render(diagnostic));
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        match self.format {
+            ErrorFormat::Human => self.render_human(diagnostic),
+            ErrorFormat::Short => self.render_short(diagnostic),
+            ErrorFormat::Json => self.render_json(diagnostic),
+        }
+    }
+
+    /// `main.vy:3:11: error[E0001]: unexpected character `$``
+    fn render_short(&self, diagnostic: &Diagnostic) -> String {
+        let (line, column) = diagnostic
+            .primary_label()
+            .or(diagnostic.labels.first())
+            .map_or((1, 1), |label| self.location(label.span));
+        let header = match diagnostic.code {
+            Some(code) => format!("{}[{}]", diagnostic.severity, code),
+            None => diagnostic.severity.to_string(),
+        };
+        format!(
+            "{}:{}:{}: {}: {}\n",
+            self.name, line, column, header, diagnostic.message
+        )
+    }
+
+    /// A single line JSON object with the fields of the `Diagnostic`, each label located by
+    /// its byte offsets and its 1-based line and column.
+    fn render_json(&self, diagnostic: &Diagnostic) -> String {
+        let labels = diagnostic
+            .labels
+            .iter()
+            .map(|label| {
+                let (line, column) = self.location(label.span);
+                format!(
+                    "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"message\":{},\"primary\":{}}}",
+                    label.span.start,
+                    label.span.end,
+                    line,
+                    column,
+                    json::string(&label.message),
+                    label.primary
+                )
+            })
+            .collect::<Vec<_>>();
+        let notes = diagnostic
+            .notes
+            .iter()
+            .map(|(severity, note)| {
+                format!(
+                    "{{\"severity\":{},\"message\":{}}}",
+                    json::string(&severity.to_string()),
+                    json::string(note)
+                )
+            })
+            .collect::<Vec<_>>();
+        let code = diagnostic.code.map_or(String::from("null"), json::string);
+
+        format!(
+            "{{\"file\":{},\"severity\":{},\"code\":{},\"message\":{},\"labels\":[{}],\"notes\":[{}]}}\n",
+            json::string(self.name),
+            json::string(&diagnostic.severity.to_string()),
+            code,
+            json::string(&diagnostic.message),
+            labels.join(","),
+            notes.join(",")
+        )
+    }
+
+    fn render_human(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+
+        let header = match diagnostic.code {
+            Some(code) => format!("{}[{}]", diagnostic.severity, code),
+            None => diagnostic.severity.to_string(),
+        };
+        out.push_str(&format!(
+            "{}{} {}\n",
+            paint(diagnostic.severity, &header),
+            ":".bold(),
+            diagnostic.message.bold()
+        ));
+
+        let mut labels = diagnostic.labels.iter().collect::<Vec<_>>();
+        labels.sort_by_key(|label| label.span.start);
+        let lines = labels
+            .iter()
+            .map(|label| self.location(label.span).0)
+            .collect::<Vec<_>>();
+        let width = lines.iter().max().map_or(1, |line| line.to_string().len());
+        let pad = " ".repeat(width);
+        let gutter = format!("{} |", pad).blue().bold();
+
+        let location = diagnostic
+            .primary_label()
+            .or(labels.first().copied())
+            .map(|label| self.location(label.span));
+        if let Some((line, column)) = location {
+            out.push_str(&format!(
+                "{}{} {}:{}:{}\n",
+                pad,
+                "-->".blue().bold(),
+                self.name,
+                line,
+                column
+            ));
+        }
+
+        if self.snippets && !labels.is_empty() {
+            out.push_str(&format!("{}\n", gutter));
+            let mut previous: Option<usize> = None;
+            let mut i = 0;
+            while i < labels.len() {
+                let line = lines[i];
+                if previous.is_some_and(|previous| line > previous + 1) {
+                    out.push_str(&format!("{}\n", "...".blue().bold()));
+                }
+                let text = self.source.line_text(line);
+                out.push_str(&format!(
+                    "{} {}\n",
+                    format!("{:>width$} |", line, width = width).blue().bold(),
+                    expand_tabs(text)
+                ));
+                while i < labels.len() && lines[i] == line {
+                    out.push_str(&format!(
+                        "{} {}\n",
+                        gutter,
+                        self.underline(labels[i], text, diagnostic.severity)
+                    ));
+                    i += 1;
+                }
+                previous = Some(line);
+            }
+        }
+
+        if self.snippets && !diagnostic.notes.is_empty() && !labels.is_empty() {
+            out.push_str(&format!("{}\n", gutter));
+        }
+        for (severity, note) in diagnostic.notes.iter() {
+            out.push_str(&format!(
+                "{} {} {}{} {}\n",
+                pad,
+                "=".blue().bold(),
+                severity.to_string().bold(),
+                ":".bold(),
+                note
+            ));
+        }
+        out.push('\n');
+
+        out
+    }
+
+    /// Builds the `^^^ message` row under `text`, the line `label` starts on.
+    fn underline(&self, label: &Label, text: &str, severity: Severity) -> ColoredString {
+        let (_, column) = self.source.location(label.span.start);
+        let before = text.chars().take(column - 1).collect::<String>();
+        let covered = self
+            .source
+            .slice(label.span)
+            .lines()
+            .next()
+            .unwrap_or_default();
+        let length = expand_tabs(covered).chars().count().max(1);
+        let marker = if label.primary { "^" } else { "-" };
+
+        let mut row = " ".repeat(expand_tabs(&before).chars().count());
+        row.push_str(&marker.repeat(length));
+        if !label.message.is_empty() {
+            row.push(' ');
+            row.push_str(&label.message);
+        }
+
+        if label.primary {
+            paint(severity, &row)
+        } else {
+            row.blue().bold()
+        }
+    }
+}
+
+/// Colors `text` the way rustc colors the given `severity`.
+fn paint(severity: Severity, text: &str) -> ColoredString {
+    match severity {
+        Severity::Error => text.red().bold(),
+        Severity::Warning => text.yellow().bold(),
+        Severity::Note => text.green().bold(),
+        Severity::Help => text.cyan().bold(),
+    }
+}
+
+/// Tabs are shown as 4 spaces, so the underline lines up with the source above it.
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', "    ")
+}
@@ -0,0 +1,811 @@
+pub(crate) mod value;
+
+pub use value::Value;
+
+use crate::error::{Diagnostic, Label, ToDiagnostic};
+use crate::lex::Span;
+use crate::syn::ast::*;
+use std::collections::HashMap;
+use std::io::Write;
+use value::{int_range, is_float};
+
+/// Calls nested deeper than this are reported as a stack overflow instead of overflowing the
+/// stack of the interpreter itself.
+pub(crate) const MAX_CALL_DEPTH: usize = 10_000;
+
+/// The stack the `Interpreter` needs for `MAX_CALL_DEPTH` nested calls, unoptimized builds
+/// taking up to 16KiB per call. Run it on a thread with a stack at least this large.
+pub const STACK_SIZE: usize = 256 << 20;
+
+/// Runs a program by walking its syntax tree, starting at `MainFn`.
+///
+/// Values carry their type and every operation checks it, so that a program runs safely even
+/// when `sem::check_types` was not run on it first. Integer literals without a suffix take the
+/// type expected where they are used, as in `let x: u8 <- 1` or `x + 1`, and are `i32`
+/// otherwise. Float literals default to `f64`.
+///
+/// `print(value)` and `println(value)` write to `out`.
+#[derive(Debug)]
+pub struct Interpreter<'a, W: Write> {
+    main: &'a MainFn,
+    functions: HashMap<&'a str, &'a FnDecl>,
+    /// The variables in scope, innermost last. A name declared again shadows the earlier one.
+    variables: Vec<(String, Variable)>,
+    /// Where the variables of the function being run start in `variables`.
+    frame: usize,
+    /// Return type of the function being run.
+    ret: Type,
+    depth: usize,
+    out: W,
+}
+
+#[derive(Debug)]
+struct Variable {
+    mutable: bool,
+    /// `None` until a `let` without a value is assigned.
+    value: Option<Value>,
+    /// The declared type, or the type of the first value.
+    ty: Option<Type>,
+}
+
+/// How a statement ended.
+enum Flow {
+    Normal,
+    Break(Span),
+    Continue(Span),
+    Return(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    /// A value of the wrong type, such as an `i64` passed for an `i32` parameter.
+    MismatchedTypes {
+        expected: Type,
+        found: Type,
+    },
+    /// A binary operator applied to types it does not take, such as `true + 1`.
+    InvalidOperands {
+        op: BinaryOp,
+        lhs: Type,
+        rhs: Type,
+    },
+    /// A unary operator applied to a type it does not take, such as `-` on a `u8`.
+    InvalidOperand {
+        op: UnaryOp,
+        ty: Type,
+    },
+    /// An integer operation whose result does not fit in its type.
+    Overflow {
+        op: String,
+        ty: Type,
+    },
+    /// `/` or `%` by an integer `0`.
+    DivisionByZero,
+    /// `**` with a negative integer exponent.
+    NegativeExponent,
+    /// A literal that does not fit in the type expected, such as `300` for a `u8`.
+    LiteralOutOfRange {
+        literal: String,
+        ty: Type,
+    },
+    UndefinedVariable(String),
+    UndefinedFunction(String),
+    /// A variable read before anything was assigned to it.
+    Uninitialized(String),
+    /// `<-` to a variable declared without `mut` that already has a value.
+    AssignToImmutable(String),
+    WrongArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// The end of a function reached without a `return`, when it is not `void`.
+    MissingReturn {
+        name: String,
+        ty: Type,
+    },
+    /// `break` or `continue` outside of a `while`.
+    OutsideLoop(&'static str),
+    /// More than `MAX_CALL_DEPTH` nested calls.
+    StackOverflow,
+    /// Writing the output of `print` failed.
+    Output(std::io::ErrorKind),
+    /// Part of the language the interpreter does not run yet.
+    Unsupported(&'static str),
+    /// More constants, locals or functions, or a longer function, than the bytecode can address.
+    TooLarge(&'static str),
+}
+
+impl std::fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RuntimeErrorKind::MismatchedTypes { expected, found } => {
+                write!(
+                    f,
+                    "mismatched types: expected `{}`, found `{}`",
+                    expected, found
+                )
+            }
+            RuntimeErrorKind::InvalidOperands { op, lhs, rhs } => {
+                write!(f, "cannot apply `{}` to `{}` and `{}`", op, lhs, rhs)
+            }
+            RuntimeErrorKind::InvalidOperand { op, ty } => {
+                write!(f, "cannot apply `{}` to `{}`", op, ty)
+            }
+            RuntimeErrorKind::Overflow { op, ty } => {
+                write!(f, "`{}` overflows the range of `{}`", op, ty)
+            }
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::NegativeExponent => write!(f, "negative integer exponent"),
+            RuntimeErrorKind::LiteralOutOfRange { literal, ty } => {
+                write!(f, "literal `{}` is out of range for `{}`", literal, ty)
+            }
+            RuntimeErrorKind::UndefinedVariable(name) => {
+                write!(f, "cannot find variable `{}`", name)
+            }
+            RuntimeErrorKind::UndefinedFunction(name) => {
+                write!(f, "cannot find function `{}`", name)
+            }
+            RuntimeErrorKind::Uninitialized(name) => {
+                write!(f, "variable `{}` is used before being assigned", name)
+            }
+            RuntimeErrorKind::AssignToImmutable(name) => {
+                write!(f, "cannot assign twice to immutable variable `{}`", name)
+            }
+            RuntimeErrorKind::WrongArgumentCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} argument{} but {} {} given",
+                name,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found,
+                if *found == 1 { "was" } else { "were" }
+            ),
+            RuntimeErrorKind::MissingReturn { name, .. } => {
+                write!(f, "`{}` ended without returning a value", name)
+            }
+            RuntimeErrorKind::OutsideLoop(keyword) => {
+                write!(f, "`{}` outside of a loop", keyword)
+            }
+            RuntimeErrorKind::StackOverflow => {
+                write!(
+                    f,
+                    "stack overflow, more than {} nested calls",
+                    MAX_CALL_DEPTH
+                )
+            }
+            RuntimeErrorKind::Output(kind) => write!(f, "could not write the output: {}", kind),
+            RuntimeErrorKind::Unsupported(what) => {
+                write!(f, "{} are not supported by the interpreter yet", what)
+            }
+            RuntimeErrorKind::TooLarge(what) => {
+                write!(f, "too many {} for the bytecode, at most 65536", what)
+            }
+        }
+    }
+}
+
+impl RuntimeErrorKind {
+    /// The error code reported with the `Diagnostic`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::MismatchedTypes { .. } => "E0200",
+            RuntimeErrorKind::InvalidOperands { .. } => "E0201",
+            RuntimeErrorKind::InvalidOperand { .. } => "E0202",
+            RuntimeErrorKind::Overflow { .. } => "E0203",
+            RuntimeErrorKind::DivisionByZero => "E0204",
+            RuntimeErrorKind::NegativeExponent => "E0205",
+            RuntimeErrorKind::LiteralOutOfRange { .. } => "E0206",
+            RuntimeErrorKind::UndefinedVariable(_) => "E0207",
+            RuntimeErrorKind::UndefinedFunction(_) => "E0208",
+            RuntimeErrorKind::Uninitialized(_) => "E0209",
+            RuntimeErrorKind::AssignToImmutable(_) => "E0210",
+            RuntimeErrorKind::WrongArgumentCount { .. } => "E0211",
+            RuntimeErrorKind::MissingReturn { .. } => "E0212",
+            RuntimeErrorKind::OutsideLoop(_) => "E0213",
+            RuntimeErrorKind::StackOverflow => "E0214",
+            RuntimeErrorKind::Output(_) => "E0215",
+            RuntimeErrorKind::Unsupported(_) => "E0216",
+            RuntimeErrorKind::TooLarge(_) => "E0217",
+        }
+    }
+}
+
+impl ToDiagnostic for RuntimeError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let label = match &self.kind {
+            RuntimeErrorKind::MismatchedTypes { expected, .. } => {
+                format!("expected `{}`", expected)
+            }
+            RuntimeErrorKind::InvalidOperands { .. } | RuntimeErrorKind::InvalidOperand { .. } => {
+                String::from("invalid operand types")
+            }
+            RuntimeErrorKind::Overflow { .. } => String::from("overflows"),
+            RuntimeErrorKind::DivisionByZero => String::from("divided by zero"),
+            RuntimeErrorKind::NegativeExponent => String::from("exponent is negative"),
+            RuntimeErrorKind::LiteralOutOfRange { .. } => String::from("out of range"),
+            RuntimeErrorKind::UndefinedVariable(_) | RuntimeErrorKind::UndefinedFunction(_) => {
+                String::from("not found")
+            }
+            RuntimeErrorKind::Uninitialized(_) => String::from("used before being assigned"),
+            RuntimeErrorKind::AssignToImmutable(_) => String::from("already assigned"),
+            RuntimeErrorKind::WrongArgumentCount { expected, .. } => {
+                format!(
+                    "expected {} argument{}",
+                    expected,
+                    if *expected == 1 { "" } else { "s" }
+                )
+            }
+            RuntimeErrorKind::MissingReturn { ty, .. } => format!("declared to return `{}`", ty),
+            RuntimeErrorKind::OutsideLoop(_) => String::from("not in a `while`"),
+            RuntimeErrorKind::StackOverflow => String::from("called too deep"),
+            RuntimeErrorKind::Output(_) => String::from("while printing this"),
+            RuntimeErrorKind::Unsupported(_) => String::from("not supported yet"),
+            RuntimeErrorKind::TooLarge(_) => String::from("too large"),
+        };
+        let diagnostic = Diagnostic::error(self.kind.to_string())
+            .with_code(self.kind.code())
+            .with_label(Label::primary(self.span, label));
+
+        match &self.kind {
+            RuntimeErrorKind::AssignToImmutable(name) => diagnostic.with_help(format!(
+                "declare it with `let mut {}` to make it mutable",
+                name
+            )),
+            RuntimeErrorKind::Overflow { ty, .. } => match int_range(ty) {
+                Some((min, max)) => {
+                    diagnostic.with_note(format!("`{}` holds values from {} to {}", ty, min, max))
+                }
+                None => diagnostic,
+            },
+            RuntimeErrorKind::LiteralOutOfRange { ty, .. } => match int_range(ty) {
+                Some((min, max)) => {
+                    diagnostic.with_note(format!("`{}` holds values from {} to {}", ty, min, max))
+                }
+                None => diagnostic,
+            },
+            _ => diagnostic,
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.span.line)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+type EvalResult<T> = Result<T, RuntimeError>;
+
+impl<'a, W: Write> Interpreter<'a, W> {
+    pub fn new(program: &'a ProgramFile, out: W) -> Self {
+        let mut functions = HashMap::new();
+        for decl in program.pre_main.iter().chain(&program.post_main) {
+            if let Decl::Fn(function) = decl {
+                functions.entry(function.name.as_str()).or_insert(function);
+            }
+        }
+
+        Self {
+            main: &program.main,
+            functions,
+            variables: Vec::new(),
+            frame: 0,
+            ret: Type::Void,
+            depth: 0,
+            out,
+        }
+    }
+
+    /// Runs `MainFn`, then flushes the output.
+    pub fn run(&mut self) -> EvalResult<()> {
+        let result = self.run_main();
+        let flushed = self.out.flush().map_err(|error| RuntimeError {
+            kind: RuntimeErrorKind::Output(error.kind()),
+            span: self.main.span,
+        });
+        result.and(flushed)
+    }
+
+    fn run_main(&mut self) -> EvalResult<()> {
+        self.depth = 1;
+        match self.block(&self.main.body)? {
+            Flow::Normal | Flow::Return(_) => Ok(()),
+            Flow::Break(span) => Err(error(RuntimeErrorKind::OutsideLoop("break"), span)),
+            Flow::Continue(span) => Err(error(RuntimeErrorKind::OutsideLoop("continue"), span)),
+        }
+    }
+
+    /// Runs the statements of `block` in a new scope.
+    fn block(&mut self, block: &Block) -> EvalResult<Flow> {
+        let scope = self.variables.len();
+        let mut flow = Ok(Flow::Normal);
+        for stmt in &block.stmts {
+            flow = self.stmt(stmt);
+            if !matches!(flow, Ok(Flow::Normal)) {
+                break;
+            }
+        }
+        self.variables.truncate(scope);
+        flow
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> EvalResult<Flow> {
+        match &stmt.kind {
+            StmtKind::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => {
+                let value = match (value, ty) {
+                    (Some(value), Some(ty)) => Some(self.expect(value, ty)?),
+                    (Some(value), None) => Some(self.expr(value)?),
+                    (None, _) => None,
+                };
+                let variable = Variable {
+                    mutable: *mutable,
+                    ty: ty.clone().or_else(|| value.as_ref().map(Value::ty)),
+                    value,
+                };
+                self.variables.push((name.clone(), variable));
+            }
+            StmtKind::Assign { target, op, value } => self.assign(target, *op, value)?,
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                if self.condition(cond)? {
+                    return self.block(then);
+                }
+                return match otherwise {
+                    Some(Else::If(stmt)) => self.stmt(stmt),
+                    Some(Else::Block(block)) => self.block(block),
+                    None => Ok(Flow::Normal),
+                };
+            }
+            StmtKind::Match { scrutinee, arms } => {
+                let value = self.expr(scrutinee)?;
+                for arm in arms {
+                    let matched = match &arm.pattern {
+                        Pattern::Wildcard => true,
+                        Pattern::Expr(pattern) => {
+                            let pattern_value = self.expr_with_hint(pattern, Some(&value.ty()))?;
+                            value::binary(BinaryOp::Eq, value.clone(), pattern_value)
+                                .map_err(|kind| error(kind, pattern.span))?
+                                == Value::Bool(true)
+                        }
+                    };
+                    if matched {
+                        return self.block(&arm.body);
+                    }
+                }
+            }
+            StmtKind::While { cond, body } => {
+                while self.condition(cond)? {
+                    match self.block(body)? {
+                        Flow::Normal | Flow::Continue(_) => {}
+                        Flow::Break(_) => break,
+                        flow @ Flow::Return(_) => return Ok(flow),
+                    }
+                }
+            }
+            StmtKind::Return(value) => {
+                let ret = self.ret.clone();
+                let value = match value {
+                    Some(value) => self.expect(value, &ret)?,
+                    None if ret == Type::Void => Value::Void,
+                    None => {
+                        return Err(error(
+                            RuntimeErrorKind::MismatchedTypes {
+                                expected: ret,
+                                found: Type::Void,
+                            },
+                            stmt.span,
+                        ))
+                    }
+                };
+                return Ok(Flow::Return(value));
+            }
+            StmtKind::Break => return Ok(Flow::Break(stmt.span)),
+            StmtKind::Continue => return Ok(Flow::Continue(stmt.span)),
+            StmtKind::Expr(expr) => {
+                self.expr(expr)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// `target <- value` or `target op= value`.
+    fn assign(&mut self, target: &Expr, op: Option<BinaryOp>, value: &Expr) -> EvalResult<()> {
+        let name = match &target.kind {
+            ExprKind::Variable(name) => name,
+            ExprKind::Group(inner) => return self.assign(inner, op, value),
+            _ => {
+                return Err(error(
+                    RuntimeErrorKind::Unsupported("assignments to fields and elements"),
+                    target.span,
+                ))
+            }
+        };
+        let (ty, current) = {
+            let variable = self.variable(name, target.span)?;
+            if !variable.mutable && variable.value.is_some() {
+                return Err(error(
+                    RuntimeErrorKind::AssignToImmutable(name.clone()),
+                    target.span,
+                ));
+            }
+            (variable.ty.clone(), variable.value.clone())
+        };
+
+        let new = match op {
+            Some(op) => {
+                let current = current.ok_or_else(|| {
+                    error(RuntimeErrorKind::Uninitialized(name.clone()), target.span)
+                })?;
+                let rhs = self.expr_with_hint(value, Some(&current.ty()))?;
+                let span = target.span.to(value.span);
+                let new = value::binary(op, current, rhs).map_err(|kind| error(kind, span))?;
+                check_type(new, ty.as_ref(), span)?
+            }
+            None => match &ty {
+                Some(ty) => self.expect(value, ty)?,
+                None => self.expr(value)?,
+            },
+        };
+
+        if let Some(variable) = self.variable_mut(name) {
+            variable.ty = Some(new.ty());
+            variable.value = Some(new);
+        }
+        Ok(())
+    }
+
+    /// Evaluates the condition of an `if` or a `while`.
+    fn condition(&mut self, cond: &Expr) -> EvalResult<bool> {
+        match self.expect(cond, &Type::Bool)? {
+            Value::Bool(value) => Ok(value),
+            _ => unreachable!("`expect` checked the type"),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> EvalResult<Value> {
+        self.expr_with_hint(expr, None)
+    }
+
+    /// Evaluates `expr`, which must be a `ty`.
+    fn expect(&mut self, expr: &Expr, ty: &Type) -> EvalResult<Value> {
+        let value = self.expr_with_hint(expr, Some(ty))?;
+        check_type(value, Some(ty), expr.span)
+    }
+
+    /// Evaluates `expr`, giving the number literals without suffix the type `hint` when it is
+    /// a number type.
+    fn expr_with_hint(&mut self, expr: &Expr, hint: Option<&Type>) -> EvalResult<Value> {
+        if let Some(value) = number_literal(expr, hint)? {
+            return Ok(value);
+        }
+
+        match &expr.kind {
+            ExprKind::Literal(literal) => match literal {
+                Literal::Str(value) => Ok(Value::Str(value.clone())),
+                Literal::Char(value) => Ok(Value::Char(*value)),
+                Literal::Byte(value) => Ok(Value::Int {
+                    value: *value as i128,
+                    ty: Type::Byte,
+                }),
+                Literal::Bool(value) => Ok(Value::Bool(*value)),
+                Literal::ByteStr(_) => Err(error(
+                    RuntimeErrorKind::Unsupported("byte strings"),
+                    expr.span,
+                )),
+                Literal::Int { .. } | Literal::Float { .. } => {
+                    unreachable!("number literals are evaluated by `number_literal`")
+                }
+            },
+            ExprKind::Variable(name) => {
+                let variable = self.variable(name, expr.span)?;
+                variable
+                    .value
+                    .clone()
+                    .ok_or_else(|| error(RuntimeErrorKind::Uninitialized(name.clone()), expr.span))
+            }
+            ExprKind::Group(inner) => self.expr_with_hint(inner, hint),
+            ExprKind::Unary { op, expr: operand } => {
+                let value = self.expr_with_hint(operand, hint)?;
+                value::unary(*op, value).map_err(|kind| error(kind, expr.span))
+            }
+            ExprKind::Binary { op, lhs, rhs } => self.binary(*op, lhs, rhs, hint, expr.span),
+            ExprKind::Call { callee, args } => self.call(callee, args, expr.span),
+            ExprKind::Interpolated(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        InterpolatedPart::Str { value, .. } => text.push_str(value),
+                        InterpolatedPart::Expr(expr) => {
+                            text.push_str(&self.expr(expr)?.to_string())
+                        }
+                    }
+                }
+                Ok(Value::Str(text))
+            }
+            ExprKind::Array(_) | ExprKind::Index { .. } => {
+                Err(error(RuntimeErrorKind::Unsupported("arrays"), expr.span))
+            }
+            ExprKind::Field { .. } => Err(error(
+                RuntimeErrorKind::Unsupported("structs and enums"),
+                expr.span,
+            )),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOp,
+        lhs: &Expr,
+        rhs: &Expr,
+        hint: Option<&Type>,
+        span: Span,
+    ) -> EvalResult<Value> {
+        let hint = operand_hint(op, hint);
+        let (lhs, rhs) = if is_untyped_literal(lhs) && !is_untyped_literal(rhs) {
+            // `1 + x` gives the literal the type of `x`, evaluating `x` first is fine as the
+            // literal has no side effects.
+            let rhs = self.expr_with_hint(rhs, hint)?;
+            (self.expr_with_hint(lhs, Some(&rhs.ty()))?, rhs)
+        } else {
+            let lhs = self.expr_with_hint(lhs, hint)?;
+            match (op, &lhs) {
+                (BinaryOp::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+                (BinaryOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                _ => {}
+            }
+            let rhs = self.expr_with_hint(rhs, Some(&lhs.ty()))?;
+            (lhs, rhs)
+        };
+        value::binary(op, lhs, rhs).map_err(|kind| error(kind, span))
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr], span: Span) -> EvalResult<Value> {
+        let name = match &callee.kind {
+            ExprKind::Variable(name) => name.as_str(),
+            ExprKind::Field { .. } => {
+                return Err(error(RuntimeErrorKind::Unsupported("methods"), callee.span))
+            }
+            _ => {
+                return Err(error(
+                    RuntimeErrorKind::Unsupported("calls of expressions"),
+                    callee.span,
+                ))
+            }
+        };
+        let function = match self.functions.get(name) {
+            Some(function) => *function,
+            None if matches!(name, "print" | "println") => {
+                return self.print(name, args, span);
+            }
+            None => {
+                return Err(error(
+                    RuntimeErrorKind::UndefinedFunction(name.to_string()),
+                    callee.span,
+                ))
+            }
+        };
+        if args.len() != function.params.len() {
+            return Err(error(
+                RuntimeErrorKind::WrongArgumentCount {
+                    name: name.to_string(),
+                    expected: function.params.len(),
+                    found: args.len(),
+                },
+                span,
+            ));
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(error(RuntimeErrorKind::StackOverflow, span));
+        }
+
+        let mut params = Vec::with_capacity(args.len());
+        for (param, arg) in function.params.iter().zip(args) {
+            let value = self.expect(arg, &param.ty)?;
+            let variable = Variable {
+                mutable: false,
+                value: Some(value),
+                ty: Some(param.ty.clone()),
+            };
+            params.push((param.name.clone(), variable));
+        }
+
+        // The caller's variables stay below `frame`, out of reach of the callee.
+        let frame = std::mem::replace(&mut self.frame, self.variables.len());
+        let ret = std::mem::replace(&mut self.ret, function.ret.clone());
+        self.variables.extend(params);
+        self.depth += 1;
+        let flow = self.block(&function.body);
+        self.depth -= 1;
+        self.variables.truncate(self.frame);
+        self.frame = frame;
+        self.ret = ret;
+
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            Flow::Normal if function.ret == Type::Void => Ok(Value::Void),
+            Flow::Normal => Err(error(
+                RuntimeErrorKind::MissingReturn {
+                    name: name.to_string(),
+                    ty: function.ret.clone(),
+                },
+                function.span,
+            )),
+            Flow::Break(span) => Err(error(RuntimeErrorKind::OutsideLoop("break"), span)),
+            Flow::Continue(span) => Err(error(RuntimeErrorKind::OutsideLoop("continue"), span)),
+        }
+    }
+
+    /// The built-in `print(value)` and `println(value)`, `println()` printing an empty line.
+    fn print(&mut self, name: &str, args: &[Expr], span: Span) -> EvalResult<Value> {
+        let newline = name == "println";
+        let value = match args {
+            [] if newline => None,
+            [arg] => Some(self.expr(arg)?),
+            _ => {
+                return Err(error(
+                    RuntimeErrorKind::WrongArgumentCount {
+                        name: name.to_string(),
+                        expected: 1,
+                        found: args.len(),
+                    },
+                    span,
+                ))
+            }
+        };
+
+        let text = value.map(|value| value.to_string()).unwrap_or_default();
+        let written = if newline {
+            writeln!(self.out, "{}", text)
+        } else {
+            write!(self.out, "{}", text)
+        };
+        written.map_err(|e| error(RuntimeErrorKind::Output(e.kind()), span))?;
+        Ok(Value::Void)
+    }
+
+    fn variable(&self, name: &str, span: Span) -> EvalResult<&Variable> {
+        self.variables[self.frame..]
+            .iter()
+            .rev()
+            .find(|(variable, _)| variable == name)
+            .map(|(_, variable)| variable)
+            .ok_or_else(|| error(RuntimeErrorKind::UndefinedVariable(name.to_string()), span))
+    }
+
+    fn variable_mut(&mut self, name: &str) -> Option<&mut Variable> {
+        self.variables[self.frame..]
+            .iter_mut()
+            .rev()
+            .find(|(variable, _)| variable == name)
+            .map(|(_, variable)| variable)
+    }
+}
+
+pub(crate) fn error(kind: RuntimeErrorKind, span: Span) -> RuntimeError {
+    RuntimeError { kind, span }
+}
+
+/// Checks that `value` is a `ty`, when there is one.
+fn check_type(value: Value, ty: Option<&Type>, span: Span) -> EvalResult<Value> {
+    match ty {
+        Some(ty) if value.ty() != *ty => Err(error(
+            RuntimeErrorKind::MismatchedTypes {
+                expected: ty.clone(),
+                found: value.ty(),
+            },
+            span,
+        )),
+        _ => Ok(value),
+    }
+}
+
+/// Checks whether `expr` is a number literal without suffix, possibly negated or in
+/// parentheses, whose type depends on where it is used.
+pub(crate) fn is_untyped_literal(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Literal(Literal::Int { suffix, .. } | Literal::Float { suffix, .. }) => {
+            suffix.is_none()
+        }
+        ExprKind::Group(inner)
+        | ExprKind::Unary {
+            op: UnaryOp::Neg,
+            expr: inner,
+        } => is_untyped_literal(inner),
+        _ => false,
+    }
+}
+
+/// The hint for the operands of `op` from the `hint` for its result: the same for the
+/// operators whose result has the type of their operands, none for the comparisons, `and` and
+/// `or`. `let x: i64 <- 1 + 2` makes both literals `i64`.
+pub(crate) fn operand_hint(op: BinaryOp, hint: Option<&Type>) -> Option<&Type> {
+    match op {
+        BinaryOp::Eq
+        | BinaryOp::Ne
+        | BinaryOp::Lt
+        | BinaryOp::Le
+        | BinaryOp::Gt
+        | BinaryOp::Ge
+        | BinaryOp::And
+        | BinaryOp::Or => None,
+        _ => hint,
+    }
+}
+
+/// The value of a number literal, `-` applied to it being folded in so that `-128i8` fits.
+/// `None` when `expr` is not a number literal.
+pub(crate) fn number_literal(expr: &Expr, hint: Option<&Type>) -> EvalResult<Option<Value>> {
+    let (negated, literal) = match &expr.kind {
+        ExprKind::Literal(literal) => (false, literal),
+        ExprKind::Unary {
+            op: UnaryOp::Neg,
+            expr: inner,
+        } => match &inner.kind {
+            ExprKind::Literal(literal) => (true, literal),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    let value = match literal {
+        Literal::Int { value, suffix } => {
+            let ty = suffix
+                .clone()
+                .or_else(|| hint.filter(|ty| int_range(ty).is_some()).cloned())
+                .unwrap_or(Type::I32);
+            let value = if negated {
+                -(*value as i128)
+            } else {
+                *value as i128
+            };
+            if negated && int_range(&ty).is_some_and(|(min, _)| min == 0) {
+                return Err(error(
+                    RuntimeErrorKind::InvalidOperand {
+                        op: UnaryOp::Neg,
+                        ty,
+                    },
+                    expr.span,
+                ));
+            }
+            Value::int(value, ty.clone()).ok_or_else(|| {
+                error(
+                    RuntimeErrorKind::LiteralOutOfRange {
+                        literal: value.to_string(),
+                        ty,
+                    },
+                    expr.span,
+                )
+            })?
+        }
+        Literal::Float { value, suffix } => {
+            let ty = suffix
+                .clone()
+                .or_else(|| hint.filter(|ty| is_float(ty)).cloned())
+                .unwrap_or(Type::F64);
+            Value::float(if negated { -value } else { *value }, ty)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
@@ -0,0 +1,263 @@
+use super::RuntimeErrorKind;
+use crate::syn::ast::{BinaryOp, Type, UnaryOp};
+
+/// A value computed by the `Interpreter`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Any of the integer types and `byte`, `value` always being in the range of `ty`.
+    Int {
+        value: i128,
+        ty: Type,
+    },
+    /// `f32` or `f64`, an `f32` being kept rounded to `f32` precision.
+    Float {
+        value: f64,
+        ty: Type,
+    },
+    Bool(bool),
+    Char(char),
+    Str(String),
+    /// What a `void` function returns.
+    Void,
+}
+
+impl Value {
+    /// An integer of type `ty`, `None` when `value` does not fit in it.
+    pub fn int(value: i128, ty: Type) -> Option<Value> {
+        let (min, max) = int_range(&ty)?;
+        (min..=max)
+            .contains(&value)
+            .then_some(Value::Int { value, ty })
+    }
+
+    /// A float of type `ty`, rounded for `f32`.
+    pub fn float(value: f64, ty: Type) -> Value {
+        let value = if ty == Type::F32 {
+            value as f32 as f64
+        } else {
+            value
+        };
+        Value::Float { value, ty }
+    }
+
+    pub fn ty(&self) -> Type {
+        match self {
+            Value::Int { ty, .. } | Value::Float { ty, .. } => ty.clone(),
+            Value::Bool(_) => Type::Bool,
+            Value::Char(_) => Type::Char,
+            Value::Str(_) => Type::String,
+            Value::Void => Type::Void,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Int { value, .. } => write!(f, "{}", value),
+            // Whole floats keep a `.0` so they read as floats, `2.0` and not `2`.
+            Value::Float { value, .. } if value.fract() == 0.0 && value.abs() < 1e16 => {
+                write!(f, "{:.1}", value)
+            }
+            Value::Float { value, ty } if *ty == Type::F32 => write!(f, "{}", *value as f32),
+            Value::Float { value, .. } => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Char(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", value),
+            Value::Void => write!(f, "void"),
+        }
+    }
+}
+
+/// The smallest and largest value of an integer type, `None` for the other types.
+pub fn int_range(ty: &Type) -> Option<(i128, i128)> {
+    let range = match ty {
+        Type::I8 => (i8::MIN as i128, i8::MAX as i128),
+        Type::I16 => (i16::MIN as i128, i16::MAX as i128),
+        Type::I32 => (i32::MIN as i128, i32::MAX as i128),
+        Type::I64 => (i64::MIN as i128, i64::MAX as i128),
+        Type::U8 | Type::Byte => (0, u8::MAX as i128),
+        Type::U16 => (0, u16::MAX as i128),
+        Type::U32 => (0, u32::MAX as i128),
+        Type::U64 => (0, u64::MAX as i128),
+        _ => return None,
+    };
+    Some(range)
+}
+
+pub fn is_float(ty: &Type) -> bool {
+    matches!(ty, Type::F32 | Type::F64)
+}
+
+/// Applies `op` to `value`.
+pub fn unary(op: UnaryOp, value: Value) -> Result<Value, RuntimeErrorKind> {
+    let invalid = |value: &Value| RuntimeErrorKind::InvalidOperand { op, ty: value.ty() };
+    match (op, &value) {
+        (UnaryOp::Neg, Value::Int { value: n, ty }) => {
+            let (min, _) = int_range(ty).unwrap_or_default();
+            if min == 0 {
+                return Err(invalid(&value));
+            }
+            Value::int(-n, ty.clone()).ok_or_else(|| overflow("-", ty))
+        }
+        (UnaryOp::Neg, Value::Float { value: n, ty }) => Ok(Value::float(-n, ty.clone())),
+        (UnaryOp::Bang | UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+        (UnaryOp::Bang, Value::Int { value: n, ty }) => {
+            let (min, max) = int_range(ty).unwrap_or_default();
+            let inverted = if min == 0 { max - n } else { !n };
+            Ok(Value::Int {
+                value: inverted,
+                ty: ty.clone(),
+            })
+        }
+        _ => Err(invalid(&value)),
+    }
+}
+
+/// Applies `op` to `lhs` and `rhs`, both already evaluated.
+///
+/// `and` and `or` only get here when both sides are needed, the `Interpreter` short-circuits
+/// them first.
+pub fn binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, RuntimeErrorKind> {
+    let invalid = |lhs: &Value, rhs: &Value| RuntimeErrorKind::InvalidOperands {
+        op,
+        lhs: lhs.ty(),
+        rhs: rhs.ty(),
+    };
+    if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+        return match (&lhs, &rhs) {
+            (Value::Int { value, ty }, Value::Int { value: amount, .. }) => {
+                shift(op, *value, *amount, ty)
+            }
+            _ => Err(invalid(&lhs, &rhs)),
+        };
+    }
+    if lhs.ty() != rhs.ty() {
+        return Err(invalid(&lhs, &rhs));
+    }
+    if let Some(ordering) = compare(op, &lhs, &rhs) {
+        return Ok(Value::Bool(ordering));
+    }
+
+    match (&lhs, &rhs) {
+        (Value::Int { value: a, ty }, Value::Int { value: b, .. }) => {
+            int_arithmetic(op, *a, *b, ty)
+        }
+        (Value::Float { value: a, ty }, Value::Float { value: b, .. }) => {
+            let value = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                BinaryOp::Rem => a % b,
+                BinaryOp::Pow => a.powf(*b),
+                _ => return Err(invalid(&lhs, &rhs)),
+            };
+            Ok(Value::float(value, ty.clone()))
+        }
+        (Value::Bool(a), Value::Bool(b)) => match op {
+            BinaryOp::And | BinaryOp::BitAnd => Ok(Value::Bool(*a && *b)),
+            BinaryOp::Or | BinaryOp::BitOr => Ok(Value::Bool(*a || *b)),
+            BinaryOp::BitXor => Ok(Value::Bool(a ^ b)),
+            _ => Err(invalid(&lhs, &rhs)),
+        },
+        (Value::Str(a), Value::Str(b)) if op == BinaryOp::Add => {
+            Ok(Value::Str(format!("{}{}", a, b)))
+        }
+        _ => Err(invalid(&lhs, &rhs)),
+    }
+}
+
+/// The result of a comparison operator, `None` when `op` is not one.
+fn compare(op: BinaryOp, lhs: &Value, rhs: &Value) -> Option<bool> {
+    use std::cmp::Ordering;
+
+    let ordering = match (lhs, rhs) {
+        (Value::Int { value: a, .. }, Value::Int { value: b, .. }) => a.partial_cmp(b),
+        (Value::Float { value: a, .. }, Value::Float { value: b, .. }) => a.partial_cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+        (Value::Char(a), Value::Char(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => a.partial_cmp(b),
+        (Value::Void, Value::Void) => Some(Ordering::Equal),
+        _ => None,
+    };
+    // `NaN` compares unequal and unordered to everything.
+    let result = match op {
+        BinaryOp::Eq => ordering == Some(Ordering::Equal),
+        BinaryOp::Ne => ordering != Some(Ordering::Equal),
+        BinaryOp::Lt => ordering == Some(Ordering::Less),
+        BinaryOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        BinaryOp::Gt => ordering == Some(Ordering::Greater),
+        BinaryOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn int_arithmetic(op: BinaryOp, a: i128, b: i128, ty: &Type) -> Result<Value, RuntimeErrorKind> {
+    let value = match op {
+        BinaryOp::Add => a.checked_add(b),
+        BinaryOp::Sub => a.checked_sub(b),
+        BinaryOp::Mul => a.checked_mul(b),
+        BinaryOp::Div | BinaryOp::Rem if b == 0 => return Err(RuntimeErrorKind::DivisionByZero),
+        BinaryOp::Div => a.checked_div(b),
+        BinaryOp::Rem => a.checked_rem(b),
+        BinaryOp::Pow => {
+            let exponent = u32::try_from(b).map_err(|_| RuntimeErrorKind::NegativeExponent)?;
+            a.checked_pow(exponent)
+        }
+        // In range values of the same type keep their two's complement bits in an `i128`, so
+        // the result is in range too.
+        BinaryOp::BitAnd => Some(a & b),
+        BinaryOp::BitOr => Some(a | b),
+        BinaryOp::BitXor => Some(a ^ b),
+        _ => {
+            return Err(RuntimeErrorKind::InvalidOperands {
+                op,
+                lhs: ty.clone(),
+                rhs: ty.clone(),
+            })
+        }
+    };
+    value
+        .and_then(|value| Value::int(value, ty.clone()))
+        .ok_or_else(|| overflow(&op.to_string(), ty))
+}
+
+/// `<<` and `>>`, shifting the bits of `value` as a `ty`. The bits shifted out are lost, a
+/// shift by as many bits as `ty` has or more overflows.
+fn shift(op: BinaryOp, value: i128, amount: i128, ty: &Type) -> Result<Value, RuntimeErrorKind> {
+    let (min, max) = int_range(ty).unwrap_or_default();
+    let bits = if min == 0 {
+        128 - max.leading_zeros()
+    } else {
+        129 - max.leading_zeros()
+    };
+    if !(0..bits as i128).contains(&amount) {
+        return Err(overflow(&op.to_string(), ty));
+    }
+
+    let value = if op == BinaryOp::Shr {
+        value >> amount
+    } else {
+        let mask = (1u128 << bits) - 1;
+        let shifted = ((value as u128) << amount) & mask;
+        // Sign extend for signed types.
+        if min < 0 && shifted >> (bits - 1) == 1 {
+            (shifted | !mask) as i128
+        } else {
+            shifted as i128
+        }
+    };
+    Ok(Value::Int {
+        value,
+        ty: ty.clone(),
+    })
+}
+
+fn overflow(op: &str, ty: &Type) -> RuntimeErrorKind {
+    RuntimeErrorKind::Overflow {
+        op: op.to_string(),
+        ty: ty.clone(),
+    }
+}
@@ -0,0 +1,606 @@
+use crate::error::{Diagnostic, ToDiagnostic};
+use crate::lex::{Lexer, SourceMap, Token, TokenStream, TokenType, TriviaKind};
+use crate::syn::ast::*;
+use crate::syn::Parser;
+use std::collections::VecDeque;
+
+/// Re-prints `source` in the canonical style, keeping its comments.
+///
+/// The program is parsed to an AST and printed back from it, the comments, which the AST does
+/// not keep, come from the tokens of a lossless `Lexer` and are put back in front of the code
+/// following them or at the end of the line they were on, and those inside an expression or a
+/// parameter list between the code they were between. Formatting the output again gives it
+/// back unchanged.
+pub fn format_source(source: &str) -> Result<String, Vec<Diagnostic>> {
+    let tokens = Lexer::new(source.to_string())
+        .lossless()
+        .parse()
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(ToDiagnostic::to_diagnostic)
+                .collect::<Vec<_>>()
+        })?;
+    let comments = comments(&tokens);
+    // The comments need every token, the parser lexes the source again as it goes.
+    let program = Parser::new(TokenStream::new(Lexer::new(source.to_string()))).parse()?;
+
+    let mut formatter = Formatter {
+        source: SourceMap::new(source.to_string()),
+        comments,
+        out: String::new(),
+        indent: 0,
+        last_end: 0,
+        fresh: true,
+        after_code: false,
+    };
+    formatter.program(&program);
+    Ok(formatter.out)
+}
+
+/// A comment of the source, `///` and `//!` included.
+#[derive(Debug)]
+struct Comment {
+    offset: usize,
+    text: String,
+    /// Whether there is code before it on its line.
+    trailing: bool,
+}
+
+/// Collects the comments around `tokens` in source order.
+fn comments(tokens: &[Token]) -> VecDeque<Comment> {
+    let mut comments = VecDeque::new();
+    let is_comment = |kind| matches!(kind, TriviaKind::LineComment | TriviaKind::BlockComment);
+
+    for (i, token) in tokens.iter().enumerate() {
+        let Some(lossless) = token.lossless.as_deref() else {
+            continue;
+        };
+
+        let mut offset = token.span.start
+            - lossless
+                .leading
+                .iter()
+                .map(|trivia| trivia.text.len())
+                .sum::<usize>();
+        for trivia in lossless.leading.iter() {
+            if is_comment(trivia.kind) {
+                comments.push_back(Comment {
+                    offset,
+                    text: trivia.text.trim_end().to_string(),
+                    trailing: false,
+                });
+            }
+            offset += trivia.text.len();
+        }
+
+        if token.token_type == TokenType::DocComment {
+            let after_newline = lossless
+                .leading
+                .iter()
+                .any(|trivia| trivia.kind == TriviaKind::Newline);
+            comments.push_back(Comment {
+                offset: token.span.start,
+                text: lossless.text.trim_end().to_string(),
+                trailing: i > 0 && !after_newline,
+            });
+        }
+
+        let mut offset = token.span.end;
+        for trivia in lossless.trailing.iter() {
+            if is_comment(trivia.kind) {
+                comments.push_back(Comment {
+                    offset,
+                    text: trivia.text.trim_end().to_string(),
+                    trailing: true,
+                });
+            }
+            offset += trivia.text.len();
+        }
+    }
+
+    comments
+}
+
+/// Prints the AST one declaration, field, statement or match arm per line, indented by 4
+/// spaces, with the comments of the source woven in.
+#[derive(Debug)]
+struct Formatter {
+    source: SourceMap,
+    /// Comments not printed yet, in source order.
+    comments: VecDeque<Comment>,
+    out: String,
+    indent: usize,
+    /// End of the last code printed. Trailing comments up to its line go at the end of the
+    /// current line.
+    last_end: usize,
+    /// Set at the start of the file and right after a `{`, where no blank line goes.
+    fresh: bool,
+    /// Whether the last line printed is code, which a trailing comment can go at the end of.
+    after_code: bool,
+}
+
+impl Formatter {
+    /// `ProgramFile` => `ImportDeclList` `DeclList` `MainFn` `DeclList`
+    fn program(&mut self, program: &ProgramFile) {
+        for import in program.imports.iter() {
+            self.import_decl(import);
+        }
+        for (i, decl) in program.pre_main.iter().enumerate() {
+            self.decl(decl, i > 0 || !program.imports.is_empty());
+        }
+
+        let main = &program.main;
+        let separate = !program.pre_main.is_empty() || !program.imports.is_empty();
+        self.item(main.span.start, separate);
+        self.start_line();
+        if main.public {
+            self.out.push_str("pub ");
+        }
+        self.out.push_str("void main() ");
+        self.block(&main.body);
+        self.end_line();
+
+        for decl in program.post_main.iter() {
+            self.decl(decl, true);
+        }
+        self.leading_comments(usize::MAX, false);
+    }
+
+    /// `ImportDecl` => import `ModulePath` `ImportTail`;
+    fn import_decl(&mut self, import: &ImportDecl) {
+        self.item(import.span.start, false);
+        self.start_line();
+        self.out
+            .push_str(&format!("import {}", import.path.join(".")));
+        if let Some(items) = &import.items {
+            let items = items
+                .iter()
+                .map(|item| match &item.alias {
+                    Some(alias) => format!("{} as {}", item.name, alias),
+                    None => item.name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.out.push_str(&format!(".{{{}}}", items));
+        }
+        if let Some(alias) = &import.alias {
+            self.out.push_str(&format!(" as {}", alias));
+        }
+        self.out.push(';');
+        self.mark(import.span.end);
+        self.end_line();
+    }
+
+    /// Prints `decl`, after a blank line if `separate` is set.
+    fn decl(&mut self, decl: &Decl, separate: bool) {
+        match decl {
+            Decl::Struct(decl) => {
+                self.item(decl.span.start, separate);
+                self.start_line();
+                if decl.public {
+                    self.out.push_str("pub ");
+                }
+                self.out.push_str(&format!("struct {} ", decl.name));
+                self.mark(decl.span.start);
+                if decl.fields.is_empty() && !self.has_comments_before(decl.span.end) {
+                    self.out.push_str("{}");
+                } else {
+                    self.open();
+                    for field in decl.fields.iter() {
+                        self.item(field.span.start, false);
+                        self.start_line();
+                        if field.public {
+                            self.out.push_str("pub ");
+                        }
+                        self.out.push_str(&format!("{} {},", field.ty, field.name));
+                        self.mark(field.span.end);
+                        self.end_line();
+                    }
+                    self.close(decl.span.end - 1);
+                }
+                self.mark(decl.span.end);
+                self.end_line();
+            }
+            Decl::Enum(decl) => {
+                self.item(decl.span.start, separate);
+                self.start_line();
+                if decl.public {
+                    self.out.push_str("pub ");
+                }
+                self.out.push_str(&format!("enum {} ", decl.name));
+                self.mark(decl.span.start);
+                if decl.variants.is_empty() && !self.has_comments_before(decl.span.end) {
+                    self.out.push_str("{}");
+                } else {
+                    self.open();
+                    for variant in decl.variants.iter() {
+                        self.item(variant.span.start, false);
+                        self.start_line();
+                        self.out.push_str(&format!("{},", variant.name));
+                        self.mark(variant.span.end);
+                        self.end_line();
+                    }
+                    self.close(decl.span.end - 1);
+                }
+                self.mark(decl.span.end);
+                self.end_line();
+            }
+            Decl::Impl(decl) => {
+                self.item(decl.span.start, separate);
+                self.start_line();
+                self.out.push_str(&format!("impl {} ", decl.name));
+                self.mark(decl.span.start);
+                if decl.fns.is_empty() && !self.has_comments_before(decl.span.end) {
+                    self.out.push_str("{}");
+                } else {
+                    self.open();
+                    for (i, decl) in decl.fns.iter().enumerate() {
+                        self.fn_decl(decl, i > 0);
+                    }
+                    self.close(decl.span.end - 1);
+                }
+                self.mark(decl.span.end);
+                self.end_line();
+            }
+            Decl::Fn(decl) => self.fn_decl(decl, separate),
+        }
+    }
+
+    /// `FnDecl` => `AccessDecl` `Type` `ID` (`FnTypeDeclList`){ `CompoundDeclList` }
+    fn fn_decl(&mut self, decl: &FnDecl, separate: bool) {
+        self.item(decl.span.start, separate);
+        self.start_line();
+        if decl.public {
+            self.out.push_str("pub ");
+        }
+        self.out.push_str(&format!("{} {}(", decl.ret, decl.name));
+        for (i, param) in decl.params.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.comments_before(param.span.start);
+            self.out.push_str(&format!("{} {}", param.ty, param.name));
+            self.comments_after(param.span.end);
+        }
+        self.out.push_str(") ");
+        self.block(&decl.body);
+        self.end_line();
+    }
+
+    /// Prints `{`, the statements of `block` and `}`, leaving the line open after it.
+    fn block(&mut self, block: &Block) {
+        if block.stmts.is_empty() && !self.has_comments_before(block.span.end) {
+            self.out.push_str("{}");
+            self.mark(block.span.end);
+            return;
+        }
+
+        self.mark(block.span.start);
+        self.open();
+        for stmt in block.stmts.iter() {
+            self.item(stmt.span.start, false);
+            self.start_line();
+            self.stmt(stmt);
+            self.end_line();
+        }
+        self.close(block.span.end - 1);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => {
+                self.out.push_str("let ");
+                if *mutable {
+                    self.out.push_str("mut ");
+                }
+                self.out.push_str(name);
+                if let Some(ty) = ty {
+                    self.out.push_str(&format!(": {}", ty));
+                }
+                if let Some(value) = value {
+                    self.out.push_str(" <- ");
+                    self.expr(value);
+                }
+                self.out.push(';');
+            }
+            StmtKind::Assign { target, op, value } => {
+                self.expr(target);
+                match op {
+                    Some(op) => self.out.push_str(&format!(" {}= ", op)),
+                    None => self.out.push_str(" <- "),
+                }
+                self.expr(value);
+                self.out.push(';');
+            }
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => self.if_stmt(cond, then, otherwise.as_ref()),
+            StmtKind::Match { scrutinee, arms } => {
+                self.out.push_str("match ");
+                self.expr(scrutinee);
+                self.out.push(' ');
+                self.open();
+                for arm in arms.iter() {
+                    self.item(arm.span.start, false);
+                    self.start_line();
+                    match &arm.pattern {
+                        Pattern::Wildcard => self.out.push('_'),
+                        Pattern::Expr(expr) => self.expr(expr),
+                    }
+                    self.out.push_str(" => ");
+                    self.block(&arm.body);
+                    self.mark(arm.span.end);
+                    self.end_line();
+                }
+                self.close(stmt.span.end - 1);
+            }
+            StmtKind::While { cond, body } => {
+                self.out.push_str("while ");
+                self.expr(cond);
+                self.out.push(' ');
+                self.block(body);
+            }
+            StmtKind::Return(value) => {
+                self.out.push_str("return");
+                if let Some(value) = value {
+                    self.out.push(' ');
+                    self.expr(value);
+                }
+                self.out.push(';');
+            }
+            StmtKind::Break => self.out.push_str("break;"),
+            StmtKind::Continue => self.out.push_str("continue;"),
+            StmtKind::Expr(expr) => {
+                self.expr(expr);
+                self.out.push(';');
+            }
+        }
+        self.mark(stmt.span.end);
+    }
+
+    /// `ConditionalStm` => if `Expression` { `CompoundDeclList` } `ElseStm`
+    fn if_stmt(&mut self, cond: &Expr, then: &Block, otherwise: Option<&Else>) {
+        self.out.push_str("if ");
+        self.expr(cond);
+        self.out.push(' ');
+        self.block(then);
+        match otherwise {
+            Some(Else::If(stmt)) => {
+                self.out.push_str(" else ");
+                self.stmt(stmt);
+            }
+            Some(Else::Block(block)) => {
+                self.out.push_str(" else ");
+                self.block(block);
+            }
+            None => {}
+        }
+    }
+
+    /// Prints `expr` on the current line, with the comments inside it. Literals are printed as
+    /// they are spelled in the source, so `0xFF` and `r"raw"` stay as they are.
+    fn expr(&mut self, expr: &Expr) {
+        self.comments_before(expr.span.start);
+        match &expr.kind {
+            ExprKind::Literal(_) => self.out.push_str(self.source.slice(expr.span)),
+            ExprKind::Variable(name) => self.out.push_str(name),
+            ExprKind::Group(inner) => {
+                self.out.push('(');
+                self.expr(inner);
+                self.out.push(')');
+            }
+            ExprKind::Array(elements) => {
+                self.out.push('[');
+                self.expr_list(elements);
+                self.out.push(']');
+            }
+            ExprKind::Unary { op, expr } => {
+                self.out.push_str(&op.to_string());
+                if *op == UnaryOp::Not {
+                    self.out.push(' ');
+                }
+                self.expr(expr);
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                self.expr(lhs);
+                self.out.push_str(&format!(" {} ", op));
+                self.expr(rhs);
+            }
+            ExprKind::Call { callee, args } => {
+                self.expr(callee);
+                self.out.push('(');
+                self.expr_list(args);
+                self.out.push(')');
+            }
+            ExprKind::Field { expr, name } => {
+                self.expr(expr);
+                self.out.push('.');
+                self.out.push_str(name);
+            }
+            ExprKind::Index { expr, index } => {
+                self.expr(expr);
+                self.out.push('[');
+                self.expr(index);
+                self.out.push(']');
+            }
+            ExprKind::Interpolated(parts) => {
+                self.out.push('"');
+                for part in parts.iter() {
+                    match part {
+                        InterpolatedPart::Str { span, .. } => {
+                            self.out.push_str(self.source.slice(*span))
+                        }
+                        InterpolatedPart::Expr(expr) => {
+                            self.out.push('{');
+                            self.expr(expr);
+                            self.out.push('}');
+                        }
+                    }
+                }
+                self.out.push('"');
+            }
+        }
+        self.comments_after(expr.span.end);
+    }
+
+    /// `ExpressionList` => `Expression`, `ExpressionList` | `null`
+    fn expr_list(&mut self, exprs: &[Expr]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expr(expr);
+        }
+    }
+
+    /// Prints the comments before the code at `offset` where they are, on the current line. A
+    /// `//` comment ends the line, the code after it going on the next one, indented once more.
+    fn comments_before(&mut self, offset: usize) {
+        while self.has_comments_before(offset) {
+            let Some(comment) = self.comments.pop_front() else {
+                break;
+            };
+            if !self.out.ends_with([' ', '(', '[']) {
+                self.out.push(' ');
+            }
+            self.out.push_str(&comment.text);
+            if comment.text.starts_with("//") {
+                self.out.push('\n');
+                self.out.push_str(&"    ".repeat(self.indent + 1));
+            } else {
+                self.out.push(' ');
+            }
+            self.mark(comment.offset + comment.text.len());
+        }
+    }
+
+    /// Prints the `/* */` comments following the code ending at `offset` with only spaces
+    /// between, on the current line.
+    fn comments_after(&mut self, offset: usize) {
+        let mut offset = offset;
+        while let Some(comment) = self.comments.front() {
+            let between = self.source.source().get(offset..comment.offset);
+            if !comment.text.starts_with("/*")
+                || !between.is_some_and(|text| text.trim().is_empty())
+            {
+                break;
+            }
+            offset = comment.offset + comment.text.len();
+            self.out.push(' ');
+            self.out.push_str(&comment.text);
+            self.comments.pop_front();
+            self.mark(offset);
+        }
+    }
+
+    /// Prints the `{` opening a body and moves to its first line.
+    fn open(&mut self) {
+        self.out.push('{');
+        self.end_line();
+        self.indent += 1;
+        self.fresh = true;
+    }
+
+    /// Prints the comments left before the `}` at `offset`, then the `}`.
+    fn close(&mut self, offset: usize) {
+        self.leading_comments(offset, false);
+        self.indent -= 1;
+        self.start_line();
+        self.out.push('}');
+        self.mark(offset + 1);
+    }
+
+    /// Prints the comments before the code at `offset` on their own lines, then puts a blank
+    /// line before that code if the source has one there or `separate` is set.
+    fn item(&mut self, offset: usize, separate: bool) {
+        let separate = self.leading_comments(offset, separate);
+        if separate || self.blank_before(offset) {
+            self.blank_line();
+        }
+    }
+
+    /// Prints the comments before `offset`. A trailing comment goes at the end of the last line
+    /// of code, the others on their own lines, the first of which goes after a blank line if
+    /// `separate` is set. Returns whether `separate` is still to be handled.
+    fn leading_comments(&mut self, offset: usize, separate: bool) -> bool {
+        let mut separate = separate;
+        while self
+            .comments
+            .front()
+            .is_some_and(|comment| comment.offset < offset)
+        {
+            let Some(comment) = self.comments.pop_front() else {
+                break;
+            };
+            if comment.trailing && self.after_code {
+                self.out.pop();
+                self.out.push(' ');
+                self.out.push_str(&comment.text);
+                self.out.push('\n');
+                self.after_code = false;
+                continue;
+            }
+
+            if separate || self.blank_before(comment.offset) {
+                self.blank_line();
+            }
+            separate = false;
+            self.start_line();
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+            self.mark(comment.offset + comment.text.len());
+            self.fresh = false;
+            self.after_code = false;
+        }
+        separate
+    }
+
+    /// Ends the current line of code. Its trailing comments are added once the code after them
+    /// is reached, see `leading_comments`.
+    fn end_line(&mut self) {
+        self.out.push('\n');
+        self.fresh = false;
+        self.after_code = true;
+    }
+
+    /// Puts a blank line before what comes next, unless right after a `{` or the start of the
+    /// file.
+    fn blank_line(&mut self) {
+        if !self.fresh && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Whether the source has a blank line between the last code printed and `offset`.
+    fn blank_before(&self, offset: usize) -> bool {
+        let line = self.line(offset);
+        line > self.line(self.last_end) + 1 && self.source.line_text(line - 1).trim().is_empty()
+    }
+
+    fn has_comments_before(&self, offset: usize) -> bool {
+        self.comments
+            .front()
+            .is_some_and(|comment| comment.offset < offset)
+    }
+
+    fn start_line(&mut self) {
+        self.out.push_str(&"    ".repeat(self.indent));
+    }
+
+    /// Records that the code up to `offset` has been printed.
+    fn mark(&mut self, offset: usize) {
+        self.last_end = self.last_end.max(offset);
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.source.location(offset).0
+    }
+}
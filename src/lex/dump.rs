@@ -0,0 +1,109 @@
+use super::{Token, TokenType};
+use crate::json;
+use colored::{ColoredString, Colorize};
+use std::io::{self, Write};
+
+/// How `TokenWriter` prints tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenFormat {
+    /// `Identifier: name`, colored by kind.
+    #[default]
+    Pretty,
+    /// A JSON array with a token per line:
+    ///
+    /// `{"type":"Identifier","lexeme":"name","span":{"start":4,"end":8,"line":1,"column":5}}`
+    Json,
+    /// A token per line: `(token Identifier "name" (span 4 8 1 5))`
+    Sexp,
+}
+
+/// Writes tokens one at a time as they are lexed.
+///
+/// The span `start` and `end` are byte offsets, `end` being exclusive, `line` and `column` are
+/// 1-based. The `lexeme` is the value of literals, `255` for `0xFF`.
+#[derive(Debug)]
+pub struct TokenWriter<W: Write> {
+    out: W,
+    format: TokenFormat,
+    /// Number of tokens written.
+    count: usize,
+}
+
+impl<W: Write> TokenWriter<W> {
+    pub fn new(out: W, format: TokenFormat) -> Self {
+        Self {
+            out,
+            format,
+            count: 0,
+        }
+    }
+
+    pub fn write(&mut self, token: &Token) -> io::Result<()> {
+        match self.format {
+            TokenFormat::Pretty => writeln!(
+                self.out,
+                "{}: {}",
+                paint(token.token_type, &token.token_type.to_string()),
+                paint(token.token_type, &token.lexeme).bold()
+            )?,
+            TokenFormat::Json => {
+                let separator = if self.count == 0 { "[\n" } else { ",\n" };
+                write!(
+                    self.out,
+                    "{}  {{\"type\":{},\"lexeme\":{},\"span\":{{\"start\":{},\"end\":{},\"line\":{},\"column\":{}}}}}",
+                    separator,
+                    json::string(&token.token_type.to_string()),
+                    json::string(&token.lexeme),
+                    token.span.start,
+                    token.span.end,
+                    token.span.line,
+                    token.span.column
+                )?
+            }
+            TokenFormat::Sexp => writeln!(
+                self.out,
+                "(token {} {} (span {} {} {} {}))",
+                token.token_type,
+                json::string(&token.lexeme),
+                token.span.start,
+                token.span.end,
+                token.span.line,
+                token.span.column
+            )?,
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Closes the output after the last token.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.format == TokenFormat::Json {
+            let end = if self.count == 0 { "[]\n" } else { "\n]\n" };
+            self.out.write_all(end.as_bytes())?;
+        }
+        self.out.flush()
+    }
+}
+
+/// Colors `text` by the kind of token it belongs to.
+fn paint(token_type: TokenType, text: &str) -> ColoredString {
+    match token_type {
+        TokenType::Identifier | TokenType::LeftParen | TokenType::RightParen => text.green(),
+        TokenType::IntLiteral
+        | TokenType::FloatLiteral
+        | TokenType::LeftBrace
+        | TokenType::RightBrace => text.yellow(),
+        TokenType::StringLiteral
+        | TokenType::CharLiteral
+        | TokenType::ByteLiteral
+        | TokenType::ByteStringLiteral
+        | TokenType::StringStart
+        | TokenType::StringPart
+        | TokenType::InterpolationStart
+        | TokenType::InterpolationEnd
+        | TokenType::StringEnd
+        | TokenType::LeftBracket
+        | TokenType::RightBracket => text.red(),
+        _ => text.purple(),
+    }
+}
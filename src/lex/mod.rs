@@ -1,533 +1,1116 @@
-use colored::Colorize;
-use std::collections::{HashMap, HashSet};
+use crate::error::{Diagnostic, Label, ToDiagnostic};
+use std::collections::VecDeque;
 
+mod dump;
+mod span;
+mod stream;
+mod trivia;
+
+pub use dump::{TokenFormat, TokenWriter};
+pub use span::{SourceMap, Span};
+pub use stream::TokenStream;
+pub use trivia::{source_text, Lossless, Trivia, TriviaKind};
+
+/// Lexes the source with a single cursor over its characters.
+///
+/// `current` is a byte offset into `source` and always lies on a character boundary, so
+/// looking at the next character is O(1) and lexing the whole file is linear.
+///
+/// `Lexer` is an `Iterator` producing the tokens and errors one at a time as they are found, so
+/// the whole file does not need to be lexed up front. `parse` collects all of them.
 #[derive(Debug)]
 pub struct Lexer {
     source: String,
-    pub tokens: Vec<Token>,
+    /// Tokens and errors lexed but not handed out yet. A single step can produce several, as
+    /// the `StringStart`, `StringPart` and `InterpolationStart` of `"a{b}"`.
+    pending: VecDeque<Result<Token, LexError>>,
+    /// Set once `Eof` has been produced.
+    finished: bool,
+    /// Whether tokens keep their source text and trivia, see `lossless`.
+    lossless: bool,
+    /// In lossless mode, the last token lexed. It is handed out once the trivia following it
+    /// is known.
+    held: Option<Token>,
+    /// In lossless mode, the end of the last token lexed, where the next trivia starts.
+    trivia_start: usize,
     current: usize,
     line: usize,
+    column: usize,
+    /// Where the token being lexed starts.
+    start: Position,
+    /// The `{` expressions of interpolated strings being lexed, innermost last.
+    interpolations: Vec<Interpolation>,
+    /// The `file` of the spans.
+    file: usize,
+}
+
+/// Byte offset, line and column of a character in the source.
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+/// An expression embedded in a string, as the `{a + b}` in `"total: {a + b}"`.
+#[derive(Debug)]
+struct Interpolation {
+    /// The opening `"` of the string.
+    quote: Position,
+    /// Number of `{` opened inside the expression and not closed yet.
+    depth: usize,
 }
 
 impl Lexer {
     pub fn new(source: String) -> Self {
         Self {
             source,
-            tokens: Vec::new(),
+            pending: VecDeque::new(),
+            finished: false,
+            lossless: false,
+            held: None,
+            trivia_start: 0,
             current: 0,
             line: 1,
+            column: 1,
+            start: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            interpolations: Vec::new(),
+            file: 0,
         }
     }
 
+    /// Sets the `file` of the spans of the tokens and errors, the index of the module of the
+    /// program the source is from.
+    pub fn file(mut self, file: usize) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Makes every token carry its source text with the whitespace and comments around it, so
+    /// `source_text` of the tokens gives back the source byte for byte.
+    pub fn lossless(mut self) -> Self {
+        self.lossless = true;
+        self
+    }
+
     /// Starts parsing the content of the file
-    pub fn parse(&mut self) {
-        let num = HashSet::from(["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
-        let alpha = HashSet::from([
-            "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q",
-            "r", "s", "t", "u", "v", "w", "x", "y", "z", "A", "B", "C", "D", "E", "F", "G", "H",
-            "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y",
-            "Z",
-        ]);
-        while self.current < self.source.len() {
-            let s = &self.source[self.current..self.current + 1];
-            match s {
-                "." => {
-                    self.tokens.push(Token::new(s, TokenType::Dot, self.line));
-                    self.advance();
-                }
-                "," => {
-                    self.tokens.push(Token::new(s, TokenType::Comma, self.line));
-                    self.advance();
-                }
-                ";" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::Semicolon, self.line));
-                    self.advance();
-                }
-                ":" => {
-                    self.tokens.push(Token::new(s, TokenType::Colon, self.line));
-                    self.advance();
-                }
-                "(" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::LeftParen, self.line));
-                    self.advance();
-                }
-                ")" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::RightParen, self.line));
-                    self.advance();
-                }
-                "{" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::LeftBrace, self.line));
-                    self.advance();
-                }
-                "}" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::RightBrace, self.line));
-                    self.advance();
-                }
-                "[" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::LeftBracket, self.line));
-                    self.advance();
-                }
-                "]" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::RightBracket, self.line));
-                    self.advance();
-                }
-                "+" => {
-                    self.tokens.push(Token::new(s, TokenType::Plus, self.line));
-                    self.advance();
-                }
-                "/" => {
-                    // Comment Handling is yet to be implemented
-                    // Check for the next character and if it is / then handle comment.
-                    self.tokens.push(Token::new(s, TokenType::Slash, self.line));
-                    self.advance();
-                }
-                "*" => {
-                    if self.check("*") {
-                        self.tokens
-                            .push(Token::new("**", TokenType::Power, self.line));
-                        self.advance();
+    ///
+    /// Lexing carries on after an error, so every lexical problem in the file is reported at once.
+    pub fn parse(&mut self) -> Result<Vec<Token>, Vec<LexError>> {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for result in self.by_ref() {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Lexes the token starting with `c`, the character at `self.current`.
+    fn scan_token(&mut self, c: char) {
+        self.start = self.cursor();
+        match c {
+            '.' => {
+                self.advance();
+                let token_type = if self.matches('.') {
+                    if self.matches('=') {
+                        TokenType::DotDotEqual
                     } else {
-                        self.tokens.push(Token::new(s, TokenType::Star, self.line));
+                        TokenType::DotDot
                     }
-                    self.advance();
-                }
-                "%" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::Percent, self.line));
-                    self.advance();
-                }
-                "|" => {
-                    self.tokens.push(Token::new(s, TokenType::Pipe, self.line));
-                    self.advance();
-                }
-                "^" => {
-                    self.tokens.push(Token::new(s, TokenType::Caret, self.line));
-                    self.advance();
+                } else {
+                    TokenType::Dot
+                };
+                self.add_token(token_type);
+            }
+            ',' => self.single(TokenType::Comma),
+            ';' => self.single(TokenType::Semicolon),
+            ':' => {
+                self.advance();
+                let token_type = if self.matches(':') {
+                    TokenType::ColonColon
+                } else {
+                    TokenType::Colon
+                };
+                self.add_token(token_type);
+            }
+            '(' => self.single(TokenType::LeftParen),
+            ')' => self.single(TokenType::RightParen),
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.depth += 1;
                 }
-                "&" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::Ampersand, self.line));
-                    self.advance();
+                self.single(TokenType::LeftBrace);
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(interpolation) if interpolation.depth == 0 => {
+                    let quote = interpolation.quote;
+                    self.interpolations.pop();
+                    self.single(TokenType::InterpolationEnd);
+                    self.string_body(quote, false, true);
                 }
-                "!" => {
-                    self.tokens.push(Token::new(s, TokenType::Bang, self.line));
-                    self.advance();
+                Some(interpolation) => {
+                    interpolation.depth -= 1;
+                    self.single(TokenType::RightBrace);
                 }
-                "?" => {
-                    self.tokens
-                        .push(Token::new(s, TokenType::Question, self.line));
-                    self.advance();
+                None => self.single(TokenType::RightBrace),
+            },
+            '[' => self.single(TokenType::LeftBracket),
+            ']' => self.single(TokenType::RightBracket),
+            '+' => {
+                self.advance();
+                let token_type = if self.matches('=') {
+                    TokenType::PlusEqual
+                } else {
+                    TokenType::Plus
+                };
+                self.add_token(token_type);
+            }
+            '/' => {
+                self.advance();
+                if self.matches('/') {
+                    self.line_comment();
+                } else if self.matches('*') {
+                    self.block_comment();
+                } else if self.matches('=') {
+                    self.add_token(TokenType::SlashEqual);
+                } else {
+                    self.add_token(TokenType::Slash);
                 }
-                "<" => {
-                    if self.check("=") {
-                        self.tokens
-                            .push(Token::new("<=", TokenType::LessEqual, self.line));
-                        self.advance();
-                    } else if self.check("-") {
-                        self.tokens
-                            .push(Token::new("<-", TokenType::LeftAssign, self.line));
-                        self.advance();
-                    } else if self.check("<") {
-                        self.tokens
-                            .push(Token::new("<<", TokenType::LeftShift, self.line));
-                        self.advance();
+            }
+            '*' => {
+                self.advance();
+                let token_type = if self.matches('*') {
+                    if self.matches('=') {
+                        TokenType::PowerEqual
                     } else {
-                        self.tokens.push(Token::new(s, TokenType::Less, self.line));
+                        TokenType::Power
                     }
-                    self.advance();
-                }
-                ">" => {
-                    if self.check("=") {
-                        self.tokens
-                            .push(Token::new(">=", TokenType::GreaterEqual, self.line));
-                        self.advance();
-                    } else if self.check(">") {
-                        self.tokens
-                            .push(Token::new(">>", TokenType::RightShift, self.line));
-                        self.advance();
+                } else if self.matches('=') {
+                    TokenType::StarEqual
+                } else {
+                    TokenType::Star
+                };
+                self.add_token(token_type);
+            }
+            '%' => {
+                self.advance();
+                let token_type = if self.matches('=') {
+                    TokenType::PercentEqual
+                } else {
+                    TokenType::Percent
+                };
+                self.add_token(token_type);
+            }
+            '|' => {
+                self.advance();
+                let token_type = if self.matches('|') {
+                    TokenType::PipePipe
+                } else {
+                    TokenType::Pipe
+                };
+                self.add_token(token_type);
+            }
+            '^' => self.single(TokenType::Caret),
+            '&' => {
+                self.advance();
+                let token_type = if self.matches('&') {
+                    TokenType::AmpersandAmpersand
+                } else {
+                    TokenType::Ampersand
+                };
+                self.add_token(token_type);
+            }
+            '!' => {
+                self.advance();
+                let token_type = if self.matches('=') {
+                    TokenType::BangEqual
+                } else {
+                    TokenType::Bang
+                };
+                self.add_token(token_type);
+            }
+            '?' => self.single(TokenType::Question),
+            '~' => self.single(TokenType::Tilde),
+            '@' => self.single(TokenType::At),
+            '<' => {
+                self.advance();
+                let token_type = if self.matches('=') {
+                    TokenType::LessEqual
+                } else if self.matches('-') {
+                    TokenType::LeftAssign
+                } else if self.matches('<') {
+                    if self.matches('=') {
+                        TokenType::LeftShiftEqual
                     } else {
-                        self.tokens
-                            .push(Token::new(s, TokenType::Greater, self.line));
+                        TokenType::LeftShift
                     }
-                    self.advance();
-                }
-                "=" => {
-                    if self.check("=") {
-                        self.tokens
-                            .push(Token::new("==", TokenType::EqualEqual, self.line));
-                        self.advance();
-                    } else if self.check(">") {
-                        self.tokens
-                            .push(Token::new("=>", TokenType::Arrow, self.line));
-                        self.advance();
+                } else {
+                    TokenType::Less
+                };
+                self.add_token(token_type);
+            }
+            '>' => {
+                self.advance();
+                let token_type = if self.matches('=') {
+                    TokenType::GreaterEqual
+                } else if self.matches('>') {
+                    if self.matches('=') {
+                        TokenType::RightShiftEqual
                     } else {
-                        self.tokens.push(Token::new(s, TokenType::Equal, self.line));
+                        TokenType::RightShift
                     }
-                    self.advance();
+                } else {
+                    TokenType::Greater
+                };
+                self.add_token(token_type);
+            }
+            '=' => {
+                self.advance();
+                let token_type = if self.matches('=') {
+                    TokenType::EqualEqual
+                } else if self.matches('>') {
+                    TokenType::Arrow
+                } else {
+                    TokenType::Equal
+                };
+                self.add_token(token_type);
+            }
+            '-' => {
+                self.advance();
+                let token_type = if self.matches('>') {
+                    TokenType::RightAssign
+                } else if self.matches('=') {
+                    TokenType::MinusEqual
+                } else {
+                    TokenType::Minus
+                };
+                self.add_token(token_type);
+            }
+            'r' if self.starts_raw_string() => {
+                self.raw_string();
+            }
+            'b' if self.peek_nth(1) == Some('"') => {
+                self.advance(); // Move past the b
+                self.string(true);
+            }
+            'b' if self.peek_nth(1) == Some('\'') => {
+                self.advance(); // Move past the b
+                self.char(true);
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                self.identifier();
+            }
+            c if c.is_ascii_digit() => {
+                self.number();
+            }
+            '"' => {
+                self.string(false);
+            }
+            '\'' => {
+                self.char(false);
+            }
+            ' ' | '\t' | '\r' | '\n' => {
+                self.advance();
+            }
+            _ => {
+                self.advance();
+                self.error(LexErrorKind::UnexpectedCharacter(c), self.span());
+            }
+        }
+    }
+
+    /// Reports the strings left open at the end of the file and pushes `Eof`.
+    fn finish(&mut self) {
+        for interpolation in std::mem::take(&mut self.interpolations) {
+            let span = self.span_from(interpolation.quote);
+            self.error(LexErrorKind::UnterminatedString, span);
+        }
+        self.start = self.cursor();
+        self.add_literal("", TokenType::Eof);
+        self.finished = true;
+    }
+
+    /// Returns the character at `self.current` without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.source[self.current..].chars().next()
+    }
+
+    /// Returns the character `n` characters after `self.current` without consuming anything.
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.source[self.current..].chars().nth(n)
+    }
+
+    /// `advance` `fn` is used to move forward by 1 character.
+    ///
+    /// It will be used everywhere, whenever need to move forward, as it keeps `self.line` and
+    /// `self.column` up to date.
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.current += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Moves past the character at `self.current` if it is `c`.
+    ///
+    /// Used for the double charactered operators and so.
+    fn matches(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+
+    /// The `Position` of the cursor, `self.current`.
+    fn cursor(&self) -> Position {
+        Position {
+            offset: self.current,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// Pushes a token for the single character at `self.current`.
+    fn single(&mut self, token_type: TokenType) {
+        self.advance();
+        self.add_token(token_type);
+    }
+
+    /// Pushes a token whose lexeme is the source text from `self.start` to `self.current`.
+    fn add_token(&mut self, token_type: TokenType) {
+        let lexeme = self.source[self.start.offset..self.current].to_string();
+        self.add_literal(&lexeme, token_type);
+    }
+
+    /// Pushes a token with the given `lexeme` spanning `self.start` to `self.current`.
+    fn add_literal(&mut self, lexeme: &str, token_type: TokenType) {
+        let span = self.span();
+        self.push_token(Token::new(lexeme, token_type, span));
+    }
+
+    /// Queues `token` to be handed out, attaching its trivia in lossless mode.
+    fn push_token(&mut self, mut token: Token) {
+        if !self.lossless {
+            self.pending.push_back(Ok(token));
+            return;
+        }
+
+        let mut leading = trivia::split(&self.source[self.trivia_start..token.span.start]);
+        if let Some(mut held) = self.held.take() {
+            let newline = leading
+                .iter()
+                .position(|trivia| trivia.kind == TriviaKind::Newline)
+                .unwrap_or(leading.len());
+            let rest = leading.split_off(newline);
+            if let Some(lossless) = held.lossless.as_mut() {
+                lossless.trailing = leading;
+            }
+            self.pending.push_back(Ok(held));
+            leading = rest;
+        }
+
+        token.lossless = Some(Box::new(Lossless {
+            leading,
+            text: self.source[token.span.start..token.span.end].to_string(),
+            trailing: Vec::new(),
+        }));
+        self.trivia_start = token.span.end;
+        if token.token_type == TokenType::Eof {
+            self.pending.push_back(Ok(token));
+        } else {
+            self.held = Some(token);
+        }
+    }
+
+    /// The `Span` of the token being lexed, from `self.start` to `self.current`.
+    fn span(&self) -> Span {
+        self.span_from(self.start)
+    }
+
+    /// The `Span` from `start` to `self.current`.
+    fn span_from(&self, start: Position) -> Span {
+        Span::new(start.offset, self.current, start.line, start.column)
+    }
+
+    /// Records a `LexError` of `kind` found at `span`.
+    fn error(&mut self, kind: LexErrorKind, span: Span) {
+        self.pending.push_back(Err(LexError { kind, span }));
+    }
+
+    /// `escape` `fn` reads the escape sequence after a `\\`, which `escape_start` points at.
+    ///
+    /// Knows `\\t`, `\\n`, `\\r`, `\\0`, `\\\\`, `\\'`, `\\"`, `\\xNN` and `\\u{XXXX}`. In a
+    /// `byte` literal `\\xNN` goes up to `\\xFF` and `\\u{}` is not allowed, elsewhere `\\xNN` is
+    /// ASCII only. Returns `None` after reporting an invalid escape or at the end of the file.
+    fn escape(&mut self, escape_start: Position, byte: bool) -> Option<char> {
+        let c = self.advance()?;
+        let escaped = match c {
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '\'' => '\'',
+            '"' => '"',
+            'x' => {
+                let mut digits = String::new();
+                while digits.len() < 2 && self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    digits.extend(self.advance());
                 }
-                "-" => {
-                    if self.check(">") {
-                        self.tokens
-                            .push(Token::new("->", TokenType::RightAssign, self.line));
-                        self.advance();
-                    } else {
-                        self.tokens.push(Token::new(s, TokenType::Minus, self.line));
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 && (byte || value.is_ascii()) => {
+                        char::from(value)
                     }
-                    self.advance();
-                }
-                s if alpha.contains(s) || s == "_" => {
-                    self.identifier();
-                }
-                s if num.contains(s) => {
-                    self.number();
+                    _ => return self.malformed_escape(escape_start),
                 }
-                "\"" => {
-                    self.string();
-                }
-                "\'" => {
-                    self.char();
+            }
+            'u' if !byte => {
+                if !self.matches('{') {
+                    return self.malformed_escape(escape_start);
                 }
-                " " | "\t" | "\r" => {
-                    self.advance();
+                let mut digits = String::new();
+                while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    digits.extend(self.advance());
                 }
-                "\n" => {
-                    self.line += 1;
-                    self.advance();
+                if !self.matches('}') || digits.is_empty() || digits.len() > 6 {
+                    return self.malformed_escape(escape_start);
                 }
-                _ => {
-                    panic!("Unexpected character: {}", s);
+                match u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(c) => c,
+                    None => return self.malformed_escape(escape_start),
                 }
             }
-            // Uncomment for token printing
-            // print!("{}[2J", 27 as char);
-            // self.printTokens();
-        }
-        self.tokens.push(Token::new("", TokenType::Eof, self.line));
+            _ => {
+                let span = self.span_from(escape_start);
+                self.error(LexErrorKind::UnknownEscape(c), span);
+                return None;
+            }
+        };
+        Some(escaped)
     }
 
-    /// `advance` `fn` is used to move forward by 1.
+    /// Reports the escape sequence from `escape_start` to `self.current` as malformed.
+    fn malformed_escape(&mut self, escape_start: Position) -> Option<char> {
+        let text = self.source[escape_start.offset..self.current].to_string();
+        let span = self.span_from(escape_start);
+        self.error(LexErrorKind::MalformedEscape(text), span);
+        None
+    }
+
+    /// `line_comment` `fn` skips a `//` comment up to the end of the line.
     ///
-    /// It will be used everywhere, whenever need to move forward.
-    fn advance(&mut self) {
-        self.current += 1;
+    /// `///` and `//!` are doc comments and are kept as a `DocComment` token, `////` is not.
+    fn line_comment(&mut self) {
+        let rest = &self.source[self.current..];
+        let doc = rest.starts_with('!') || (rest.starts_with('/') && !rest.starts_with("//"));
+
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.advance();
+        }
+
+        if doc {
+            let lexeme = self.source[self.start.offset..self.current].trim_end_matches('\r');
+            let lexeme = lexeme.to_string();
+            self.add_literal(&lexeme, TokenType::DocComment);
+        }
+    }
+
+    /// `block_comment` `fn` skips a `/* */` comment, which can be nested.
+    fn block_comment(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance() {
+                Some('/') if self.matches('*') => depth += 1,
+                Some('*') if self.matches('/') => depth -= 1,
+                Some(_) => {}
+                None => {
+                    let span = Span::new(
+                        self.start.offset,
+                        self.start.offset + 2,
+                        self.start.line,
+                        self.start.column,
+                    );
+                    self.error(LexErrorKind::UnterminatedBlockComment, span);
+                    return;
+                }
+            }
+        }
     }
 
-    /// Checks the next character of the file, after `self.current`.
+    /// `char` `fn` deals with the `char type` in the language, and with `byte` literals such
+    /// as `b'a'` when `byte` is set.
     ///
-    /// Used for the double charactered operators and so.
-    fn check(&self, s: &str) -> bool {
-        if self.ended() {
-            return false;
+    /// Reports an error if no character is specified at between `''` or uncomplete `char`.
+    fn char(&mut self, byte: bool) {
+        self.advance(); // Move past the initial '
+
+        let ch = match self.peek() {
+            Some('\'') => {
+                self.advance();
+                self.error(LexErrorKind::EmptyChar, self.span());
+                return;
+            }
+            Some('\\') => {
+                let escape_start = self.cursor();
+                self.advance();
+                self.escape(escape_start, byte)
+            }
+            Some(c) if c != '\n' => {
+                let char_start = self.cursor();
+                self.advance(); // Move past the character
+                if byte && !c.is_ascii() {
+                    let span = self.span_from(char_start);
+                    self.error(LexErrorKind::NonAsciiByte(c), span);
+                }
+                Some(c)
+            }
+            _ => {
+                self.error(LexErrorKind::UnterminatedChar, self.span());
+                return;
+            }
+        };
+
+        if !self.matches('\'') {
+            self.error(LexErrorKind::UnterminatedChar, self.span());
+            return;
+        }
+
+        if let Some(ch) = ch {
+            let token_type = if byte {
+                TokenType::ByteLiteral
+            } else {
+                TokenType::CharLiteral
+            };
+            self.add_literal(&ch.to_string(), token_type);
         }
-        let i = self.current + 1;
-        let c = &self.source[i..i + 1];
-        c == s
     }
 
-    /// Checks for the end of the file.
-    fn ended(&self) -> bool {
-        self.current + 1 >= self.source.len()
+    /// `string` `fn` deals with the string literals in the language, and with byte strings
+    /// such as `b"GET"` when `byte` is set.
+    ///
+    /// If encounter `"` `string` is called. Strings can span lines. A byte string is kept
+    /// with every byte as the `char` of the same value.
+    fn string(&mut self, byte: bool) {
+        let quote = self.start;
+        self.advance(); // Move past the initial "
+        self.string_body(quote, byte, false);
     }
 
-    /// `char` `fn` deals with the `char type` in the language.
+    /// Lexes the text of the string opened at `quote`, up to its closing `"`.
     ///
-    /// Simply panics if no character is specified at between `''` or uncomplete `char`.
-    fn char(&mut self) {
-        if self.ended() {
-            panic!(
-                "Unterminated character definition at {}, in line {}",
-                self.current, self.line
-            );
+    /// A string without `{` is a single `StringLiteral`. `"total: {a + b}!"` instead becomes
+    /// `StringStart`, `StringPart` `total: `, `InterpolationStart`, the tokens of `a + b`,
+    /// `InterpolationEnd`, `StringPart` `!` and `StringEnd`. The text stops at every `{`, and
+    /// `interpolated` is set when it picks up again after the matching `}`. `{{` and `}}`
+    /// stand for `{` and `}`, byte strings have no interpolation.
+    fn string_body(&mut self, quote: Position, byte: bool, interpolated: bool) {
+        let mut s = String::new();
+        let part_start = self.cursor();
+
+        loop {
+            let Some(current_char) = self.peek() else {
+                let span = self.span_from(quote);
+                self.error(LexErrorKind::UnterminatedString, span);
+                // The strings this one is interpolated in end with it, `"a {b "` is one error.
+                self.interpolations.clear();
+                return;
+            };
+
+            let char_start = self.cursor();
+            match current_char {
+                '"' => break,
+                '{' | '}' if !byte && self.peek_nth(1) == Some(current_char) => {
+                    self.advance();
+                    self.advance();
+                    s.push(current_char);
+                }
+                '{' if !byte => {
+                    if !interpolated {
+                        let span =
+                            Span::new(quote.offset, quote.offset + 1, quote.line, quote.column);
+                        self.push_token(Token::new("\"", TokenType::StringStart, span));
+                    }
+                    if !s.is_empty() {
+                        let span = self.span_from(part_start);
+                        self.push_token(Token::new(&s, TokenType::StringPart, span));
+                    }
+                    self.start = char_start;
+                    self.single(TokenType::InterpolationStart);
+                    self.interpolations.push(Interpolation { quote, depth: 0 });
+                    return;
+                }
+                '}' if !byte => {
+                    self.advance();
+                    let span = self.span_from(char_start);
+                    self.error(LexErrorKind::UnmatchedStringBrace, span);
+                }
+                '\\' => {
+                    self.advance();
+                    s.extend(self.escape(char_start, byte));
+                }
+                _ => {
+                    self.advance();
+                    if byte && !current_char.is_ascii() {
+                        let span = self.span_from(char_start);
+                        self.error(LexErrorKind::NonAsciiByte(current_char), span);
+                    }
+                    s.push(current_char);
+                }
+            }
         }
 
-        self.advance(); // Move past the initial '
+        if interpolated {
+            if !s.is_empty() {
+                let span = self.span_from(part_start);
+                self.push_token(Token::new(&s, TokenType::StringPart, span));
+            }
+            self.start = self.cursor();
+            self.single(TokenType::StringEnd);
+            return;
+        }
 
-        let mut ch = String::new();
-        let current_char = self
-            .source
-            .chars()
-            .nth(self.current)
-            .expect("Unexpected program exit");
+        self.advance(); // Move past the closing "
 
-        if current_char == '\\' {
-            self.advance();
-            let escape_char = self
-                .source
-                .chars()
-                .nth(self.current)
-                .expect("Unexpected program exit");
-
-            ch.push(match escape_char {
-                't' => '\t',
-                'n' => '\n',
-                'r' => '\r',
-                '\\' => '\\',
-                '\'' => '\'',
-                _ => panic!(
-                    "Unknown escape character at {}, in line {}",
-                    self.current, self.line
-                ),
-            });
+        let token_type = if byte {
+            TokenType::ByteStringLiteral
         } else {
-            ch.push(current_char);
-        }
+            TokenType::StringLiteral
+        };
+        self.start = quote;
+        self.add_literal(&s, token_type);
+    }
 
-        self.advance(); // Move past the character or escape sequence
+    /// Checks for `r"` or `r#"`, `r##"` and so on at `self.current`.
+    fn starts_raw_string(&self) -> bool {
+        self.source[self.current + 1..]
+            .trim_start_matches('#')
+            .starts_with('"')
+    }
 
-        if self
-            .source
-            .chars()
-            .nth(self.current)
-            .expect("Unexpected program exit")
-            != '\''
-        {
-            panic!(
-                "Unterminated character definition at {}, in line {}",
-                self.current, self.line
-            );
+    /// `raw_string` `fn` deals with `r"..."` strings, which have no escapes.
+    ///
+    /// `r#"..."#` can contain `"`, the string only ends at a `"` followed by as many `#` as
+    /// it started with.
+    fn raw_string(&mut self) {
+        self.advance(); // Move past the r
+        let mut hashes = 0;
+        while self.matches('#') {
+            hashes += 1;
         }
+        self.advance(); // Move past the initial "
 
-        self.advance(); // Move past the closing '
+        let content_start = self.current;
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        while !self.source[self.current..].starts_with(&terminator) {
+            if self.advance().is_none() {
+                self.error(LexErrorKind::UnterminatedString, self.span());
+                return;
+            }
+        }
+        let s = self.source[content_start..self.current].to_string();
+        for _ in 0..terminator.len() {
+            self.advance();
+        }
 
-        self.tokens
-            .push(Token::new(&ch, TokenType::CharLiteral, self.line));
+        self.add_literal(&s, TokenType::StringLiteral);
     }
 
-    /// `string` `fn` deals with the string literals in the language.
+    /// `number` `fn` deals with the number literals in the language.
     ///
-    /// If encounter `"` `string` is called.
-    fn string(&mut self) {
-        let mut s = String::new();
-        self.advance(); // Move past the initial "
+    /// If encounter any number `number` is called. `1`, `1_000`, `0xFF`, `0o17`, `0b1010`,
+    /// `1.5`, `2e10`, `1.5E-3` are all numbers and can end with a type suffix, as in `255u8`
+    /// or `1.5f32`. The lexeme is the value in decimal followed by the suffix, `0xFF_u8` gives
+    /// `255u8`.
+    fn number(&mut self) {
+        let radix = match (self.peek(), self.peek_nth(1)) {
+            (Some('0'), Some('x')) => 16,
+            (Some('0'), Some('o')) => 8,
+            (Some('0'), Some('b')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.advance();
+            self.advance();
+            self.radix_number(radix);
+            return;
+        }
 
-        while !self.ended() {
-            let current_char = self
-                .source
-                .chars()
-                .nth(self.current)
-                .expect("Unexpected program exit");
+        self.digits();
+        let mut float = false;
+        // `1..2` and `1.abs()` are not floats, a fraction needs a digit after the `.`.
+        if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+            float = true;
+            self.advance();
+            self.digits();
 
-            if current_char == '"' {
-                break;
+            if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '.')
+                {
+                    self.advance();
+                }
+                let n = self.source[self.start.offset..self.current].to_string();
+                self.error(LexErrorKind::MalformedNumber(n), self.span());
+                return;
             }
-
-            if current_char == '\\' {
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let exponent = match self.peek_nth(1) {
+                Some('+' | '-') => self.peek_nth(2).is_some_and(|c| c.is_ascii_digit()),
+                c => c.is_some_and(|c| c.is_ascii_digit()),
+            };
+            if exponent {
+                float = true;
                 self.advance();
-                let escape_char = self
-                    .source
-                    .chars()
-                    .nth(self.current)
-                    .expect("Unexpected program exit");
-
-                s.push(match escape_char {
-                    't' => '\t',
-                    'n' => '\n',
-                    'r' => '\r',
-                    '\\' => '\\',
-                    '"' => '"',
-                    _ => panic!(
-                        "Unknown escape sequence at {}, in line {}",
-                        self.current, self.line
-                    ),
-                });
-            } else {
-                s.push(current_char);
+                if matches!(self.peek(), Some('+' | '-')) {
+                    self.advance();
+                }
+                self.digits();
+            }
+        }
+
+        let number_end = self.current;
+        let suffix = self.suffix();
+        let n = self.source[self.start.offset..number_end].replace('_', "");
+
+        match suffix.as_str() {
+            "" if float => self.float(&n, &suffix),
+            "f32" | "f64" => self.float(&n, &suffix),
+            "" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" if !float => {
+                self.int(&n, 10, &suffix)
             }
+            _ => self.error(LexErrorKind::InvalidSuffix(suffix), self.span()),
+        }
+    }
 
+    /// The part of a `0x`, `0o` or `0b` number after its prefix.
+    fn radix_number(&mut self, radix: u32) {
+        let body_start = self.current;
+        while self.peek().is_some_and(|c| c.is_digit(radix) || c == '_') {
             self.advance();
         }
+        let digits = self.source[body_start..self.current].replace('_', "");
+        let suffix = self.suffix();
 
-        if self.ended() {
-            panic!(
-                "Unterminated string definition at {}, in line {}",
-                self.current, self.line
-            );
+        if digits.is_empty() || suffix.starts_with(|c: char| c.is_ascii_digit()) {
+            let n = self.source[self.start.offset..self.current].to_string();
+            self.error(LexErrorKind::MalformedNumber(n), self.span());
+            return;
         }
+        match suffix.as_str() {
+            "" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" => {
+                self.int(&digits, radix, &suffix)
+            }
+            _ => self.error(LexErrorKind::InvalidSuffix(suffix), self.span()),
+        }
+    }
 
-        self.advance(); // Move past the closing "
+    /// Moves past the decimal digits and `_` separators at `self.current`.
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '_') {
+            self.advance();
+        }
+    }
 
-        self.tokens
-            .push(Token::new(&s, TokenType::StringLiteral, self.line));
+    /// Moves past the type suffix of a number, if any, and returns it.
+    fn suffix(&mut self) -> String {
+        let start = self.current;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.advance();
+        }
+        self.source[start..self.current].to_string()
     }
 
-    /// `number` `fn` deals with the number literals in the language.
-    ///
-    /// If encounter any number `number` is called.
-    fn number(&mut self) {
-        let mut n = String::new();
-        while !self.ended() {
-            let c = self
-                .source
-                .chars()
-                .nth(self.current)
-                .expect("Unexpected Program Exit");
-            if c.is_numeric() || c == '.' {
-                n.push(c);
-            } else {
-                break;
+    /// Pushes an `IntLiteral` for `digits` in `radix`.
+    fn int(&mut self, digits: &str, radix: u32, suffix: &str) {
+        match u64::from_str_radix(digits, radix) {
+            Ok(value) => self.add_literal(&format!("{}{}", value, suffix), TokenType::IntLiteral),
+            Err(_) => {
+                let n = self.source[self.start.offset..self.current].to_string();
+                self.error(LexErrorKind::IntegerTooLarge(n), self.span());
+            }
+        }
+    }
+
+    /// Pushes a `FloatLiteral` for `n`.
+    fn float(&mut self, n: &str, suffix: &str) {
+        match n.parse::<f64>() {
+            Ok(value) => self.add_literal(&format!("{}{}", value, suffix), TokenType::FloatLiteral),
+            Err(_) => {
+                let n = self.source[self.start.offset..self.current].to_string();
+                self.error(LexErrorKind::MalformedNumber(n), self.span());
             }
-            self.advance();
         }
-        self.tokens
-            .push(Token::new(&n, TokenType::NumberLiteral, self.line));
     }
 
     /// `indentifier` `fn` deals with the variables in the language.
     ///
     /// If encounter any alphabet or `_` `indentifier` is called.
     fn identifier(&mut self) {
-        let num = HashSet::from(["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
-        let alpha = HashSet::from([
-            "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q",
-            "r", "s", "t", "u", "v", "w", "x", "y", "z", "A", "B", "C", "D", "E", "F", "G", "H",
-            "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y",
-            "Z",
-        ]);
-        let keywords = HashMap::from([
-            ("void", TokenType::Void),
-            ("main", TokenType::Main),
-            ("let", TokenType::Let),
-            ("const", TokenType::Const),
-            ("if", TokenType::If),
-            ("else", TokenType::Else),
-            ("while", TokenType::While),
-            ("return", TokenType::Return),
-            ("break", TokenType::Break),
-            ("continue", TokenType::Continue),
-            ("in", TokenType::In),
-            ("match", TokenType::Match),
-            ("struct", TokenType::Struct),
-            ("enum", TokenType::Enum),
-            ("impl", TokenType::Impl),
-            ("true", TokenType::True),
-            ("false", TokenType::False),
-            ("goto", TokenType::Goto),
-            ("label", TokenType::Label),
-            ("mut", TokenType::Mut),
-            ("pub", TokenType::Pub),
-            ("and", TokenType::And),
-            ("or", TokenType::Or),
-            ("not", TokenType::Not),
-            ("i8", TokenType::I8),
-            ("i16", TokenType::I16),
-            ("i32", TokenType::I32),
-            ("i64", TokenType::I64),
-            ("f32", TokenType::F32),
-            ("f64", TokenType::F64),
-            ("u8", TokenType::U8),
-            ("u16", TokenType::U16),
-            ("u32", TokenType::U32),
-            ("u64", TokenType::U64),
-            ("bool", TokenType::Bool),
-            ("byte", TokenType::Byte),
-            ("char", TokenType::Char),
-            ("String", TokenType::String),
-        ]);
-        let mut id = String::new();
-        while !self.ended() {
-            let c = self
-                .source
-                .chars()
-                .nth(self.current)
-                .expect("Unexpected Program Exit");
-            let ch = String::from(c);
-            if num.contains(ch.as_str()) || alpha.contains(ch.as_str()) {
-                id.push(c);
-            } else {
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_alphanumeric() && c != '_' {
                 break;
             }
             self.advance();
         }
 
-        if keywords.contains_key(id.as_str()) {
-            let ttype = keywords[id.as_str()];
-            self.tokens.push(Token::new(&id, ttype, self.line));
-            return;
-        } else {
-            self.tokens
-                .push(Token::new(&id, TokenType::Identifier, self.line));
+        let id = &self.source[self.start.offset..self.current];
+        let token_type = keyword(id).unwrap_or(TokenType::Identifier);
+        self.add_token(token_type);
+    }
+}
+
+impl Iterator for Lexer {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.finished {
+            match self.peek() {
+                Some(c) => self.scan_token(c),
+                None => self.finish(),
+            }
         }
+        let mut result = self.pending.pop_front()?;
+        match &mut result {
+            Ok(token) => token.span.file = self.file,
+            Err(error) => error.span.file = self.file,
+        }
+        Some(result)
     }
+}
 
-    fn printTokens(&self) {
-        for token in self.tokens.iter() {
-            match token.token_type {
-                TokenType::Identifier => {
-                    println!(
-                        "{}: {}",
-                        token.token_type.to_string().green(),
-                        token.lexeme.green().bold()
-                    );
-                }
-                TokenType::NumberLiteral => {
-                    println!(
-                        "{}: {}",
-                        token.token_type.to_string().yellow(),
-                        token.lexeme.yellow().bold()
-                    );
-                }
-                TokenType::StringLiteral | TokenType::CharLiteral => {
-                    println!(
-                        "{}: {}",
-                        token.token_type.to_string().red(),
-                        token.lexeme.red().bold()
-                    );
-                }
-                TokenType::LeftBrace | TokenType::RightBrace => {
-                    println!(
-                        "{}: {}",
-                        token.token_type.to_string().yellow(),
-                        token.lexeme.yellow().bold()
-                    );
-                }
-                TokenType::LeftBracket | TokenType::RightBracket => {
-                    println!(
-                        "{}: {}",
-                        token.token_type.to_string().red(),
-                        token.lexeme.red().bold()
-                    );
-                }
-                TokenType::LeftParen | TokenType::RightParen => {
-                    println!(
-                        "{}: {}",
-                        token.token_type.to_string().green(),
-                        token.lexeme.green().bold()
-                    );
-                }
-                _ => {
-                    println!(
-                        "{}: {}",
-                        token.token_type.to_string().purple(),
-                        token.lexeme.purple().bold()
-                    );
-                }
+/// Maps a keyword to its `TokenType`, `None` for any other identifier.
+fn keyword(id: &str) -> Option<TokenType> {
+    let token_type = match id {
+        "void" => TokenType::Void,
+        "main" => TokenType::Main,
+        "let" => TokenType::Let,
+        "const" => TokenType::Const,
+        "if" => TokenType::If,
+        "else" => TokenType::Else,
+        "while" => TokenType::While,
+        "return" => TokenType::Return,
+        "break" => TokenType::Break,
+        "continue" => TokenType::Continue,
+        "in" => TokenType::In,
+        "match" => TokenType::Match,
+        "struct" => TokenType::Struct,
+        "enum" => TokenType::Enum,
+        "impl" => TokenType::Impl,
+        "true" => TokenType::True,
+        "false" => TokenType::False,
+        "goto" => TokenType::Goto,
+        "label" => TokenType::Label,
+        "mut" => TokenType::Mut,
+        "pub" => TokenType::Pub,
+        "import" => TokenType::Import,
+        "as" => TokenType::As,
+        "and" => TokenType::And,
+        "or" => TokenType::Or,
+        "not" => TokenType::Not,
+        "i8" => TokenType::I8,
+        "i16" => TokenType::I16,
+        "i32" => TokenType::I32,
+        "i64" => TokenType::I64,
+        "f32" => TokenType::F32,
+        "f64" => TokenType::F64,
+        "u8" => TokenType::U8,
+        "u16" => TokenType::U16,
+        "u32" => TokenType::U32,
+        "u64" => TokenType::U64,
+        "bool" => TokenType::Bool,
+        "byte" => TokenType::Byte,
+        "char" => TokenType::Char,
+        "String" => TokenType::String,
+        _ => return None,
+    };
+    Some(token_type)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    /// A character that can not start any token.
+    UnexpectedCharacter(char),
+    /// A `"` without its closing `"`.
+    UnterminatedString,
+    /// A `'` without its closing `'`.
+    UnterminatedChar,
+    /// `''`
+    EmptyChar,
+    /// A `\` followed by a character that is not a known escape.
+    UnknownEscape(char),
+    /// A number such as `1.2.3` or `0x`.
+    MalformedNumber(String),
+    /// A number ending in something other than a type suffix, as in `1.5u8` or `10px`.
+    InvalidSuffix(String),
+    /// An integer that does not fit in 64 bits.
+    IntegerTooLarge(String),
+    /// A `/*` without its closing `*/`.
+    UnterminatedBlockComment,
+    /// A `\x` or `\u` escape with missing or out of range digits, such as `\x4` or `\u{D800}`.
+    MalformedEscape(String),
+    /// A non-ASCII character in a `byte` literal or byte string.
+    NonAsciiByte(char),
+    /// A `}` in a string that does not close an interpolation.
+    UnmatchedStringBrace,
+}
+
+impl std::fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character `{}`", c),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::UnterminatedChar => write!(f, "unterminated character literal"),
+            LexErrorKind::EmptyChar => write!(f, "empty character literal"),
+            LexErrorKind::UnknownEscape(c) => write!(f, "unknown escape sequence `\\{}`", c),
+            LexErrorKind::MalformedNumber(n) => write!(f, "malformed number literal `{}`", n),
+            LexErrorKind::UnterminatedBlockComment => write!(f, "unterminated block comment"),
+            LexErrorKind::InvalidSuffix(suffix) => {
+                write!(f, "invalid suffix `{}` for number literal", suffix)
+            }
+            LexErrorKind::IntegerTooLarge(n) => write!(f, "integer literal `{}` is too large", n),
+            LexErrorKind::MalformedEscape(text) => {
+                write!(f, "malformed escape sequence `{}`", text)
+            }
+            LexErrorKind::NonAsciiByte(c) => {
+                write!(f, "non-ASCII character `{}` in byte literal", c)
             }
+            LexErrorKind::UnmatchedStringBrace => write!(f, "unmatched `}}` in string literal"),
         }
     }
 }
 
-#[derive(Debug)]
+impl LexErrorKind {
+    /// The error code reported with the `Diagnostic`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexErrorKind::UnexpectedCharacter(_) => "E0001",
+            LexErrorKind::UnterminatedString => "E0002",
+            LexErrorKind::UnterminatedChar => "E0003",
+            LexErrorKind::EmptyChar => "E0004",
+            LexErrorKind::UnknownEscape(_) => "E0005",
+            LexErrorKind::MalformedNumber(_) => "E0006",
+            LexErrorKind::UnterminatedBlockComment => "E0007",
+            LexErrorKind::InvalidSuffix(_) => "E0008",
+            LexErrorKind::IntegerTooLarge(_) => "E0009",
+            LexErrorKind::MalformedEscape(_) => "E0010",
+            LexErrorKind::NonAsciiByte(_) => "E0011",
+            LexErrorKind::UnmatchedStringBrace => "E0012",
+        }
+    }
+}
+
+impl ToDiagnostic for LexError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let label = match &self.kind {
+            LexErrorKind::UnexpectedCharacter(_) => "unexpected character",
+            LexErrorKind::UnterminatedString => "string literal is never closed",
+            LexErrorKind::UnterminatedChar => "character literal is never closed",
+            LexErrorKind::EmptyChar => "expected a character between the quotes",
+            LexErrorKind::UnknownEscape(_) => "unknown escape",
+            LexErrorKind::MalformedNumber(_) => "malformed number",
+            LexErrorKind::UnterminatedBlockComment => "comment is never closed",
+            LexErrorKind::InvalidSuffix(_) => "invalid suffix",
+            LexErrorKind::IntegerTooLarge(_) => "does not fit in 64 bits",
+            LexErrorKind::MalformedEscape(_) => "malformed escape",
+            LexErrorKind::NonAsciiByte(_) => "must be ASCII",
+            LexErrorKind::UnmatchedStringBrace => "unmatched `}`",
+        };
+        let diagnostic = Diagnostic::error(self.kind.to_string())
+            .with_code(self.kind.code())
+            .with_label(Label::primary(self.span, label));
+
+        match &self.kind {
+            LexErrorKind::UnterminatedBlockComment => {
+                diagnostic.with_note("block comments nest, every `/*` needs its own `*/`")
+            }
+            LexErrorKind::UnknownEscape(_) => diagnostic.with_help(
+                "valid escapes are `\\t`, `\\n`, `\\r`, `\\0`, `\\\\`, `\\'`, `\\\"`, `\\xNN` and `\\u{XXXX}`",
+            ),
+            LexErrorKind::MalformedEscape(text) if text.starts_with("\\x") => diagnostic.with_help(
+                "`\\x` takes two hex digits, up to `\\x7F` or up to `\\xFF` in byte literals",
+            ),
+            LexErrorKind::MalformedEscape(_) => diagnostic.with_help(
+                "`\\u{XXXX}` takes 1 to 6 hex digits naming a unicode scalar value, and is not allowed in byte literals",
+            ),
+            LexErrorKind::NonAsciiByte(_) => {
+                diagnostic.with_help("use a `\\xNN` escape for bytes above `\\x7F`")
+            }
+            LexErrorKind::UnmatchedStringBrace => {
+                diagnostic.with_help("use `}}` for a literal `}` and `{{` for a literal `{`")
+            }
+            LexErrorKind::EmptyChar => diagnostic.with_help("use `' '` for a space"),
+            LexErrorKind::InvalidSuffix(_) => diagnostic.with_help(
+                "integers take `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32` or `u64`, floats take `f32` or `f64`",
+            ),
+            LexErrorKind::IntegerTooLarge(_) => {
+                diagnostic.with_note("the largest integer literal is `18446744073709551615`")
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.span.line)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+#[derive(Debug, Clone)]
 pub struct Token {
-    lexeme: String,
-    token_type: TokenType,
-    pub line: usize,
+    pub lexeme: String,
+    pub token_type: TokenType,
+    pub span: Span,
+    /// Source text and trivia, only set by a lossless `Lexer`.
+    pub lossless: Option<Box<Lossless>>,
 }
 
 impl Token {
-    pub fn new(lexeme: &str, token_type: TokenType, line: usize) -> Self {
+    pub fn new(lexeme: &str, token_type: TokenType, span: Span) -> Self {
         let lexeme = lexeme.to_string();
 
         Self {
             lexeme,
             token_type,
-            line,
+            span,
+            lossless: None,
         }
     }
 }
@@ -592,7 +1175,7 @@ pub enum TokenType {
     RightAssign,
     /// `|`
     Pipe,
-    /// **
+    /// `**`
     Power,
     /// `^`
     Caret,
@@ -602,14 +1185,64 @@ pub enum TokenType {
     LeftShift,
     /// `>>`
     RightShift,
+    /// `!=`
+    BangEqual,
+    /// `&&`
+    AmpersandAmpersand,
+    /// `||`
+    PipePipe,
+    /// `+=`
+    PlusEqual,
+    /// `-=`
+    MinusEqual,
+    /// `*=`
+    StarEqual,
+    /// `/=`
+    SlashEqual,
+    /// `%=`
+    PercentEqual,
+    /// `**=`
+    PowerEqual,
+    /// `<<=`
+    LeftShiftEqual,
+    /// `>>=`
+    RightShiftEqual,
+    /// `::`
+    ColonColon,
+    /// `..`
+    DotDot,
+    /// `..=`
+    DotDotEqual,
+    /// `~`
+    Tilde,
+    /// `@`
+    At,
 
     /// Literals
     /// `"string"`
     StringLiteral,
     /// `'c'`
     CharLiteral,
-    /// `12345`
-    NumberLiteral,
+    /// `b'c'`
+    ByteLiteral,
+    /// `b"bytes"`
+    ByteStringLiteral,
+    /// The `"` opening an interpolated string such as `"total: {a + b}"`
+    StringStart,
+    /// Text of an interpolated string, `total: `
+    StringPart,
+    /// `{` starting an expression in an interpolated string
+    InterpolationStart,
+    /// `}` ending an expression in an interpolated string
+    InterpolationEnd,
+    /// The `"` closing an interpolated string
+    StringEnd,
+    /// `12345`, `0xFF`, `255u8`
+    IntLiteral,
+    /// `1.5`, `2e10`, `1.5f32`
+    FloatLiteral,
+    /// `/// docs` or `//! docs`
+    DocComment,
 
     /// Keywords
     /// `void`
@@ -654,6 +1287,10 @@ pub enum TokenType {
     Mut,
     ///  `pub`
     Pub,
+    /// `import`
+    Import,
+    /// `as`
+    As,
     /// `and`
     And,
     /// `or`
@@ -730,9 +1367,34 @@ impl std::fmt::Display for TokenType {
             TokenType::Ampersand => write!(f, "Ampersand"),
             TokenType::LeftShift => write!(f, "LeftShift"),
             TokenType::RightShift => write!(f, "RightShift"),
+            TokenType::BangEqual => write!(f, "BangEqual"),
+            TokenType::AmpersandAmpersand => write!(f, "AmpersandAmpersand"),
+            TokenType::PipePipe => write!(f, "PipePipe"),
+            TokenType::PlusEqual => write!(f, "PlusEqual"),
+            TokenType::MinusEqual => write!(f, "MinusEqual"),
+            TokenType::StarEqual => write!(f, "StarEqual"),
+            TokenType::SlashEqual => write!(f, "SlashEqual"),
+            TokenType::PercentEqual => write!(f, "PercentEqual"),
+            TokenType::PowerEqual => write!(f, "PowerEqual"),
+            TokenType::LeftShiftEqual => write!(f, "LeftShiftEqual"),
+            TokenType::RightShiftEqual => write!(f, "RightShiftEqual"),
+            TokenType::ColonColon => write!(f, "ColonColon"),
+            TokenType::DotDot => write!(f, "DotDot"),
+            TokenType::DotDotEqual => write!(f, "DotDotEqual"),
+            TokenType::Tilde => write!(f, "Tilde"),
+            TokenType::At => write!(f, "At"),
             TokenType::StringLiteral => write!(f, "StringLiteral"),
             TokenType::CharLiteral => write!(f, "CharLiteral"),
-            TokenType::NumberLiteral => write!(f, "NumberLiteral"),
+            TokenType::ByteLiteral => write!(f, "ByteLiteral"),
+            TokenType::ByteStringLiteral => write!(f, "ByteStringLiteral"),
+            TokenType::StringStart => write!(f, "StringStart"),
+            TokenType::StringPart => write!(f, "StringPart"),
+            TokenType::InterpolationStart => write!(f, "InterpolationStart"),
+            TokenType::InterpolationEnd => write!(f, "InterpolationEnd"),
+            TokenType::StringEnd => write!(f, "StringEnd"),
+            TokenType::IntLiteral => write!(f, "IntLiteral"),
+            TokenType::FloatLiteral => write!(f, "FloatLiteral"),
+            TokenType::DocComment => write!(f, "DocComment"),
             TokenType::Void => write!(f, "Void"),
             TokenType::Main => write!(f, "Main"),
             TokenType::Let => write!(f, "Let"),
@@ -754,6 +1416,8 @@ impl std::fmt::Display for TokenType {
             TokenType::Label => write!(f, "Label"),
             TokenType::Mut => write!(f, "Mut"),
             TokenType::Pub => write!(f, "Pub"),
+            TokenType::Import => write!(f, "Import"),
+            TokenType::As => write!(f, "As"),
             TokenType::And => write!(f, "And"),
             TokenType::Or => write!(f, "Or"),
             TokenType::Not => write!(f, "Not"),
@@ -0,0 +1,91 @@
+/// Location of a token in the source.
+///
+/// `start` and `end` are byte offsets, `end` being exclusive. `line` and `column` are 1-based
+/// and point at `start`. `file` is the index of the module of the program the source is from,
+/// 0 for the file the program starts from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub file: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+            file: 0,
+        }
+    }
+
+    /// The span from the start of `self` to the end of `end`.
+    pub fn to(self, end: Span) -> Span {
+        Span {
+            end: end.end,
+            ..self
+        }
+    }
+}
+
+/// Maps byte offsets of a source file back to lines, columns and text.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    source: String,
+    /// Byte offset of the first character of every line.
+    line_starts: Vec<usize>,
+}
+
+impl SourceMap {
+    pub fn new(source: String) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Converts a byte offset to its 1-based line and column, the column counted in characters.
+    ///
+    /// Offsets past the end of the source map to the end of the last line.
+    pub fn location(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = match self.source.get(line_start..offset) {
+            Some(text) => text.chars().count() + 1,
+            None => offset - line_start + 1,
+        };
+        (line, column)
+    }
+
+    /// The source text covered by `span`.
+    pub fn slice(&self, span: Span) -> &str {
+        let end = span.end.min(self.source.len());
+        self.source
+            .get(span.start.min(end)..end)
+            .unwrap_or_default()
+    }
+
+    /// The text of the 1-based `line`, without its line terminator.
+    pub fn line_text(&self, line: usize) -> &str {
+        let Some(&start) = self.line_starts.get(line.wrapping_sub(1)) else {
+            return "";
+        };
+        let end = self
+            .line_starts
+            .get(line)
+            .map(|&next| next - 1)
+            .unwrap_or(self.source.len());
+        self.source[start..end].trim_end_matches('\r')
+    }
+}
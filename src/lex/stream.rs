@@ -0,0 +1,42 @@
+use super::{LexError, Lexer, Token};
+use std::collections::VecDeque;
+
+/// Pulls tokens from a `Lexer` on demand, with lookahead.
+///
+/// Only the tokens peeked at and not consumed yet are kept in memory.
+#[derive(Debug)]
+pub struct TokenStream {
+    lexer: Lexer,
+    /// Tokens and errors peeked at, the next one first.
+    buffer: VecDeque<Result<Token, LexError>>,
+}
+
+impl TokenStream {
+    pub fn new(lexer: Lexer) -> Self {
+        Self {
+            lexer,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next token or error without consuming it.
+    pub fn peek(&mut self) -> Option<&Result<Token, LexError>> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the token or error `n` places ahead without consuming anything, `None` past `Eof`.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&Result<Token, LexError>> {
+        while self.buffer.len() <= n {
+            self.buffer.push_back(self.lexer.next()?);
+        }
+        self.buffer.get(n)
+    }
+}
+
+impl Iterator for TokenStream {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.pop_front().or_else(|| self.lexer.next())
+    }
+}
@@ -0,0 +1,124 @@
+use super::Token;
+
+/// Source text between tokens, kept by a lossless `Lexer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+    /// A run of spaces, tabs and lone `\r`.
+    Whitespace,
+    /// `\n` or `\r\n`.
+    Newline,
+    /// `// comment`, without the line terminator. `///` and `//!` are `DocComment` tokens.
+    LineComment,
+    /// `/* comment */`, possibly nested.
+    BlockComment,
+    /// Text that is not part of any token because of a `LexError`, such as `$`.
+    Skipped,
+}
+
+/// What a token looks like in the source, kept by a lossless `Lexer`.
+///
+/// Trailing trivia is everything after the token up to the end of its line, the line terminator
+/// itself starts the leading trivia of the next token.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Lossless {
+    pub leading: Vec<Trivia>,
+    /// The source text of the token, `lexeme` being the cooked value for literals.
+    pub text: String,
+    pub trailing: Vec<Trivia>,
+}
+
+/// Concatenates `tokens` and their trivia, which gives back the source byte for byte when the
+/// tokens come from a lossless `Lexer`.
+pub fn source_text(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for lossless in tokens.iter().filter_map(|token| token.lossless.as_deref()) {
+        for trivia in lossless.leading.iter() {
+            out.push_str(&trivia.text);
+        }
+        out.push_str(&lossless.text);
+        for trivia in lossless.trailing.iter() {
+            out.push_str(&trivia.text);
+        }
+    }
+    out
+}
+
+/// Splits the text between two tokens into its trivia.
+pub(super) fn split(text: &str) -> Vec<Trivia> {
+    let mut pieces: Vec<Trivia> = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let (kind, len) = if rest.starts_with('\n') {
+            (TriviaKind::Newline, 1)
+        } else if rest.starts_with("\r\n") {
+            (TriviaKind::Newline, 2)
+        } else if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let end = if rest[..end].ends_with('\r') {
+                end - 1
+            } else {
+                end
+            };
+            (TriviaKind::LineComment, end)
+        } else if rest.starts_with("/*") {
+            (TriviaKind::BlockComment, block_comment_len(rest))
+        } else if starts_whitespace(rest) {
+            let mut len = 0;
+            while starts_whitespace(&rest[len..]) {
+                len += 1;
+            }
+            (TriviaKind::Whitespace, len)
+        } else {
+            let len = rest.chars().next().map_or(1, char::len_utf8);
+            (TriviaKind::Skipped, len)
+        };
+
+        let (piece, tail) = rest.split_at(len);
+        match pieces.last_mut() {
+            Some(last) if kind == TriviaKind::Skipped && last.kind == TriviaKind::Skipped => {
+                last.text.push_str(piece);
+            }
+            _ => pieces.push(Trivia {
+                kind,
+                text: piece.to_string(),
+            }),
+        }
+        rest = tail;
+    }
+
+    pieces
+}
+
+/// Checks for a space, a tab or a `\r` that does not start a `\r\n`.
+fn starts_whitespace(text: &str) -> bool {
+    text.starts_with([' ', '\t']) || (text.starts_with('\r') && !text.starts_with("\r\n"))
+}
+
+/// Length of the nested `/* */` comment `text` starts with, all of `text` when unterminated.
+fn block_comment_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"/*") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b"*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    text.len()
+}
@@ -0,0 +1,9 @@
+pub mod error;
+pub mod eval;
+pub mod format;
+mod json;
+pub mod lex;
+pub mod load;
+pub mod sem;
+pub mod syn;
+pub mod vm;
@@ -0,0 +1,341 @@
+//! Loading a program from the file it starts from and the files its `import`s name.
+
+use crate::error::{Diagnostic, Label, ToDiagnostic};
+use crate::lex::{Lexer, SourceMap, Span, TokenStream};
+use crate::syn::ast::{ImportDecl, ProgramFile};
+use crate::syn::Parser;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The extension of the source files.
+pub const EXTENSION: &str = "vy";
+
+/// The modules of a program, the one it starts from first.
+///
+/// A module is a file. Its index is the `file` of the spans of its source.
+#[derive(Debug)]
+pub struct Program {
+    pub files: Vec<SourceFile>,
+    pub modules: Vec<Module>,
+}
+
+/// A file read by the `Loader`.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// The path of the file as named in the errors.
+    pub name: String,
+    pub source: SourceMap,
+}
+
+#[derive(Debug)]
+pub struct Module {
+    /// The path of the module from the project root, as `math.vector` for `math/vector.vy`.
+    /// Empty for the module the program starts from.
+    pub path: String,
+    pub program: ProgramFile,
+    /// The module each of the `imports` of `program` refers to.
+    pub imports: Vec<usize>,
+}
+
+/// Reads and parses the modules of a program, each once however many modules import it.
+///
+/// `import math.vector;` in `dir/main.vy` loads `dir/math/vector.vy`, or when there is no such
+/// file `math/vector.vy` in the project root.
+#[derive(Debug)]
+pub struct Loader {
+    /// The directory of the file the program starts from.
+    root: PathBuf,
+    files: Vec<SourceFile>,
+    /// By the index of their file, `None` until the module is parsed and its imports loaded.
+    modules: Vec<Option<Module>>,
+    /// The index of every file read, by its canonical path.
+    cache: HashMap<PathBuf, usize>,
+    /// The modules whose imports are being loaded, each imported by the one before it.
+    stack: Vec<usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Loader {
+    /// `root` is the project root, the modules not found next to the file importing them are
+    /// looked for in it.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            files: Vec::new(),
+            modules: Vec::new(),
+            cache: HashMap::new(),
+            stack: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Loads the program starting from `source`, the content of the file `name`, and every
+    /// module it imports. `path` is where the file is, `None` when it was read from stdin.
+    ///
+    /// Stops at the first file that can not be lexed or parsed, but reports every import that
+    /// can not be loaded.
+    pub fn load(
+        &mut self,
+        name: String,
+        path: Option<&Path>,
+        source: String,
+    ) -> Result<(), Vec<Diagnostic>> {
+        let path = path.and_then(|path| path.canonicalize().ok());
+        self.module(name, String::new(), path, source);
+        if self.diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.diagnostics))
+        }
+    }
+
+    /// The files read so far, by the `file` of their spans, to report the errors of `load`.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// The program loaded by a successful `load`.
+    pub fn finish(self) -> Program {
+        Program {
+            files: self.files,
+            modules: self
+                .modules
+                .into_iter()
+                .map(|module| module.expect("every module was loaded"))
+                .collect(),
+        }
+    }
+
+    /// Parses `source` as the module `module_path` and loads its imports, returning the index
+    /// of its file. `path` is its canonical path.
+    fn module(
+        &mut self,
+        name: String,
+        module_path: String,
+        path: Option<PathBuf>,
+        source: String,
+    ) -> Option<usize> {
+        let file = self.files.len();
+        self.files.push(SourceFile {
+            name,
+            source: SourceMap::new(source.clone()),
+        });
+        self.modules.push(None);
+        if let Some(path) = &path {
+            self.cache.insert(path.clone(), file);
+        }
+
+        let tokens = TokenStream::new(Lexer::new(source).file(file));
+        let program = match Parser::new(tokens).parse() {
+            Ok(program) => program,
+            Err(diagnostics) => {
+                self.diagnostics.extend(diagnostics);
+                return None;
+            }
+        };
+
+        // The imports are looked for next to the file as it is named, so that they are named
+        // from the same directory in the errors.
+        let dir = match path {
+            Some(_) => Path::new(&self.files[file].name)
+                .parent()
+                .map(Path::to_path_buf),
+            None => None,
+        };
+        self.stack.push(file);
+        let mut imports = Vec::new();
+        for import in &program.imports {
+            if let Some(module) = self.import(dir.as_deref(), import) {
+                imports.push(module);
+            }
+        }
+        self.stack.pop();
+
+        if imports.len() < program.imports.len() {
+            return None;
+        }
+        self.modules[file] = Some(Module {
+            path: module_path,
+            program,
+            imports,
+        });
+        Some(file)
+    }
+
+    /// Loads the module `import` names, if it is not loaded yet, returning the index of its
+    /// file. `dir` is the directory of the importing file.
+    fn import(&mut self, dir: Option<&Path>, import: &ImportDecl) -> Option<usize> {
+        let relative = import
+            .path
+            .iter()
+            .collect::<PathBuf>()
+            .with_extension(EXTENSION);
+        let mut searched = Vec::new();
+        for dir in dir.into_iter().chain([self.root.as_path()]) {
+            let candidate = dir.join(&relative);
+            if !searched.contains(&candidate) {
+                searched.push(candidate);
+            }
+        }
+        let Some((found, path)) = searched.iter().find_map(|candidate| {
+            let path = candidate.canonicalize().ok()?;
+            path.is_file().then_some((candidate, path))
+        }) else {
+            self.error(
+                ImportErrorKind::NotFound {
+                    module: import.path.join("."),
+                    searched: searched
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect(),
+                },
+                import.span,
+            );
+            return None;
+        };
+
+        if let Some(&file) = self.cache.get(&path) {
+            if let Some(position) = self.stack.iter().position(|&module| module == file) {
+                let mut cycle = self.stack[position..]
+                    .iter()
+                    .map(|&module| self.files[module].name.clone())
+                    .collect::<Vec<_>>();
+                cycle.push(self.files[file].name.clone());
+                self.error(ImportErrorKind::Cycle(cycle), import.span);
+                return None;
+            }
+            // Loaded already, or failed to and reported.
+            return self.modules[file].is_some().then_some(file);
+        }
+
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) => {
+                self.error(
+                    ImportErrorKind::Unreadable {
+                        file: found.display().to_string(),
+                        error: error.to_string(),
+                    },
+                    import.span,
+                );
+                return None;
+            }
+        };
+        let module_path = self.module_path(&path, import);
+        self.module(found.display().to_string(), module_path, Some(path), source)
+    }
+
+    /// The path of the module at `path` from the project root, or as `import` writes it when
+    /// the file is not under the root.
+    fn module_path(&self, path: &Path, import: &ImportDecl) -> String {
+        // An empty root is the current directory.
+        let root = Path::new(".").join(&self.root);
+        let root = root.canonicalize().unwrap_or(root);
+        match path.with_extension("").strip_prefix(&root) {
+            Ok(relative) => relative
+                .iter()
+                .map(|part| part.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("."),
+            Err(_) => import.path.join("."),
+        }
+    }
+
+    fn error(&mut self, kind: ImportErrorKind, span: Span) {
+        self.diagnostics
+            .push(ImportError { kind, span }.to_diagnostic());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportError {
+    pub kind: ImportErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportErrorKind {
+    /// No file for `module` in the directories `searched`.
+    NotFound {
+        module: String,
+        searched: Vec<String>,
+    },
+    Unreadable {
+        file: String,
+        error: String,
+    },
+    /// The files of a cycle of imports, each importing the next, the last being the first.
+    Cycle(Vec<String>),
+}
+
+impl std::fmt::Display for ImportErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ImportErrorKind::NotFound { module, .. } => {
+                write!(f, "cannot find module `{}`", module)
+            }
+            ImportErrorKind::Unreadable { file, error } => {
+                write!(f, "could not read `{}`: {}", file, error)
+            }
+            ImportErrorKind::Cycle(cycle) => match cycle.as_slice() {
+                [file, _] => write!(f, "`{}` imports itself", file),
+                _ => write!(f, "cycle of imports through `{}`", cycle[0]),
+            },
+        }
+    }
+}
+
+impl ImportErrorKind {
+    /// The error code reported with the `Diagnostic`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ImportErrorKind::NotFound { .. } => "E0700",
+            ImportErrorKind::Unreadable { .. } => "E0701",
+            ImportErrorKind::Cycle(_) => "E0702",
+        }
+    }
+}
+
+impl ToDiagnostic for ImportError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let label = match &self.kind {
+            ImportErrorKind::NotFound { .. } => "no such file",
+            ImportErrorKind::Unreadable { .. } => "imported here",
+            ImportErrorKind::Cycle(_) => "closes the cycle",
+        };
+        let mut diagnostic = Diagnostic::error(self.kind.to_string())
+            .with_code(self.kind.code())
+            .with_label(Label::primary(self.span, label));
+
+        match &self.kind {
+            ImportErrorKind::NotFound { searched, .. } => {
+                let searched = searched
+                    .iter()
+                    .map(|path| format!("`{}`", path))
+                    .collect::<Vec<_>>()
+                    .join(" and ");
+                diagnostic = diagnostic.with_note(format!("looked for {}", searched));
+            }
+            ImportErrorKind::Unreadable { .. } => {}
+            ImportErrorKind::Cycle(cycle) if cycle.len() == 2 => {
+                diagnostic = diagnostic.with_help("remove the import, a module sees its own items");
+            }
+            ImportErrorKind::Cycle(cycle) => {
+                for pair in cycle.windows(2) {
+                    diagnostic =
+                        diagnostic.with_note(format!("`{}` imports `{}`", pair[0], pair[1]));
+                }
+                diagnostic = diagnostic.with_help(
+                    "move what the modules share into a module that imports neither of them",
+                );
+            }
+        }
+        diagnostic
+    }
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.span.line)
+    }
+}
@@ -1,34 +1,427 @@
-mod lex;
-mod error;
-use lex::Lexer;
-use std::result::Result;
-use std::{env, process::exit};
+mod cli;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = env::args().collect::<Vec<String>>();
-    if args.len() > 2 {
-        println!("Too many arguments");
-        println!("Usage vid <path>/file.vy");
-        exit(1);
+use cli::{Cli, Color, Command};
+use std::io::{BufWriter, ErrorKind, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use vidyut::error::{Diagnostic, Emitter, ToDiagnostic};
+use vidyut::eval::{Interpreter, RuntimeError, RuntimeErrorKind, STACK_SIZE};
+use vidyut::format::format_source;
+use vidyut::lex::{Lexer, SourceMap, TokenFormat, TokenStream, TokenWriter};
+use vidyut::load::{Loader, SourceFile};
+use vidyut::sem::{check_mutability, check_types, link, resolve, unused_exports};
+use vidyut::syn::ast::ProgramFile;
+use vidyut::syn::{write_tree, Parser};
+use vidyut::vm::{self, Module, Vm};
+
+/// Exit status when the program has errors or `fmt --check` finds unformatted files.
+const EXIT_ERRORS: i32 = 1;
+/// Exit status for an invalid command line.
+const EXIT_USAGE: i32 = 2;
+/// Exit status when a file can not be read or written.
+const EXIT_IO: i32 = 3;
+
+fn main() {
+    let cli = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            eprintln!("Run `vid help` for usage.");
+            exit(EXIT_USAGE);
+        }
+    };
+
+    let color = match cli.color {
+        Color::Always => true,
+        Color::Never => false,
+        Color::Auto => {
+            std::io::stdout().is_terminal()
+                && std::io::stderr().is_terminal()
+                && std::env::var_os("NO_COLOR").is_none()
+        }
+    };
+    colored::control::set_override(color);
+
+    if let Err(status) = execute(&cli) {
+        exit(status);
     }
-    if args.len() < 2 {
-        help();
-        exit(1);
+}
+
+/// Runs the command of `cli`, returning the exit status on failure.
+fn execute(cli: &Cli) -> Result<(), i32> {
+    match &cli.command {
+        Command::Lex { file, format } => lex(cli, file, *format)?,
+        Command::Parse { file } => {
+            let input = Input::read(cli, file)?;
+            let program = input.parse()?;
+            // A closed stdout, as with `vid parse file.vy | head`, is not an error.
+            let mut out = BufWriter::new(std::io::stdout().lock());
+            let _ = write_tree(&mut out, &program).and_then(|()| out.flush());
+        }
+        Command::Check { file } => {
+            let mut input = Input::read(cli, file)?;
+            input.check()?;
+        }
+        Command::Run { file, vm } => {
+            let (input, result) = if is_compiled(file) {
+                let (input, module) = Input::load(cli, file)?;
+                let result = run_vm(&module);
+                (input, result)
+            } else {
+                let mut input = Input::read(cli, file)?;
+                let program = input.check()?;
+                let result = if *vm {
+                    run_vm(&input.compile(&program)?)
+                } else {
+                    run(&program)
+                };
+                (input, result)
+            };
+            match result {
+                Ok(()) => {}
+                // The output was closed early, as with `vid run file.vy | head`.
+                Err(RuntimeError {
+                    kind: RuntimeErrorKind::Output(ErrorKind::BrokenPipe),
+                    ..
+                }) => {}
+                Err(error) => {
+                    input.report(&[error.to_diagnostic()]);
+                    return Err(EXIT_ERRORS);
+                }
+            }
+        }
+        Command::Disasm { file } => {
+            let module = if is_compiled(file) {
+                Input::load(cli, file)?.1
+            } else {
+                let mut input = Input::read(cli, file)?;
+                let program = input.check()?;
+                input.compile(&program)?
+            };
+            let _ = write!(std::io::stdout(), "{}", module);
+        }
+        Command::Build { file, output } => build(cli, file, output.as_deref())?,
+        Command::Fmt { files, check } => fmt(cli, files, *check)?,
+        Command::Help { command } => print!("{}", cli::help(command.as_deref())),
+        Command::Version => println!("vid {}", env!("CARGO_PKG_VERSION")),
+        Command::Usage => {
+            eprint!("{}", cli::help(None));
+            return Err(EXIT_USAGE);
+        }
     }
+    Ok(())
+}
+
+/// Interprets `program` on a thread with a stack deep enough for its calls.
+fn run(program: &ProgramFile) -> Result<(), RuntimeError> {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || {
+                let out = BufWriter::new(std::io::stdout().lock());
+                Interpreter::new(program, out).run()
+            })
+            .expect("failed to spawn the interpreter thread")
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
 
-    let file = &args[1];
+/// Runs `module` on the `Vm`.
+fn run_vm(module: &Module) -> Result<(), RuntimeError> {
+    let out = BufWriter::new(std::io::stdout().lock());
+    Vm::new(module, out).run()
+}
+
+/// Whether `file` is bytecode written by `vid build` rather than source.
+fn is_compiled(file: &str) -> bool {
+    Path::new(file)
+        .extension()
+        .is_some_and(|extension| extension == "vyc")
+}
 
-    let content = std::fs::read_to_string(file)?;
+/// `vid build [-o <out>] <file>` compiles the program to a `.vyc` file, next to it unless
+/// `out` is given, `-` being stdout.
+fn build(cli: &Cli, file: &str, out: Option<&str>) -> Result<(), i32> {
+    let mut input = Input::read(cli, file)?;
+    let program = input.check()?;
+    let bytes = input.compile(&program)?.to_vyc();
+
+    let out = out.map_or_else(|| Path::new(file).with_extension("vyc"), PathBuf::from);
+    let written = if out == Path::new("-") {
+        std::io::stdout().lock().write_all(&bytes)
+    } else {
+        std::fs::write(&out, bytes)
+    };
+    match written {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == ErrorKind::BrokenPipe => Ok(()),
+        Err(error) => {
+            input.report(&[Diagnostic::error(format!(
+                "could not write `{}`: {}",
+                out.display(),
+                error
+            ))]);
+            Err(EXIT_IO)
+        }
+    }
+}
 
-    let mut lexer = Lexer::new(content);
-    lexer.parse();
+/// `vid lex [--format=pretty|json|sexp] <file>` prints the tokens as they are lexed, then
+/// reports the lexical errors.
+fn lex(cli: &Cli, file: &str, format: TokenFormat) -> Result<(), i32> {
+    let input = Input::read(cli, file)?;
+    let mut writer = TokenWriter::new(std::io::stdout().lock(), format);
+    let mut diagnostics = Vec::new();
+    for result in Lexer::new(input.source.source().to_string()) {
+        let written = match result {
+            Ok(token) => writer.write(&token),
+            Err(error) => {
+                diagnostics.push(error.to_diagnostic());
+                Ok(())
+            }
+        };
+        match written {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::BrokenPipe => return Ok(()),
+            Err(error) => {
+                input.report(&[Diagnostic::error(format!(
+                    "could not write tokens: {}",
+                    error
+                ))]);
+                return Err(EXIT_IO);
+            }
+        }
+    }
+    let _ = writer.finish();
 
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        input.report(&diagnostics);
+        Err(EXIT_ERRORS)
+    }
+}
 
+/// `vid fmt [--check] <files>...` formats the files in place, or stdin to stdout for `-`.
+/// With `--check` nothing is written and the files that are not formatted are listed.
+fn fmt(cli: &Cli, files: &[String], check: bool) -> Result<(), i32> {
+    let mut status = Ok(());
+    for file in files {
+        let input = Input::read(cli, file)?;
+        let formatted = match format_source(input.source.source()) {
+            Ok(formatted) => formatted,
+            Err(diagnostics) => {
+                input.report(&diagnostics);
+                status = Err(EXIT_ERRORS);
+                continue;
+            }
+        };
 
-    println!("{:?}", lexer.tokens);
-    Ok(())
+        if check {
+            if formatted != input.source.source() {
+                println!("Would reformat {}", input.name);
+                status = Err(EXIT_ERRORS);
+            }
+        } else if file == "-" {
+            print!("{}", formatted);
+        } else if formatted != input.source.source() {
+            if let Err(error) = std::fs::write(file, formatted) {
+                input.report(&[Diagnostic::error(format!(
+                    "could not write `{}`: {}",
+                    file, error
+                ))]);
+                return Err(EXIT_IO);
+            }
+        }
+    }
+    status
+}
+
+/// A program read from a file or from stdin.
+struct Input<'a> {
+    cli: &'a Cli,
+    name: String,
+    /// Where the file is, `None` for stdin.
+    path: Option<PathBuf>,
+    source: SourceMap,
+    /// The files the program imports, by the `file` of their spans from 1.
+    imported: Vec<SourceFile>,
+    /// Whether the program was loaded from a `.vyc` file, the source not being at hand.
+    compiled: bool,
 }
 
-fn help() {
-    println!("Help is here");
+impl<'a> Input<'a> {
+    /// Reads `file`, stdin for `-`.
+    fn read(cli: &'a Cli, file: &str) -> Result<Self, i32> {
+        let (name, path, content) = if file == "-" {
+            let mut content = String::new();
+            let result = std::io::stdin().read_to_string(&mut content);
+            (String::from("<stdin>"), None, result.map(|_| content))
+        } else {
+            let path = Some(PathBuf::from(file));
+            (file.to_string(), path, std::fs::read_to_string(file))
+        };
+
+        let mut input = Self {
+            cli,
+            name,
+            path,
+            source: SourceMap::new(String::new()),
+            imported: Vec::new(),
+            compiled: false,
+        };
+        match content {
+            Ok(content) => {
+                input.source = SourceMap::new(content);
+                Ok(input)
+            }
+            Err(error) => {
+                input.report(&[Diagnostic::error(format!(
+                    "could not read `{}`: {}",
+                    file, error
+                ))]);
+                Err(EXIT_IO)
+            }
+        }
+    }
+
+    /// Loads the bytecode `file` written by `vid build`. Its errors are reported with the names
+    /// of the sources it was compiled from.
+    fn load(cli: &'a Cli, file: &str) -> Result<(Self, Module), i32> {
+        let mut input = Self {
+            cli,
+            name: file.to_string(),
+            path: Some(PathBuf::from(file)),
+            source: SourceMap::new(String::new()),
+            imported: Vec::new(),
+            compiled: true,
+        };
+        let bytes = std::fs::read(file).map_err(|error| {
+            input.report(&[Diagnostic::error(format!(
+                "could not read `{}`: {}",
+                file, error
+            ))]);
+            EXIT_IO
+        })?;
+        let module = Module::from_vyc(&bytes).map_err(|error| {
+            let mut diagnostic = error.to_diagnostic();
+            diagnostic.message = format!("could not load `{}`: {}", file, diagnostic.message);
+            input.report(&[diagnostic]);
+            EXIT_ERRORS
+        })?;
+
+        if let Some((name, imported)) = module.files.split_first() {
+            input.name = name.clone();
+            input.imported = imported
+                .iter()
+                .map(|name| SourceFile {
+                    name: name.clone(),
+                    source: SourceMap::new(String::new()),
+                })
+                .collect();
+        }
+        Ok((input, module))
+    }
+
+    fn parse(&self) -> Result<ProgramFile, i32> {
+        let tokens = TokenStream::new(Lexer::new(self.source.source().to_string()));
+        Parser::new(tokens).parse().map_err(|diagnostics| {
+            self.report(&diagnostics);
+            EXIT_ERRORS
+        })
+    }
+
+    /// Loads the program and the modules it imports, resolves their names and checks their
+    /// assignments, then links them into one program and checks its types.
+    fn check(&mut self) -> Result<ProgramFile, i32> {
+        let root = match self.path.as_deref().and_then(Path::parent) {
+            Some(dir) => dir.to_path_buf(),
+            None => PathBuf::new(),
+        };
+        let mut loader = Loader::new(root);
+        let loaded = loader.load(
+            self.name.clone(),
+            self.path.as_deref(),
+            self.source.source().to_string(),
+        );
+        self.imported = loader.files().iter().skip(1).cloned().collect();
+        if let Err(diagnostics) = loaded {
+            self.report(&diagnostics);
+            return Err(EXIT_ERRORS);
+        }
+        let program = loader.finish();
+
+        let mut resolutions = Vec::new();
+        let mut errors = Vec::new();
+        for module in 0..program.modules.len() {
+            match resolve(&program, module) {
+                Ok(resolution) => resolutions.push(resolution),
+                Err(found) => errors.extend(found),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(self.report_errors(&errors));
+        }
+        let mut errors = Vec::new();
+        for (module, resolution) in program.modules.iter().zip(&resolutions) {
+            if let Err(found) = check_mutability(&module.program, resolution) {
+                errors.extend(found);
+            }
+        }
+        if !errors.is_empty() {
+            return Err(self.report_errors(&errors));
+        }
+        let warnings = unused_exports(&program, &resolutions)
+            .iter()
+            .map(ToDiagnostic::to_diagnostic)
+            .collect::<Vec<_>>();
+        self.report(&warnings);
+
+        let mut linked = link(&program, &resolutions);
+        check_types(&mut linked).map_err(|errors| self.report_errors(&errors))?;
+        Ok(linked)
+    }
+
+    /// Reports all of `errors`, giving the exit status for them.
+    fn report_errors(&self, errors: &[impl ToDiagnostic]) -> i32 {
+        let diagnostics = errors
+            .iter()
+            .map(ToDiagnostic::to_diagnostic)
+            .collect::<Vec<_>>();
+        self.report(&diagnostics);
+        EXIT_ERRORS
+    }
+
+    fn compile(&self, program: &ProgramFile) -> Result<Module, i32> {
+        let mut module = vm::compile(program).map_err(|error| {
+            self.report(&[error.to_diagnostic()]);
+            EXIT_ERRORS
+        })?;
+        module.files = std::iter::once(&self.name)
+            .chain(self.imported.iter().map(|file| &file.name))
+            .cloned()
+            .collect();
+        Ok(module)
+    }
+
+    /// Prints `diagnostics` to stderr in the `--error-format` asked for, each with the file
+    /// its primary label is in.
+    fn report(&self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics.iter() {
+            let file = diagnostic
+                .primary_label()
+                .or(diagnostic.labels.first())
+                .map_or(0, |label| label.span.file);
+            let (name, source) = match file.checked_sub(1).and_then(|i| self.imported.get(i)) {
+                Some(imported) => (&imported.name, &imported.source),
+                None => (&self.name, &self.source),
+            };
+            let mut emitter = Emitter::new(name, source).with_format(self.cli.error_format);
+            if self.compiled {
+                emitter = emitter.without_source();
+            }
+            emitter.emit(diagnostic);
+        }
+    }
 }
@@ -0,0 +1,293 @@
+use crate::eval::value::{int_range, is_float};
+use crate::syn::ast::*;
+use std::collections::HashMap;
+
+/// The type of an expression while the types of a program are inferred.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Ty {
+    Known(Type),
+    /// An array of elements of the type. Arrays are not written in types, so only the elements
+    /// of an array can be known.
+    Array(Box<Ty>),
+    /// A variable of the `Table`, for a type that is not known yet.
+    Var(usize),
+    /// A type that is not known and never will be, as the expression uses a part of the
+    /// language that does not run yet or has an error already reported. It unifies with every
+    /// type.
+    Unknown,
+}
+
+/// What a type variable can stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Kind {
+    /// Any type, for a `let` without a type nor a value.
+    Any,
+    /// An integer type, for an integer literal without a suffix.
+    Int,
+    /// `f32` or `f64`, for a float literal without a suffix.
+    Float,
+}
+
+#[derive(Debug, Clone)]
+enum Var {
+    Free(Kind),
+    Bound(Ty),
+}
+
+/// The type variables and what unifying them found.
+#[derive(Debug, Default)]
+pub(super) struct Table {
+    vars: Vec<Var>,
+}
+
+impl Table {
+    pub fn fresh(&mut self, kind: Kind) -> Ty {
+        self.vars.push(Var::Free(kind));
+        Ty::Var(self.vars.len() - 1)
+    }
+
+    /// `ty` with the variables already bound replaced by what they are bound to.
+    pub fn resolve(&self, ty: &Ty) -> Ty {
+        let mut ty = ty.clone();
+        while let Ty::Var(var) = ty {
+            match &self.vars[var] {
+                Var::Bound(bound) => ty = bound.clone(),
+                Var::Free(_) => break,
+            }
+        }
+        ty
+    }
+
+    /// Makes `a` and `b` the same type, binding their variables. `false`, binding nothing,
+    /// when they can not be.
+    pub fn unify(&mut self, a: &Ty, b: &Ty) -> bool {
+        match (self.resolve(a), self.resolve(b)) {
+            (Ty::Unknown, other) | (other, Ty::Unknown) => {
+                // A variable that could be anything is as unknown as what it is used as. The
+                // literals keep their kind, to be checked against their default type.
+                if let Ty::Var(var) = other {
+                    if matches!(self.vars[var], Var::Free(Kind::Any)) {
+                        self.vars[var] = Var::Bound(Ty::Unknown);
+                    }
+                }
+                true
+            }
+            (Ty::Known(a), Ty::Known(b)) => a == b,
+            (Ty::Array(a), Ty::Array(b)) => self.unify(&a, &b),
+            (Ty::Var(var), Ty::Known(ty)) | (Ty::Known(ty), Ty::Var(var)) => {
+                let fits = match self.vars[var] {
+                    Var::Free(Kind::Int) => int_range(&ty).is_some(),
+                    Var::Free(Kind::Float) => is_float(&ty),
+                    _ => true,
+                };
+                if fits {
+                    self.vars[var] = Var::Bound(Ty::Known(ty));
+                }
+                fits
+            }
+            (Ty::Var(var), array @ Ty::Array(_)) | (array @ Ty::Array(_), Ty::Var(var)) => {
+                // An array containing itself has no type.
+                let fits =
+                    matches!(self.vars[var], Var::Free(Kind::Any)) && !self.occurs(var, &array);
+                if fits {
+                    self.vars[var] = Var::Bound(array);
+                }
+                fits
+            }
+            (Ty::Known(_), Ty::Array(_)) | (Ty::Array(_), Ty::Known(_)) => false,
+            (Ty::Var(a), Ty::Var(b)) if a == b => true,
+            (Ty::Var(a), Ty::Var(b)) => {
+                let (Var::Free(kind_a), Var::Free(kind_b)) = (&self.vars[a], &self.vars[b]) else {
+                    unreachable!("resolved variables are free")
+                };
+                let kind = match (*kind_a, *kind_b) {
+                    (Kind::Any, kind) | (kind, Kind::Any) => kind,
+                    (kind_a, kind_b) if kind_a == kind_b => kind_a,
+                    _ => return false,
+                };
+                self.vars[a] = Var::Free(kind);
+                self.vars[b] = Var::Bound(Ty::Var(a));
+                true
+            }
+        }
+    }
+
+    /// Checks whether the variable `var` is part of `ty`.
+    fn occurs(&self, var: usize, ty: &Ty) -> bool {
+        match self.resolve(ty) {
+            Ty::Var(other) => other == var,
+            Ty::Array(elem) => self.occurs(var, &elem),
+            Ty::Known(_) | Ty::Unknown => false,
+        }
+    }
+
+    /// Gives the integer and float variables still free their default type, `i32` and `f64`.
+    pub fn default_numbers(&mut self) {
+        for var in self.vars.iter_mut() {
+            match var {
+                Var::Free(Kind::Int) => *var = Var::Bound(Ty::Known(Type::I32)),
+                Var::Free(Kind::Float) => *var = Var::Bound(Ty::Known(Type::F64)),
+                _ => {}
+            }
+        }
+    }
+
+    /// The type `ty` stands for, `None` when it is not known yet or never will be.
+    pub fn known(&self, ty: &Ty) -> Option<Type> {
+        match self.resolve(ty) {
+            Ty::Known(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    /// Like `known`, but an integer or float variable is the type it defaults to: whatever it
+    /// turns out to be, it is a number, never a struct.
+    pub fn known_or_number(&self, ty: &Ty) -> Option<Type> {
+        match self.resolve(ty) {
+            Ty::Var(var) if matches!(self.vars[var], Var::Free(Kind::Int | Kind::Float)) => {
+                Some(self.show(ty))
+            }
+            ty => self.known(&ty),
+        }
+    }
+
+    /// Checks whether `ty` is a variable that nothing gave a type to.
+    pub fn is_free(&self, ty: &Ty) -> bool {
+        matches!(self.resolve(ty), Ty::Var(_))
+    }
+
+    /// `ty` as named in an error, an integer or float variable by the type it defaults to.
+    pub fn show(&self, ty: &Ty) -> Type {
+        match self.resolve(ty) {
+            Ty::Known(ty) => ty,
+            Ty::Array(elem) => Type::Named(format!("[{}]", self.show(&elem))),
+            Ty::Var(var) => match self.vars[var] {
+                Var::Free(Kind::Int) => Type::I32,
+                Var::Free(Kind::Float) => Type::F64,
+                _ => Type::Named(String::from("_")),
+            },
+            Ty::Unknown => Type::Named(String::from("_")),
+        }
+    }
+}
+
+/// The types inferred for a program: those of the `let`s without a type and of the number
+/// literals without a suffix, by the file and the offset they start at.
+#[derive(Debug, Default)]
+pub(super) struct Inferred {
+    pub lets: HashMap<(usize, usize), Type>,
+    pub literals: HashMap<(usize, usize), Type>,
+}
+
+/// Writes the `inferred` types into `program`, as if they had been written in the source.
+pub(super) fn fill_in(program: &mut ProgramFile, inferred: &Inferred) {
+    fill_block(&mut program.main.body, inferred);
+    for decl in program.pre_main.iter_mut().chain(&mut program.post_main) {
+        match decl {
+            Decl::Fn(function) => fill_block(&mut function.body, inferred),
+            Decl::Impl(decl) => {
+                for function in &mut decl.fns {
+                    fill_block(&mut function.body, inferred);
+                }
+            }
+            Decl::Struct(_) | Decl::Enum(_) => {}
+        }
+    }
+}
+
+fn fill_block(block: &mut Block, inferred: &Inferred) {
+    for stmt in &mut block.stmts {
+        fill_stmt(stmt, inferred);
+    }
+}
+
+fn fill_stmt(stmt: &mut Stmt, inferred: &Inferred) {
+    match &mut stmt.kind {
+        StmtKind::Let { ty, value, .. } => {
+            if ty.is_none() {
+                *ty = inferred
+                    .lets
+                    .get(&(stmt.span.file, stmt.span.start))
+                    .cloned();
+            }
+            if let Some(value) = value {
+                fill_expr(value, inferred);
+            }
+        }
+        StmtKind::Assign { target, value, .. } => {
+            fill_expr(target, inferred);
+            fill_expr(value, inferred);
+        }
+        StmtKind::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            fill_expr(cond, inferred);
+            fill_block(then, inferred);
+            match otherwise {
+                Some(Else::If(stmt)) => fill_stmt(stmt, inferred),
+                Some(Else::Block(block)) => fill_block(block, inferred),
+                None => {}
+            }
+        }
+        StmtKind::Match { scrutinee, arms } => {
+            fill_expr(scrutinee, inferred);
+            for arm in arms {
+                if let Pattern::Expr(pattern) = &mut arm.pattern {
+                    fill_expr(pattern, inferred);
+                }
+                fill_block(&mut arm.body, inferred);
+            }
+        }
+        StmtKind::While { cond, body } => {
+            fill_expr(cond, inferred);
+            fill_block(body, inferred);
+        }
+        StmtKind::Return(Some(expr)) | StmtKind::Expr(expr) => fill_expr(expr, inferred),
+        StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+    }
+}
+
+fn fill_expr(expr: &mut Expr, inferred: &Inferred) {
+    match &mut expr.kind {
+        ExprKind::Literal(Literal::Int { suffix, .. } | Literal::Float { suffix, .. }) => {
+            if suffix.is_none() {
+                *suffix = inferred
+                    .literals
+                    .get(&(expr.span.file, expr.span.start))
+                    .cloned();
+            }
+        }
+        ExprKind::Literal(_) | ExprKind::Variable(_) => {}
+        ExprKind::Group(inner)
+        | ExprKind::Unary { expr: inner, .. }
+        | ExprKind::Field { expr: inner, .. } => fill_expr(inner, inferred),
+        ExprKind::Array(items) => {
+            for item in items {
+                fill_expr(item, inferred);
+            }
+        }
+        ExprKind::Binary { lhs, rhs, .. } => {
+            fill_expr(lhs, inferred);
+            fill_expr(rhs, inferred);
+        }
+        ExprKind::Call { callee, args } => {
+            fill_expr(callee, inferred);
+            for arg in args {
+                fill_expr(arg, inferred);
+            }
+        }
+        ExprKind::Index { expr, index } => {
+            fill_expr(expr, inferred);
+            fill_expr(index, inferred);
+        }
+        ExprKind::Interpolated(parts) => {
+            for part in parts {
+                if let InterpolatedPart::Expr(expr) = part {
+                    fill_expr(expr, inferred);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use super::resolve::{Binding, Resolution};
+use crate::lex::Span;
+use crate::load::Program;
+use crate::syn::ast::*;
+use std::collections::HashMap;
+
+/// Joins the modules of `program` into the one `ProgramFile` that is type checked and run:
+/// `main` of the module the program starts from, then the functions, structs and enums of every
+/// module, in the order the modules were loaded.
+///
+/// The items of the imported modules are renamed after their module, `dot` of `math.vector`
+/// becoming `math.vector.dot`, and every use of an item is written with its new name using the
+/// `resolutions` of the modules: `vector.dot(a, b)` becomes a call of the variable
+/// `math.vector.dot`. The `main` of an imported module is left out, it is never run.
+pub fn link(program: &Program, resolutions: &[Resolution]) -> ProgramFile {
+    let mut names = HashMap::new();
+    for module in program.modules.iter().skip(1) {
+        for decl in module
+            .program
+            .pre_main
+            .iter()
+            .chain(&module.program.post_main)
+        {
+            let (name, span) = match decl {
+                Decl::Fn(decl) => (&decl.name, decl.span),
+                Decl::Struct(decl) => (&decl.name, decl.span),
+                Decl::Enum(decl) => (&decl.name, decl.span),
+                Decl::Impl(_) => continue,
+            };
+            names.insert(span, format!("{}.{}", module.path, name));
+        }
+    }
+
+    let mut files = program
+        .modules
+        .iter()
+        .zip(resolutions)
+        .map(|(module, resolution)| {
+            let mut file = module.program.clone();
+            Linker {
+                names: &names,
+                resolution,
+            }
+            .file(&mut file);
+            file
+        });
+    let mut linked = files.next().expect("a program has a module");
+    for file in files {
+        linked.post_main.extend(file.pre_main);
+        linked.post_main.extend(file.post_main);
+    }
+    linked.imports.clear();
+    linked
+}
+
+struct Linker<'a> {
+    /// The names of the items of the imported modules, by their declaration.
+    names: &'a HashMap<Span, String>,
+    resolution: &'a Resolution,
+}
+
+impl Linker<'_> {
+    fn file(&self, file: &mut ProgramFile) {
+        self.block(&mut file.main.body);
+        for decl in file.pre_main.iter_mut().chain(&mut file.post_main) {
+            match decl {
+                Decl::Fn(decl) => {
+                    self.rename(&mut decl.name, decl.span);
+                    self.function(decl);
+                }
+                Decl::Struct(decl) => {
+                    self.rename(&mut decl.name, decl.span);
+                    for field in &mut decl.fields {
+                        self.ty(&mut field.ty);
+                    }
+                }
+                Decl::Enum(decl) => self.rename(&mut decl.name, decl.span),
+                Decl::Impl(decl) => {
+                    if let Some(declared) = self.resolution.ty(&decl.name) {
+                        self.rename(&mut decl.name, declared);
+                    }
+                    for function in &mut decl.fns {
+                        self.function(function);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Renames the item declared at `declared`, if it belongs to an imported module.
+    fn rename(&self, name: &mut String, declared: Span) {
+        if let Some(linked) = self.names.get(&declared) {
+            name.clone_from(linked);
+        }
+    }
+
+    fn function(&self, function: &mut FnDecl) {
+        for param in &mut function.params {
+            self.ty(&mut param.ty);
+        }
+        self.ty(&mut function.ret);
+        self.block(&mut function.body);
+    }
+
+    fn ty(&self, ty: &mut Type) {
+        if let Type::Named(name) = ty {
+            if let Some(declared) = self.resolution.ty(name) {
+                self.rename(name, declared);
+            }
+        }
+    }
+
+    fn block(&self, block: &mut Block) {
+        for stmt in &mut block.stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&self, stmt: &mut Stmt) {
+        match &mut stmt.kind {
+            StmtKind::Let { ty, value, .. } => {
+                if let Some(ty) = ty {
+                    self.ty(ty);
+                }
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            StmtKind::Assign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.expr(cond);
+                self.block(then);
+                match otherwise {
+                    Some(Else::If(stmt)) => self.stmt(stmt),
+                    Some(Else::Block(block)) => self.block(block),
+                    None => {}
+                }
+            }
+            StmtKind::Match { scrutinee, arms } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    if let Pattern::Expr(pattern) = &mut arm.pattern {
+                        self.expr(pattern);
+                    }
+                    self.block(&mut arm.body);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Return(Some(expr)) | StmtKind::Expr(expr) => self.expr(expr),
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    fn expr(&self, expr: &mut Expr) {
+        if let Some(Binding::Function(declared) | Binding::Type(declared)) =
+            self.resolution.binding(expr.span)
+        {
+            if let Some(linked) = self.names.get(declared) {
+                expr.kind = ExprKind::Variable(linked.clone());
+                return;
+            }
+        }
+
+        match &mut expr.kind {
+            ExprKind::Literal(_) | ExprKind::Variable(_) => {}
+            ExprKind::Group(inner)
+            | ExprKind::Unary { expr: inner, .. }
+            | ExprKind::Field { expr: inner, .. } => self.expr(inner),
+            ExprKind::Array(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::Call { callee, args } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::Index { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::Interpolated(parts) => {
+                for part in parts {
+                    if let InterpolatedPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+        }
+    }
+}
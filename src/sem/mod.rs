@@ -0,0 +1,14 @@
+//! Checks run on the syntax tree before a program runs.
+
+mod infer;
+mod link;
+mod mutability;
+mod resolve;
+mod typeck;
+
+pub use link::link;
+pub use mutability::{check_mutability, MutabilityError, MutabilityErrorKind};
+pub use resolve::{
+    resolve, unused_exports, Binding, NameError, NameErrorKind, Resolution, UnusedExport,
+};
+pub use typeck::{check_types, TypeError, TypeErrorKind};
@@ -0,0 +1,312 @@
+use super::resolve::{Binding, Resolution};
+use crate::error::{Diagnostic, Label, ToDiagnostic};
+use crate::lex::Span;
+use crate::syn::ast::*;
+use std::collections::{HashMap, HashSet};
+
+/// Checks that only variables declared with `mut` are assigned again or changed through a
+/// field or an element, using the names `resolution` bound.
+///
+/// A variable declared without `mut` nor a value, as `let x: i32;`, can be assigned once. It is
+/// an error when another assignment may follow it on some path through the function, as after an
+/// `if` that assigned it in one branch or in a loop. Parameters are never assigned.
+pub fn check_mutability(
+    program: &ProgramFile,
+    resolution: &Resolution,
+) -> Result<(), Vec<MutabilityError>> {
+    let mut checker = Checker {
+        resolution,
+        lets: HashMap::new(),
+        assigned: HashSet::new(),
+        loops: Vec::new(),
+        report: true,
+        errors: Vec::new(),
+    };
+    checker.function(&program.main.body);
+    for decl in program.pre_main.iter().chain(&program.post_main) {
+        match decl {
+            Decl::Fn(function) => checker.function(&function.body),
+            Decl::Impl(decl) => {
+                for function in &decl.fns {
+                    checker.function(&function.body);
+                }
+            }
+            Decl::Struct(_) | Decl::Enum(_) => {}
+        }
+    }
+
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        checker.errors.sort_by_key(|error| error.span.start);
+        Err(checker.errors)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutabilityError {
+    pub kind: MutabilityErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MutabilityErrorKind {
+    /// `<-` or a compound assignment to a variable declared at `declared` without `mut`, that
+    /// has or may have a value already.
+    AssignTwice {
+        name: String,
+        declared: Span,
+    },
+    AssignToParameter {
+        name: String,
+        declared: Span,
+    },
+    /// An assignment to a field or an element of a variable declared without `mut`, or of a
+    /// parameter.
+    MutateImmutable {
+        name: String,
+        declared: Span,
+        parameter: bool,
+    },
+}
+
+impl std::fmt::Display for MutabilityErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MutabilityErrorKind::AssignTwice { name, .. } => {
+                write!(f, "cannot assign twice to immutable variable `{}`", name)
+            }
+            MutabilityErrorKind::AssignToParameter { name, .. } => {
+                write!(f, "cannot assign to parameter `{}`", name)
+            }
+            MutabilityErrorKind::MutateImmutable { name, .. } => {
+                write!(
+                    f,
+                    "cannot assign to a part of immutable variable `{}`",
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl MutabilityErrorKind {
+    /// The error code reported with the `Diagnostic`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MutabilityErrorKind::AssignTwice { .. } => "E0600",
+            MutabilityErrorKind::AssignToParameter { .. } => "E0601",
+            MutabilityErrorKind::MutateImmutable { .. } => "E0602",
+        }
+    }
+}
+
+impl ToDiagnostic for MutabilityError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let (label, name, declared, parameter) = match &self.kind {
+            MutabilityErrorKind::AssignTwice { name, declared } => {
+                ("cannot assign twice", name, declared, false)
+            }
+            MutabilityErrorKind::AssignToParameter { name, declared } => {
+                ("cannot assign", name, declared, true)
+            }
+            MutabilityErrorKind::MutateImmutable {
+                name,
+                declared,
+                parameter,
+            } => ("cannot assign", name, declared, *parameter),
+        };
+        let help = if parameter {
+            format!(
+                "parameters are immutable, copy it into a variable with `let mut {} <- {};`",
+                name, name
+            )
+        } else {
+            format!("declare it with `let mut {}` to make it mutable", name)
+        };
+        Diagnostic::error(self.kind.to_string())
+            .with_code(self.kind.code())
+            .with_label(Label::primary(self.span, label))
+            .with_label(Label::secondary(*declared, "declared here without `mut`"))
+            .with_help(help)
+    }
+}
+
+impl std::fmt::Display for MutabilityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.span.line)
+    }
+}
+
+struct Checker<'a> {
+    resolution: &'a Resolution,
+    /// The `let`s without `mut` of the function, by where they start: whether they have a value.
+    lets: HashMap<usize, bool>,
+    /// The `let`s without `mut` nor a value that may have been assigned on the path followed.
+    assigned: HashSet<usize>,
+    /// The loops the path is in, innermost last.
+    loops: Vec<Jumps>,
+    /// `false` while a loop body is walked to find what it assigns, before it is checked.
+    report: bool,
+    errors: Vec<MutabilityError>,
+}
+
+/// `assigned` at the `break`s and `continue`s of a loop.
+#[derive(Default)]
+struct Jumps {
+    breaks: HashSet<usize>,
+    continues: HashSet<usize>,
+}
+
+impl<'a> Checker<'a> {
+    fn function(&mut self, body: &Block) {
+        self.lets.clear();
+        self.assigned.clear();
+        self.block(body);
+    }
+
+    /// Checks `block`, returning whether it always leaves through a `return`, `break` or
+    /// `continue`.
+    fn block(&mut self, block: &Block) -> bool {
+        let mut diverges = false;
+        for stmt in &block.stmts {
+            diverges |= self.stmt(stmt);
+        }
+        diverges
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> bool {
+        match &stmt.kind {
+            StmtKind::Let { mutable, value, .. } => {
+                if !mutable {
+                    self.lets.insert(stmt.span.start, value.is_some());
+                }
+                // A `let` in a loop declares a new variable on every turn.
+                self.assigned.remove(&stmt.span.start);
+                false
+            }
+            StmtKind::Assign { target, op, .. } => {
+                self.assign(target, op.is_some());
+                false
+            }
+            StmtKind::If {
+                then, otherwise, ..
+            } => {
+                let before = self.assigned.clone();
+                let then_diverges = self.block(then);
+                let after_then = std::mem::replace(&mut self.assigned, before);
+                let otherwise_diverges = match otherwise {
+                    Some(Else::If(stmt)) => self.stmt(stmt),
+                    Some(Else::Block(block)) => self.block(block),
+                    None => false,
+                };
+                match (then_diverges, otherwise_diverges) {
+                    (true, true) => true,
+                    (true, false) => false,
+                    (false, true) => {
+                        self.assigned = after_then;
+                        false
+                    }
+                    (false, false) => {
+                        self.assigned.extend(after_then);
+                        false
+                    }
+                }
+            }
+            StmtKind::Match { arms, .. } => {
+                let before = self.assigned.clone();
+                let mut after = before.clone();
+                for arm in arms {
+                    self.assigned = before.clone();
+                    if !self.block(&arm.body) {
+                        after.extend(self.assigned.drain());
+                    }
+                }
+                self.assigned = after;
+                false
+            }
+            StmtKind::While { body, .. } => {
+                // The body runs again after a turn: walk it once to find what it assigns, then
+                // check it from the start of the loop or the end of a turn.
+                let before = self.assigned.clone();
+                let report = std::mem::replace(&mut self.report, false);
+                let (turn, _) = self.turn(body);
+                self.report = report;
+
+                self.assigned = before.clone();
+                self.assigned.extend(turn);
+                let (turn, breaks) = self.turn(body);
+                // The loop may not run at all, or leave after a turn or at a `break`.
+                self.assigned = before;
+                self.assigned.extend(turn);
+                self.assigned.extend(breaks);
+                false
+            }
+            StmtKind::Return(_) => true,
+            StmtKind::Break | StmtKind::Continue => {
+                if let Some(jumps) = self.loops.last_mut() {
+                    let jumps = match stmt.kind {
+                        StmtKind::Break => &mut jumps.breaks,
+                        _ => &mut jumps.continues,
+                    };
+                    jumps.extend(self.assigned.iter().copied());
+                }
+                true
+            }
+            StmtKind::Expr(_) => false,
+        }
+    }
+
+    /// Checks a turn of the loop `body`, returning `assigned` at the end of the turn and at its
+    /// `break`s.
+    fn turn(&mut self, body: &Block) -> (HashSet<usize>, HashSet<usize>) {
+        self.loops.push(Jumps::default());
+        let diverges = self.block(body);
+        let jumps = self.loops.pop().expect("the loop was pushed");
+        let mut end = jumps.continues;
+        if !diverges {
+            end.extend(self.assigned.drain());
+        }
+        (end, jumps.breaks)
+    }
+
+    /// `target <- value`, or a compound assignment when `compound`.
+    fn assign(&mut self, target: &Expr, compound: bool) {
+        let mut root = target;
+        while let ExprKind::Field { expr, .. } | ExprKind::Index { expr, .. } = &root.kind {
+            root = expr;
+        }
+        let ExprKind::Variable(name) = &root.kind else {
+            return;
+        };
+        let Some(Binding::Variable {
+            declared,
+            mutable: false,
+        }) = self.resolution.binding(root.span).copied()
+        else {
+            return;
+        };
+        let name = name.clone();
+        let parameter = !self.lets.contains_key(&declared.start);
+
+        let kind = if !std::ptr::eq(root, target) {
+            MutabilityErrorKind::MutateImmutable {
+                name,
+                declared,
+                parameter,
+            }
+        } else if parameter {
+            MutabilityErrorKind::AssignToParameter { name, declared }
+        } else if compound || self.lets[&declared.start] || !self.assigned.insert(declared.start) {
+            MutabilityErrorKind::AssignTwice { name, declared }
+        } else {
+            return;
+        };
+        if self.report {
+            self.errors.push(MutabilityError {
+                kind,
+                span: target.span,
+            });
+        }
+    }
+}
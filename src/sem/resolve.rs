@@ -0,0 +1,922 @@
+use crate::error::{Diagnostic, Label, ToDiagnostic};
+use crate::lex::Span;
+use crate::load::Program;
+use crate::syn::ast::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Binds every name used in the module `module` of `program` to what it refers to, following
+/// the scopes of the language: the module holds the functions, structs and enums, usable
+/// anywhere in it whatever their order, an `impl` body its methods, a function its parameters
+/// and each block the variables declared in it from their `let` to the end of the block. A
+/// `let` can shadow a variable of the same name, from the same block or an enclosing one.
+///
+/// The module scope also holds the items its imports name, as `dot` for
+/// `import math.vector.{dot};`, and the modules imported whole, whose items are used as
+/// `vector.dot()`. Only the `pub` items of a module can be used outside it.
+///
+/// Reports the names that are not found, the variants of enums among them, with the closest
+/// name in scope as a suggestion, the variables used before their `let`, the names declared
+/// twice in one scope and the private items used through an import.
+pub fn resolve(program: &Program, module: usize) -> Result<Resolution, Vec<NameError>> {
+    let mut resolver = Resolver {
+        program,
+        functions: HashMap::new(),
+        types: HashMap::new(),
+        modules: HashMap::new(),
+        methods: HashMap::new(),
+        scopes: Vec::new(),
+        resolution: Resolution::default(),
+        errors: Vec::new(),
+    };
+    let file = &program.modules[module];
+    let decls = file.program.pre_main.iter().chain(&file.program.post_main);
+    // In the order of the source, the imports being first, so that a name declared twice is
+    // reported where it is declared the second time.
+    for (import, &imported) in file.program.imports.iter().zip(&file.imports) {
+        resolver.import(import, imported);
+    }
+    for decl in decls.clone() {
+        resolver.declare(decl);
+    }
+    resolver.resolution.types = resolver
+        .types
+        .iter()
+        .map(|(name, item)| (name.to_string(), item.declared))
+        .collect();
+
+    resolver.function(
+        &[],
+        &Type::Void,
+        &file.program.main.body,
+        file.program.main.span,
+    );
+    for decl in decls {
+        resolver.decl(decl);
+    }
+
+    if resolver.errors.is_empty() {
+        Ok(resolver.resolution)
+    } else {
+        resolver.errors.sort_by_key(|error| error.span.start);
+        Err(resolver.errors)
+    }
+}
+
+/// Finds the `pub` functions, structs and enums of the modules of `program` that no other
+/// module uses, `resolutions` being those of every module. The module the program starts from
+/// is left out, as no module can import it.
+pub fn unused_exports(program: &Program, resolutions: &[Resolution]) -> Vec<UnusedExport> {
+    let used = resolutions
+        .iter()
+        .flat_map(|resolution| &resolution.imported)
+        .collect::<HashSet<_>>();
+    program
+        .modules
+        .iter()
+        .skip(1)
+        .flat_map(|module| {
+            module
+                .program
+                .pre_main
+                .iter()
+                .chain(&module.program.post_main)
+        })
+        .filter_map(item)
+        .filter(|item| item.public && !used.contains(&item.span))
+        .map(|item| UnusedExport {
+            what: item.what,
+            name: item.name.to_string(),
+            span: item.span,
+        })
+        .collect()
+}
+
+/// What the names of a module refer to, by the span of the expression using them.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    bindings: HashMap<(usize, usize), Binding>,
+    /// The structs and enums of the module scope.
+    types: HashMap<String, Span>,
+    /// The items of other modules the module imports by name or uses through their module.
+    imported: HashSet<Span>,
+}
+
+impl Resolution {
+    /// What the `Variable` expression at `span`, or the callee of a call, refers to. The
+    /// `Field` expressions naming an item of another module, as `vector.dot`, are bound too.
+    pub fn binding(&self, span: Span) -> Option<&Binding> {
+        self.bindings.get(&(span.start, span.end))
+    }
+
+    /// The struct or enum the type `name` refers to in the module, as in `let v: Vec2;`.
+    pub fn ty(&self, name: &str) -> Option<Span> {
+        self.types.get(name).copied()
+    }
+}
+
+/// The declaration a name refers to. The `file` of the spans is the module it is declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    /// A variable, declared by the `let` or the parameter at `declared`.
+    Variable { declared: Span, mutable: bool },
+    /// A function, or a method of the `impl` the name is used in.
+    Function(Span),
+    /// `print` or `println`.
+    Builtin,
+    /// A struct or an enum, as `Color` in `Color.Red`.
+    Type(Span),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameError {
+    pub kind: NameErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NameErrorKind {
+    /// `suggestion` is the closest variable in scope, if one is close enough.
+    UndefinedVariable {
+        name: String,
+        suggestion: Option<String>,
+    },
+    UndefinedFunction {
+        name: String,
+        suggestion: Option<String>,
+    },
+    UndefinedType {
+        name: String,
+        suggestion: Option<String>,
+    },
+    /// A variable used in a block before the `let` at `declared` that declares it.
+    UsedBeforeDeclaration { name: String, declared: Span },
+    /// `what` is declared twice in one scope, first at `first`.
+    Duplicate {
+        what: &'static str,
+        name: String,
+        first: Span,
+    },
+    /// No function, struct or enum `name` in the module `module`.
+    UndefinedItem {
+        name: String,
+        module: String,
+        suggestion: Option<String>,
+    },
+    /// An item declared without `pub` in the file `file`, used by another module.
+    Private {
+        what: &'static str,
+        name: String,
+        file: String,
+    },
+    /// A module imported as `name`, used as a variable.
+    ModuleAsValue { name: String },
+    /// No variant `name` in the enum `ty`, as `Color.Blu`.
+    UndefinedVariant {
+        name: String,
+        ty: String,
+        suggestion: Option<String>,
+    },
+}
+
+impl std::fmt::Display for NameErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NameErrorKind::UndefinedVariable { name, .. } => {
+                write!(f, "cannot find variable `{}` in this scope", name)
+            }
+            NameErrorKind::UndefinedFunction { name, .. } => {
+                write!(f, "cannot find function `{}` in this scope", name)
+            }
+            NameErrorKind::UndefinedType { name, .. } => {
+                write!(f, "cannot find type `{}` in this scope", name)
+            }
+            NameErrorKind::UsedBeforeDeclaration { name, .. } => {
+                write!(f, "variable `{}` is used before its declaration", name)
+            }
+            NameErrorKind::Duplicate { what, name, .. } => {
+                write!(f, "the {} `{}` is declared more than once", what, name)
+            }
+            NameErrorKind::UndefinedItem { name, module, .. } => {
+                write!(f, "cannot find `{}` in module `{}`", name, module)
+            }
+            NameErrorKind::Private { what, name, .. } => {
+                write!(f, "{} `{}` is private", what, name)
+            }
+            NameErrorKind::ModuleAsValue { name } => {
+                write!(f, "expected a value, found module `{}`", name)
+            }
+            NameErrorKind::UndefinedVariant { name, ty, .. } => {
+                write!(f, "no variant `{}` in enum `{}`", name, ty)
+            }
+        }
+    }
+}
+
+impl NameErrorKind {
+    /// The error code reported with the `Diagnostic`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            NameErrorKind::UndefinedVariable { .. } => "E0500",
+            NameErrorKind::UndefinedFunction { .. } => "E0501",
+            NameErrorKind::UndefinedType { .. } => "E0502",
+            NameErrorKind::UsedBeforeDeclaration { .. } => "E0503",
+            NameErrorKind::Duplicate { .. } => "E0504",
+            NameErrorKind::UndefinedItem { .. } => "E0505",
+            NameErrorKind::Private { .. } => "E0506",
+            NameErrorKind::ModuleAsValue { .. } => "E0507",
+            NameErrorKind::UndefinedVariant { .. } => "E0508",
+        }
+    }
+}
+
+impl ToDiagnostic for NameError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let label = match &self.kind {
+            NameErrorKind::UndefinedVariable { .. }
+            | NameErrorKind::UndefinedFunction { .. }
+            | NameErrorKind::UndefinedType { .. } => "not found in this scope",
+            NameErrorKind::UsedBeforeDeclaration { .. } => "used here",
+            NameErrorKind::Duplicate { .. } => "declared again here",
+            NameErrorKind::UndefinedItem { .. } => "not found in the module",
+            NameErrorKind::Private { .. } => "private",
+            NameErrorKind::ModuleAsValue { .. } => "not a value",
+            NameErrorKind::UndefinedVariant { .. } => "not found in the enum",
+        };
+        let diagnostic = Diagnostic::error(self.kind.to_string())
+            .with_code(self.kind.code())
+            .with_label(Label::primary(self.span, label));
+
+        match &self.kind {
+            NameErrorKind::UndefinedVariable {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | NameErrorKind::UndefinedFunction {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | NameErrorKind::UndefinedType {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | NameErrorKind::UndefinedItem {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | NameErrorKind::UndefinedVariant {
+                suggestion: Some(suggestion),
+                ..
+            } => diagnostic.with_help(format!("did you mean `{}`?", suggestion)),
+            NameErrorKind::UsedBeforeDeclaration { name, declared } => {
+                let diagnostic =
+                    diagnostic.with_label(Label::secondary(*declared, "declared here"));
+                if declared.start <= self.span.start && self.span.end <= declared.end {
+                    // `let x <- x + 1;`
+                    diagnostic.with_note("a variable is in scope only after its `let`")
+                } else {
+                    diagnostic
+                        .with_help(format!("move the declaration of `{}` before its use", name))
+                }
+            }
+            NameErrorKind::Duplicate { first, .. } => {
+                diagnostic.with_label(Label::secondary(*first, "first declared here"))
+            }
+            NameErrorKind::Private { name, file, .. } => diagnostic
+                .with_note(format!(
+                    "`{}` is declared without `pub` in `{}`",
+                    name, file
+                ))
+                .with_help("declare it with `pub` to use it in other modules"),
+            NameErrorKind::ModuleAsValue { name } => {
+                diagnostic.with_help(format!("use an item of the module, as `{}.f()`", name))
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+impl std::fmt::Display for NameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.span.line)
+    }
+}
+
+/// A `pub` item no other module uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnusedExport {
+    pub what: &'static str,
+    pub name: String,
+    pub span: Span,
+}
+
+impl ToDiagnostic for UnusedExport {
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::warning(format!(
+            "{} `{}` is `pub` but no other module uses it",
+            self.what, self.name
+        ))
+        .with_label(Label::primary(self.span, "never imported"))
+        .with_help("remove `pub`, or import it where it is needed")
+    }
+}
+
+impl std::fmt::Display for UnusedExport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} `{}` is never imported at line {}",
+            self.what, self.name, self.span.line
+        )
+    }
+}
+
+/// The names of the primitive types, suggested for a misspelled type.
+const PRIMITIVES: [&str; 14] = [
+    "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64", "bool", "byte", "char",
+    "String",
+];
+
+const BUILTINS: [&str; 2] = ["print", "println"];
+
+/// The variables of a function or a block.
+struct Scope<'a> {
+    /// In the order of their `let`, the last one shadowing the others of the same name.
+    variables: Vec<(&'a str, Binding)>,
+    /// The `let`s of the block not reached yet, in order.
+    later: VecDeque<(&'a str, Span)>,
+}
+
+/// A function, struct or enum of the module scope.
+#[derive(Clone, Copy)]
+struct Item {
+    declared: Span,
+    /// Where the module names it: its declaration, or the import of an item of another module.
+    named: Span,
+}
+
+/// The declaration of a function, struct or enum.
+#[derive(Clone, Copy)]
+struct ItemDecl<'a> {
+    what: &'static str,
+    name: &'a str,
+    public: bool,
+    span: Span,
+}
+
+impl ItemDecl<'_> {
+    fn binding(&self) -> Binding {
+        match self.what {
+            "function" => Binding::Function(self.span),
+            _ => Binding::Type(self.span),
+        }
+    }
+}
+
+/// `decl`, unless it is an `impl`.
+fn item(decl: &Decl) -> Option<ItemDecl<'_>> {
+    let (what, name, public, span) = match decl {
+        Decl::Fn(decl) => ("function", &decl.name, decl.public, decl.span),
+        Decl::Struct(decl) => ("struct", &decl.name, decl.public, decl.span),
+        Decl::Enum(decl) => ("enum", &decl.name, decl.public, decl.span),
+        Decl::Impl(_) => return None,
+    };
+    Some(ItemDecl {
+        what,
+        name,
+        public,
+        span,
+    })
+}
+
+struct Resolver<'a> {
+    program: &'a Program,
+    functions: HashMap<&'a str, Item>,
+    /// The structs and enums.
+    types: HashMap<&'a str, Item>,
+    /// The modules imported whole, by the name they are known by, with their import.
+    modules: HashMap<&'a str, (usize, Span)>,
+    /// The methods of the `impl` being resolved.
+    methods: HashMap<&'a str, Span>,
+    /// The scopes of the function being resolved, innermost last.
+    scopes: Vec<Scope<'a>>,
+    resolution: Resolution,
+    errors: Vec<NameError>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Adds the item `decl` to the module scope.
+    fn declare(&mut self, decl: &'a Decl) {
+        if let Some(item) = item(decl) {
+            self.add(item, item.name, item.span);
+        }
+    }
+
+    /// Adds what `import` names to the module scope, `imported` being the module it imports.
+    fn import(&mut self, import: &'a ImportDecl, imported: usize) {
+        let Some(items) = &import.items else {
+            let name = import.name();
+            let first = match self.modules.get(name) {
+                Some((_, first)) => Some(*first),
+                None => self.types.get(name).map(|item| item.named),
+            };
+            match first {
+                Some(first) => self.duplicate("module", name, first, import.span),
+                None => {
+                    self.modules.insert(name, (imported, import.span));
+                }
+            }
+            return;
+        };
+        for item in items {
+            if let Some(decl) = self.member(imported, &item.name, item.span) {
+                self.add(decl, item.local_name(), item.span);
+            }
+        }
+    }
+
+    /// Adds the item `decl` to the module scope as `name`, the module naming it at `named`.
+    fn add(&mut self, decl: ItemDecl<'a>, name: &'a str, named: Span) {
+        let (map, what) = match decl.what {
+            "function" => (&mut self.functions, "function"),
+            _ => (&mut self.types, "type"),
+        };
+        let first = match map.get(name) {
+            Some(first) => Some(first.named),
+            // `Color.Red` would not tell the type from the module.
+            None if what == "type" => self.modules.get(name).map(|(_, first)| *first),
+            None => None,
+        };
+        match first {
+            Some(first) => self.duplicate(what, name, first, named),
+            None => {
+                map.insert(
+                    name,
+                    Item {
+                        declared: decl.span,
+                        named,
+                    },
+                );
+            }
+        }
+    }
+
+    /// The item `name` of the module `module`, named at `span`, when there is one and it is
+    /// `pub`.
+    fn member(&mut self, module: usize, name: &str, span: Span) -> Option<ItemDecl<'a>> {
+        let program = &self.program.modules[module].program;
+        let items = program
+            .pre_main
+            .iter()
+            .chain(&program.post_main)
+            .filter_map(item);
+        let Some(found) = items.clone().find(|item| item.name == name) else {
+            let candidates = items.filter(|item| item.public).map(|item| item.name);
+            let suggestion = closest(name, candidates);
+            self.error(
+                NameErrorKind::UndefinedItem {
+                    name: name.to_string(),
+                    module: self.program.modules[module].path.clone(),
+                    suggestion,
+                },
+                span,
+            );
+            return None;
+        };
+        if !found.public {
+            self.error(
+                NameErrorKind::Private {
+                    what: found.what,
+                    name: name.to_string(),
+                    file: self.program.files[module].name.clone(),
+                },
+                span,
+            );
+            return None;
+        }
+        self.resolution.imported.insert(found.span);
+        Some(found)
+    }
+
+    /// The module `expr` names, when it is the name of an imported module and not of a variable.
+    fn module(&self, expr: &Expr) -> Option<usize> {
+        match &expr.kind {
+            ExprKind::Variable(name) if self.lookup(name).is_none() => {
+                self.modules.get(name.as_str()).map(|(module, _)| *module)
+            }
+            _ => None,
+        }
+    }
+
+    /// `module.name`, an item of another module, at `span`.
+    fn qualified(&mut self, module: usize, name: &str, span: Span) {
+        if let Some(item) = self.member(module, name, span) {
+            self.bind(span, item.binding());
+        }
+    }
+
+    fn bind(&mut self, span: Span, binding: Binding) {
+        self.resolution
+            .bindings
+            .insert((span.start, span.end), binding);
+    }
+
+    fn decl(&mut self, decl: &'a Decl) {
+        match decl {
+            Decl::Fn(function) => self.fn_decl(function),
+            Decl::Struct(decl) => {
+                let mut fields = HashMap::new();
+                for field in &decl.fields {
+                    self.ty(&field.ty, field.span);
+                    if let Some(first) = fields.insert(field.name.as_str(), field.span) {
+                        self.duplicate("field", &field.name, first, field.span);
+                    }
+                }
+            }
+            Decl::Enum(decl) => {
+                let mut variants = HashMap::new();
+                for variant in &decl.variants {
+                    if let Some(first) = variants.insert(variant.name.as_str(), variant.span) {
+                        self.duplicate("variant", &variant.name, first, variant.span);
+                    }
+                }
+            }
+            Decl::Impl(decl) => {
+                self.ty(&Type::Named(decl.name.clone()), decl.span);
+                self.methods.clear();
+                for function in &decl.fns {
+                    if let Some(first) = self.methods.get(function.name.as_str()) {
+                        let first = *first;
+                        self.duplicate("method", &function.name, first, function.span);
+                    } else {
+                        self.methods.insert(&function.name, function.span);
+                    }
+                }
+                for function in &decl.fns {
+                    self.fn_decl(function);
+                }
+                self.methods.clear();
+            }
+        }
+    }
+
+    fn fn_decl(&mut self, function: &'a FnDecl) {
+        self.function(
+            &function.params,
+            &function.ret,
+            &function.body,
+            function.span,
+        );
+    }
+
+    fn function(&mut self, params: &'a [Param], ret: &Type, body: &'a Block, span: Span) {
+        self.ty(ret, span);
+        let mut scope = Scope {
+            variables: Vec::new(),
+            later: VecDeque::new(),
+        };
+        for param in params {
+            self.ty(&param.ty, param.span);
+            let first = scope.variables.iter().find(|(name, _)| *name == param.name);
+            if let Some((_, Binding::Variable { declared, .. })) = first {
+                let first = *declared;
+                self.duplicate("parameter", &param.name, first, param.span);
+                continue;
+            }
+            let binding = Binding::Variable {
+                declared: param.span,
+                mutable: false,
+            };
+            scope.variables.push((&param.name, binding));
+        }
+        self.scopes = vec![scope];
+        self.block(body);
+        self.scopes.clear();
+    }
+
+    fn block(&mut self, block: &'a Block) {
+        let later = block
+            .stmts
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StmtKind::Let { name, .. } => Some((name.as_str(), stmt.span)),
+                _ => None,
+            })
+            .collect();
+        self.scopes.push(Scope {
+            variables: Vec::new(),
+            later,
+        });
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match &stmt.kind {
+            StmtKind::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => {
+                if let Some(ty) = ty {
+                    self.ty(ty, stmt.span);
+                }
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+                // The variable is in scope after its value, `let x <- x + 1;` uses an outer `x`.
+                let scope = self.scopes.last_mut().expect("a block has a scope");
+                // The `let`s of a block are reached in order, this one is the first left.
+                scope.later.pop_front();
+                let binding = Binding::Variable {
+                    declared: stmt.span,
+                    mutable: *mutable,
+                };
+                scope.variables.push((name, binding));
+            }
+            StmtKind::Assign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.expr(cond);
+                self.block(then);
+                match otherwise {
+                    Some(Else::If(stmt)) => self.stmt(stmt),
+                    Some(Else::Block(block)) => self.block(block),
+                    None => {}
+                }
+            }
+            StmtKind::Match { scrutinee, arms } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    if let Pattern::Expr(pattern) = &arm.pattern {
+                        self.expr(pattern);
+                    }
+                    self.block(&arm.body);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::Return(Some(expr)) | StmtKind::Expr(expr) => self.expr(expr),
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::Literal(_) => {}
+            ExprKind::Variable(name) => self.variable(name, expr.span),
+            ExprKind::Group(inner) | ExprKind::Unary { expr: inner, .. } => self.expr(inner),
+            ExprKind::Field { expr: inner, name } => {
+                match (self.module(inner), &inner.kind) {
+                    // `vector.dot` in `vector.dot(a, b)`, or `shapes.Color` in
+                    // `shapes.Color.Red`.
+                    (Some(module), _) => self.qualified(module, name, expr.span),
+                    // `Color.Red`, when no variable is named `Color`.
+                    (None, ExprKind::Variable(name))
+                        if self.lookup(name).is_none()
+                            && self.types.contains_key(name.as_str()) =>
+                    {
+                        let binding = Binding::Type(self.types[name.as_str()].declared);
+                        self.bind(inner.span, binding);
+                    }
+                    _ => self.expr(inner),
+                }
+                if let Some(Binding::Type(declared)) = self.resolution.binding(inner.span) {
+                    self.variant(*declared, name, expr.span);
+                }
+            }
+            ExprKind::Array(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::Call { callee, args } => {
+                match &callee.kind {
+                    ExprKind::Variable(name) => self.callee(name, callee.span),
+                    _ => self.expr(callee),
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::Index { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::Interpolated(parts) => {
+                for part in parts {
+                    if let InterpolatedPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The innermost variable named `name` in scope.
+    fn lookup(&self, name: &str) -> Option<Binding> {
+        self.scopes.iter().rev().find_map(|scope| {
+            scope
+                .variables
+                .iter()
+                .rev()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, binding)| *binding)
+        })
+    }
+
+    fn variable(&mut self, name: &str, span: Span) {
+        if let Some(binding) = self.lookup(name) {
+            self.bind(span, binding);
+            return;
+        }
+        if self.modules.contains_key(name) {
+            let name = name.to_string();
+            self.error(NameErrorKind::ModuleAsValue { name }, span);
+            return;
+        }
+
+        let later = self.scopes.iter().rev().find_map(|scope| {
+            scope
+                .later
+                .iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, declared)| *declared)
+        });
+        let kind = match later {
+            Some(declared) => NameErrorKind::UsedBeforeDeclaration {
+                name: name.to_string(),
+                declared,
+            },
+            None => {
+                let candidates = self
+                    .scopes
+                    .iter()
+                    .flat_map(|scope| scope.variables.iter().map(|(name, _)| *name));
+                NameErrorKind::UndefinedVariable {
+                    name: name.to_string(),
+                    suggestion: closest(name, candidates),
+                }
+            }
+        };
+        self.error(kind, span);
+    }
+
+    /// The function called by the name `name`, a method of the `impl` before the functions
+    /// of the module and those before the builtins.
+    fn callee(&mut self, name: &str, span: Span) {
+        let declared = match self.methods.get(name) {
+            Some(declared) => Some(*declared),
+            None => self.functions.get(name).map(|item| item.declared),
+        };
+        let binding = match declared {
+            Some(declared) => Binding::Function(declared),
+            None if BUILTINS.contains(&name) => Binding::Builtin,
+            None => {
+                let candidates = self
+                    .methods
+                    .keys()
+                    .chain(self.functions.keys())
+                    .copied()
+                    .chain(BUILTINS);
+                let suggestion = closest(name, candidates);
+                self.error(
+                    NameErrorKind::UndefinedFunction {
+                        name: name.to_string(),
+                        suggestion,
+                    },
+                    span,
+                );
+                return;
+            }
+        };
+        self.bind(span, binding);
+    }
+
+    /// Checks that `name`, used at `span`, is a variant of the type declared at `declared` when
+    /// it is an enum.
+    fn variant(&mut self, declared: Span, name: &str, span: Span) {
+        let program = &self.program.modules[declared.file].program;
+        let decl = program
+            .pre_main
+            .iter()
+            .chain(&program.post_main)
+            .find_map(|decl| match decl {
+                Decl::Enum(decl) if decl.span == declared => Some(decl),
+                _ => None,
+            });
+        let Some(decl) = decl else {
+            return;
+        };
+        if decl.variants.iter().any(|variant| variant.name == name) {
+            return;
+        }
+        let candidates = decl.variants.iter().map(|variant| variant.name.as_str());
+        let suggestion = closest(name, candidates);
+        self.error(
+            NameErrorKind::UndefinedVariant {
+                name: name.to_string(),
+                ty: decl.name.clone(),
+                suggestion,
+            },
+            span,
+        );
+    }
+
+    /// Checks that `ty`, written in the declaration at `span`, exists.
+    fn ty(&mut self, ty: &Type, span: Span) {
+        let Type::Named(name) = ty else {
+            return;
+        };
+        if !self.types.contains_key(name.as_str()) {
+            let candidates = self.types.keys().copied().chain(PRIMITIVES);
+            let suggestion = closest(name, candidates);
+            self.error(
+                NameErrorKind::UndefinedType {
+                    name: name.clone(),
+                    suggestion,
+                },
+                span,
+            );
+        }
+    }
+
+    fn duplicate(&mut self, what: &'static str, name: &str, first: Span, span: Span) {
+        self.error(
+            NameErrorKind::Duplicate {
+                what,
+                name: name.to_string(),
+                first,
+            },
+            span,
+        );
+    }
+
+    fn error(&mut self, kind: NameErrorKind, span: Span) {
+        self.errors.push(NameError { kind, span });
+    }
+}
+
+/// The candidate closest to `name`, if it is close enough to be a misspelling of it: within
+/// an edit for every 3 characters, the case not counting. Any name is an edit away from a
+/// single character one, so those get no suggestion.
+fn closest<'n>(name: &str, candidates: impl Iterator<Item = &'n str>) -> Option<String> {
+    let length = name.chars().count();
+    if length < 2 {
+        return None;
+    }
+    let limit = (length / 3).max(1);
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| {
+            let distance = edit_distance(&name.to_lowercase(), &candidate.to_lowercase());
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// The optimal string alignment distance between `a` and `b`: the number of characters to
+/// insert, delete or replace, or of neighbouring characters to swap, to turn one into the
+/// other, no character being edited twice.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // `distances[i][j]` is the distance between the first `i` characters of `a` and the
+    // first `j` of `b`.
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let replace = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = replace
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
@@ -0,0 +1,974 @@
+use super::infer::{fill_in, Inferred, Kind, Table, Ty};
+use crate::error::{Diagnostic, Label, ToDiagnostic};
+use crate::eval::value::int_range;
+use crate::eval::{number_literal, RuntimeErrorKind};
+use crate::lex::Span;
+use crate::syn::ast::*;
+use std::collections::HashMap;
+
+/// Checks the types of `program` without running it: the values given to variables,
+/// parameters and `return`, the operands of the operators, the conditions and the patterns of
+/// `match`, and that a function that is not `void` returns on every path.
+///
+/// The rules are those the `Interpreter` applies as it runs. Both operands of an operator have
+/// the same type, so an `i32` is never used as an `i64` nor an `i64` as an `i32` without a
+/// conversion.
+///
+/// The types not written in the source are inferred: that of a `let` without one from its value
+/// and from how the variable is used later, that of a number literal without a suffix from the
+/// operands, variables, parameters and return values it meets, `i32` or `f64` when nothing
+/// constrains it. They are then written into `program`, so that the `Interpreter` and `compile`
+/// run it with the types it was checked with.
+///
+/// It runs after `resolve`, every name being found. The parts of the language that do not run
+/// yet are checked as far as their types are known: the fields of structs, the variants of enums,
+/// the elements of arrays and the methods of `impl`s. A field without `pub` can only be used in
+/// the module of its struct.
+pub fn check_types(program: &mut ProgramFile) -> Result<(), Vec<TypeError>> {
+    let inferred = infer(program)?;
+    fill_in(program, &inferred);
+    Ok(())
+}
+
+fn infer(program: &ProgramFile) -> Result<Inferred, Vec<TypeError>> {
+    let mut functions = HashMap::new();
+    let mut structs = HashMap::new();
+    let mut enums = HashMap::new();
+    let mut impls = HashMap::new();
+    for decl in program.pre_main.iter().chain(&program.post_main) {
+        match decl {
+            Decl::Fn(function) => {
+                functions.entry(function.name.as_str()).or_insert(function);
+            }
+            Decl::Struct(decl) => {
+                structs.entry(decl.name.as_str()).or_insert(decl);
+            }
+            Decl::Enum(decl) => {
+                enums.entry(decl.name.as_str()).or_insert(decl);
+            }
+            Decl::Impl(decl) => {
+                let methods: &mut HashMap<_, _> = impls.entry(decl.name.as_str()).or_default();
+                for function in &decl.fns {
+                    methods.entry(function.name.as_str()).or_insert(function);
+                }
+            }
+        }
+    }
+
+    let mut checker = Checker {
+        functions,
+        structs,
+        enums,
+        impls,
+        methods: None,
+        variables: Vec::new(),
+        ret: Type::Void,
+        table: Table::default(),
+        lets: Vec::new(),
+        literals: Vec::new(),
+        operations: Vec::new(),
+        errors: Vec::new(),
+    };
+    checker.function(&[], &Type::Void, &program.main.body);
+    for decl in program.pre_main.iter().chain(&program.post_main) {
+        match decl {
+            Decl::Fn(function) => checker.fn_decl(function),
+            Decl::Impl(decl) => {
+                checker.methods = checker.impls.get(decl.name.as_str()).cloned();
+                for function in &decl.fns {
+                    checker.fn_decl(function);
+                }
+                checker.methods = None;
+            }
+            Decl::Struct(_) | Decl::Enum(_) => {}
+        }
+    }
+    checker.finish()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeErrorKind {
+    /// A value of another type than the one expected, such as an `i64` passed for an `i32`
+    /// parameter.
+    MismatchedTypes { expected: Type, found: Type },
+    /// A binary operator applied to types it does not take, such as `true + 1` or an `i32`
+    /// added to an `i64`.
+    InvalidOperands { op: BinaryOp, lhs: Type, rhs: Type },
+    /// A unary operator applied to a type it does not take, such as `-` on a `u8`.
+    InvalidOperand { op: UnaryOp, ty: Type },
+    /// A literal that does not fit in the type it takes, such as `300` for a `u8`.
+    LiteralOutOfRange { literal: String, ty: Type },
+    WrongArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A function that is not `void` and can reach its end without a `return`.
+    MissingReturn { name: String, ty: Type },
+    /// A `let` without a type whose variable is never given a value nor used where the type
+    /// is known.
+    CannotInfer(String),
+    /// `.name` on a value of a type without a field `name`, such as a struct that does not
+    /// declare it or an `i32`.
+    NoField { ty: Type, name: String },
+    /// The field `name` of the struct `ty`, declared without `pub`, used in another module.
+    /// Reported with the code of `NameErrorKind::Private`, the same error for an item.
+    PrivateField { ty: Type, name: String },
+    /// A call of the method `name` on a value whose type has no such method.
+    NoMethod { ty: Type, name: String },
+    /// `[]` on a value that is not an array.
+    NotIndexable(Type),
+    /// A `let` without a type given the result of a `void` function.
+    VoidValue(String),
+}
+
+impl std::fmt::Display for TypeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TypeErrorKind::MismatchedTypes { expected, found } => {
+                write!(
+                    f,
+                    "mismatched types: expected `{}`, found `{}`",
+                    expected, found
+                )
+            }
+            TypeErrorKind::InvalidOperands { op, lhs, rhs } => {
+                write!(f, "cannot apply `{}` to `{}` and `{}`", op, lhs, rhs)
+            }
+            TypeErrorKind::InvalidOperand { op, ty } => {
+                write!(f, "cannot apply `{}` to `{}`", op, ty)
+            }
+            TypeErrorKind::LiteralOutOfRange { literal, ty } => {
+                write!(f, "literal `{}` is out of range for `{}`", literal, ty)
+            }
+            TypeErrorKind::WrongArgumentCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} argument{} but {} {} given",
+                name,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found,
+                if *found == 1 { "was" } else { "were" }
+            ),
+            TypeErrorKind::MissingReturn { name, .. } => {
+                write!(f, "`{}` can end without returning a value", name)
+            }
+            TypeErrorKind::CannotInfer(name) => {
+                write!(f, "cannot infer the type of `{}`", name)
+            }
+            TypeErrorKind::NoField { ty, name } => {
+                write!(f, "no field `{}` on type `{}`", name, ty)
+            }
+            TypeErrorKind::PrivateField { ty, name } => {
+                write!(f, "field `{}` of struct `{}` is private", name, ty)
+            }
+            TypeErrorKind::NoMethod { ty, name } => {
+                write!(f, "no method `{}` on type `{}`", name, ty)
+            }
+            TypeErrorKind::NotIndexable(ty) => {
+                write!(f, "cannot index into a value of type `{}`", ty)
+            }
+            TypeErrorKind::VoidValue(name) => {
+                write!(f, "`{}` is given the result of a `void` function", name)
+            }
+        }
+    }
+}
+
+impl TypeErrorKind {
+    /// The error code reported with the `Diagnostic`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeErrorKind::MismatchedTypes { .. } => "E0400",
+            TypeErrorKind::InvalidOperands { .. } => "E0401",
+            TypeErrorKind::InvalidOperand { .. } => "E0402",
+            TypeErrorKind::LiteralOutOfRange { .. } => "E0403",
+            TypeErrorKind::WrongArgumentCount { .. } => "E0404",
+            TypeErrorKind::MissingReturn { .. } => "E0405",
+            TypeErrorKind::CannotInfer(_) => "E0406",
+            TypeErrorKind::NoField { .. } => "E0407",
+            TypeErrorKind::PrivateField { .. } => "E0506",
+            TypeErrorKind::NoMethod { .. } => "E0408",
+            TypeErrorKind::NotIndexable(_) => "E0409",
+            TypeErrorKind::VoidValue(_) => "E0410",
+        }
+    }
+}
+
+impl ToDiagnostic for TypeError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let label = match &self.kind {
+            TypeErrorKind::MismatchedTypes { expected, found } => {
+                format!("expected `{}`, found `{}`", expected, found)
+            }
+            TypeErrorKind::InvalidOperands { .. } | TypeErrorKind::InvalidOperand { .. } => {
+                String::from("invalid operand types")
+            }
+            TypeErrorKind::LiteralOutOfRange { .. } => String::from("out of range"),
+            TypeErrorKind::WrongArgumentCount { expected, .. } => {
+                format!(
+                    "expected {} argument{}",
+                    expected,
+                    if *expected == 1 { "" } else { "s" }
+                )
+            }
+            TypeErrorKind::MissingReturn { ty, .. } => format!("declared to return `{}`", ty),
+            TypeErrorKind::CannotInfer(_) => {
+                String::from("the type of this variable is never given")
+            }
+            TypeErrorKind::NoField { .. } => String::from("unknown field"),
+            TypeErrorKind::PrivateField { .. } => String::from("private"),
+            TypeErrorKind::NoMethod { .. } => String::from("unknown method"),
+            TypeErrorKind::NotIndexable(_) => String::from("not an array"),
+            TypeErrorKind::VoidValue(_) => String::from("has no value"),
+        };
+        let diagnostic = Diagnostic::error(self.kind.to_string())
+            .with_code(self.kind.code())
+            .with_label(Label::primary(self.span, label));
+
+        match &self.kind {
+            TypeErrorKind::MismatchedTypes { expected, found }
+            | TypeErrorKind::InvalidOperands {
+                lhs: expected,
+                rhs: found,
+                ..
+            } if expected != found
+                && int_range(expected).is_some()
+                && int_range(found).is_some() =>
+            {
+                diagnostic.with_note("integers of different types are never converted implicitly")
+            }
+            TypeErrorKind::LiteralOutOfRange { ty, .. } => match int_range(ty) {
+                Some((min, max)) => {
+                    diagnostic.with_note(format!("`{}` holds values from {} to {}", ty, min, max))
+                }
+                None => diagnostic,
+            },
+            TypeErrorKind::MissingReturn { .. } => {
+                diagnostic.with_help("end every path through the function with a `return`")
+            }
+            TypeErrorKind::CannotInfer(name) => diagnostic.with_help(format!(
+                "give `{}` a type, as in `let {}: i32;`",
+                name, name
+            )),
+            TypeErrorKind::PrivateField { name, ty } => diagnostic
+                .with_note(format!(
+                    "`{}` is declared without `pub` in the module of `{}`",
+                    name, ty
+                ))
+                .with_help("declare it with `pub` to use it in other modules"),
+            TypeErrorKind::VoidValue(_) => {
+                diagnostic.with_help("call the function as a statement, without the `let`")
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.span.line)
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+struct Checker<'a> {
+    functions: HashMap<&'a str, &'a FnDecl>,
+    structs: HashMap<&'a str, &'a StructDecl>,
+    enums: HashMap<&'a str, &'a EnumDecl>,
+    /// The methods of the `impl`s, by the name of their type.
+    impls: HashMap<&'a str, HashMap<&'a str, &'a FnDecl>>,
+    /// The methods of the `impl` being checked, which its functions call without a value.
+    methods: Option<HashMap<&'a str, &'a FnDecl>>,
+    /// The variables in scope, innermost last.
+    variables: Vec<(&'a str, Ty)>,
+    /// Return type of the function being checked.
+    ret: Type,
+    table: Table,
+    /// The `let`s without a type.
+    lets: Vec<(&'a Stmt, &'a str, Ty)>,
+    /// The number literals without a suffix, with the `-` folded into them if there is one.
+    literals: Vec<(&'a Expr, Ty)>,
+    /// The operators applied, checked once the types of their operands are inferred.
+    operations: Vec<Operation>,
+    errors: Vec<TypeError>,
+}
+
+enum Operation {
+    Unary {
+        op: UnaryOp,
+        ty: Ty,
+        span: Span,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Ty,
+        rhs: Ty,
+        span: Span,
+    },
+}
+
+impl<'a> Checker<'a> {
+    fn fn_decl(&mut self, decl: &'a FnDecl) {
+        self.function(&decl.params, &decl.ret, &decl.body);
+        if decl.ret != Type::Void && !block_returns(&decl.body) {
+            self.error(
+                TypeErrorKind::MissingReturn {
+                    name: decl.name.clone(),
+                    ty: decl.ret.clone(),
+                },
+                decl.span,
+            );
+        }
+    }
+
+    fn function(&mut self, params: &'a [Param], ret: &Type, body: &'a Block) {
+        self.variables = params
+            .iter()
+            .map(|param| (param.name.as_str(), Ty::Known(param.ty.clone())))
+            .collect();
+        self.ret = ret.clone();
+        self.block(body);
+    }
+
+    fn block(&mut self, block: &'a Block) {
+        let scope = self.variables.len();
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        self.variables.truncate(scope);
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match &stmt.kind {
+            StmtKind::Let {
+                name, ty, value, ..
+            } => {
+                let ty = match (ty, value) {
+                    (Some(ty), Some(value)) => {
+                        let ty = Ty::Known(ty.clone());
+                        self.expect(value, &ty);
+                        ty
+                    }
+                    (Some(ty), None) => Ty::Known(ty.clone()),
+                    (None, Some(value)) => {
+                        let ty = self.expr(value);
+                        if self.table.known(&ty) == Some(Type::Void) {
+                            self.error(TypeErrorKind::VoidValue(name.clone()), value.span);
+                            Ty::Unknown
+                        } else {
+                            ty
+                        }
+                    }
+                    (None, None) => self.table.fresh(Kind::Any),
+                };
+                if ty_missing(stmt) {
+                    self.lets.push((stmt, name, ty.clone()));
+                }
+                self.variables.push((name, ty));
+            }
+            StmtKind::Assign { target, op, value } => self.assign(target, *op, value),
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.expect(cond, &Ty::Known(Type::Bool));
+                self.block(then);
+                match otherwise {
+                    Some(Else::If(stmt)) => self.stmt(stmt),
+                    Some(Else::Block(block)) => self.block(block),
+                    None => {}
+                }
+            }
+            StmtKind::Match { scrutinee, arms } => {
+                let ty = self.expr(scrutinee);
+                for arm in arms {
+                    if let Pattern::Expr(pattern) = &arm.pattern {
+                        self.expect(pattern, &ty);
+                    }
+                    self.block(&arm.body);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                self.expect(cond, &Ty::Known(Type::Bool));
+                self.block(body);
+            }
+            StmtKind::Return(value) => {
+                let ret = Ty::Known(self.ret.clone());
+                match value {
+                    Some(value) => self.expect(value, &ret),
+                    None if self.ret == Type::Void => {}
+                    None => self.error(
+                        TypeErrorKind::MismatchedTypes {
+                            expected: self.ret.clone(),
+                            found: Type::Void,
+                        },
+                        stmt.span,
+                    ),
+                }
+            }
+            StmtKind::Break | StmtKind::Continue => {}
+            StmtKind::Expr(expr) => {
+                self.expr(expr);
+            }
+        }
+    }
+
+    /// `target <- value` or `target op= value`.
+    fn assign(&mut self, target: &'a Expr, op: Option<BinaryOp>, value: &'a Expr) {
+        let ty = match &target.kind {
+            ExprKind::Variable(_) | ExprKind::Group(_) => self.expr(target),
+            _ => {
+                self.expr(target);
+                self.expr(value);
+                return;
+            }
+        };
+        match op {
+            Some(op) => {
+                let rhs = self.expr(value);
+                let span = target.span.to(value.span);
+                let result = self.operator(op, ty.clone(), rhs, span);
+                if !self.table.unify(&result, &ty) {
+                    self.error(
+                        TypeErrorKind::MismatchedTypes {
+                            expected: self.table.show(&ty),
+                            found: self.table.show(&result),
+                        },
+                        span,
+                    );
+                }
+            }
+            None => self.expect(value, &ty),
+        }
+    }
+
+    /// Checks that `expr` is a `ty`.
+    fn expect(&mut self, expr: &'a Expr, ty: &Ty) {
+        let found = self.expr(expr);
+        if !self.table.unify(&found, ty) {
+            self.error(
+                TypeErrorKind::MismatchedTypes {
+                    expected: self.table.show(ty),
+                    found: self.table.show(&found),
+                },
+                expr.span,
+            );
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) -> Ty {
+        if let Some(literal) = number(expr) {
+            return self.number(expr, literal);
+        }
+
+        match &expr.kind {
+            ExprKind::Literal(literal) => match literal {
+                Literal::Str(_) => Ty::Known(Type::String),
+                Literal::Char(_) => Ty::Known(Type::Char),
+                Literal::Byte(_) => Ty::Known(Type::Byte),
+                Literal::Bool(_) => Ty::Known(Type::Bool),
+                Literal::ByteStr(_) | Literal::Int { .. } | Literal::Float { .. } => Ty::Unknown,
+            },
+            ExprKind::Variable(name) => self
+                .variables
+                .iter()
+                .rev()
+                .find(|(var, _)| var == name)
+                .map_or(Ty::Unknown, |(_, ty)| ty.clone()),
+            ExprKind::Group(inner) => self.expr(inner),
+            ExprKind::Unary { op, expr: operand } => {
+                let ty = self.expr(operand);
+                if *op == UnaryOp::Not {
+                    let boolean = Ty::Known(Type::Bool);
+                    if !self.table.unify(&ty, &boolean) {
+                        let ty = self.table.show(&ty);
+                        self.error(TypeErrorKind::InvalidOperand { op: *op, ty }, expr.span);
+                    }
+                    return boolean;
+                }
+                self.operations.push(Operation::Unary {
+                    op: *op,
+                    ty: ty.clone(),
+                    span: expr.span,
+                });
+                ty
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                let lhs = self.expr(lhs);
+                let rhs = self.expr(rhs);
+                self.operator(*op, lhs, rhs, expr.span)
+            }
+            ExprKind::Call { callee, args } => self.call(callee, args, expr.span),
+            ExprKind::Interpolated(parts) => {
+                for part in parts {
+                    if let InterpolatedPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+                Ty::Known(Type::String)
+            }
+            ExprKind::Array(items) => {
+                // The elements take the type of the first one.
+                let elem = self.table.fresh(Kind::Any);
+                for item in items {
+                    self.expect(item, &elem);
+                }
+                Ty::Array(Box::new(elem))
+            }
+            ExprKind::Index { expr: array, index } => {
+                let ty = self.expr(array);
+                self.expr(index);
+                let elem = self.table.fresh(Kind::Any);
+                if self.table.unify(&ty, &Ty::Array(Box::new(elem.clone()))) {
+                    elem
+                } else {
+                    let ty = self.table.show(&ty);
+                    self.error(TypeErrorKind::NotIndexable(ty), array.span);
+                    Ty::Unknown
+                }
+            }
+            ExprKind::Field { expr: inner, name } => self.field(inner, name, expr.span),
+        }
+    }
+
+    /// The type of `inner.name`, a field of a struct or a variant of an enum.
+    fn field(&mut self, inner: &'a Expr, name: &str, span: Span) -> Ty {
+        if let Some(decl) = self.enum_named(inner) {
+            // The resolver reported the variants that do not exist.
+            return match decl.variants.iter().any(|variant| variant.name == name) {
+                true => Ty::Known(Type::Named(decl.name.clone())),
+                false => Ty::Unknown,
+            };
+        }
+        let ty = self.expr(inner);
+        let Some(ty) = self.table.known_or_number(&ty) else {
+            return Ty::Unknown;
+        };
+        let field = match &ty {
+            Type::Named(ty) => self
+                .structs
+                .get(ty.as_str())
+                .and_then(|decl| decl.fields.iter().find(|field| field.name == name)),
+            _ => None,
+        };
+        match field {
+            Some(field) => {
+                if !field.public && field.span.file != span.file {
+                    let name = name.to_string();
+                    self.error(TypeErrorKind::PrivateField { ty, name }, span);
+                }
+                Ty::Known(field.ty.clone())
+            }
+            None => {
+                let name = name.to_string();
+                self.error(TypeErrorKind::NoField { ty, name }, span);
+                Ty::Unknown
+            }
+        }
+    }
+
+    /// The enum `expr` names, as `Color` in `Color.Red`, when it is not a variable.
+    fn enum_named(&self, expr: &Expr) -> Option<&'a EnumDecl> {
+        let ExprKind::Variable(name) = &expr.kind else {
+            return None;
+        };
+        if self.variables.iter().any(|(variable, _)| variable == name) {
+            return None;
+        }
+        self.enums.get(name.as_str()).copied()
+    }
+
+    /// The type of the number literal `expr`, `literal` possibly negated. Without a suffix,
+    /// a variable to be inferred.
+    fn number(&mut self, expr: &'a Expr, literal: &Literal) -> Ty {
+        let suffix = match literal {
+            Literal::Int { suffix, .. } | Literal::Float { suffix, .. } => suffix,
+            _ => unreachable!("`number` only returns number literals"),
+        };
+        match suffix {
+            Some(ty) => {
+                self.literal_range(expr, ty);
+                Ty::Known(ty.clone())
+            }
+            None => {
+                let kind = match literal {
+                    Literal::Float { .. } => Kind::Float,
+                    _ => Kind::Int,
+                };
+                let ty = self.table.fresh(kind);
+                self.literals.push((expr, ty.clone()));
+                ty
+            }
+        }
+    }
+
+    /// Reports the number literal `expr` when it does not fit in `ty`.
+    fn literal_range(&mut self, expr: &Expr, ty: &Type) {
+        let Err(error) = number_literal(expr, Some(ty)) else {
+            return;
+        };
+        let kind = match error.kind {
+            RuntimeErrorKind::LiteralOutOfRange { literal, ty } => {
+                TypeErrorKind::LiteralOutOfRange { literal, ty }
+            }
+            RuntimeErrorKind::InvalidOperand { op, ty } => TypeErrorKind::InvalidOperand { op, ty },
+            _ => return,
+        };
+        self.error(kind, error.span);
+    }
+
+    /// The type of `lhs op rhs`. Both operands take the same type, but for the amount of a
+    /// shift, and whether `op` takes it is checked once it is inferred.
+    fn operator(&mut self, op: BinaryOp, lhs: Ty, rhs: Ty, span: Span) -> Ty {
+        let boolean = Ty::Known(Type::Bool);
+        match op {
+            BinaryOp::Shl | BinaryOp::Shr => {
+                self.operations.push(Operation::Binary {
+                    op,
+                    lhs: lhs.clone(),
+                    rhs,
+                    span,
+                });
+                lhs
+            }
+            BinaryOp::And | BinaryOp::Or => {
+                if !self.table.unify(&lhs, &boolean) || !self.table.unify(&rhs, &boolean) {
+                    self.error(
+                        TypeErrorKind::InvalidOperands {
+                            op,
+                            lhs: self.table.show(&lhs),
+                            rhs: self.table.show(&rhs),
+                        },
+                        span,
+                    );
+                }
+                boolean
+            }
+            _ => {
+                let comparison = matches!(
+                    op,
+                    BinaryOp::Eq
+                        | BinaryOp::Ne
+                        | BinaryOp::Lt
+                        | BinaryOp::Le
+                        | BinaryOp::Gt
+                        | BinaryOp::Ge
+                );
+                if self.table.unify(&lhs, &rhs) {
+                    self.operations.push(Operation::Binary {
+                        op,
+                        lhs: lhs.clone(),
+                        rhs,
+                        span,
+                    });
+                } else {
+                    self.error(
+                        TypeErrorKind::InvalidOperands {
+                            op,
+                            lhs: self.table.show(&lhs),
+                            rhs: self.table.show(&rhs),
+                        },
+                        span,
+                    );
+                    if !comparison {
+                        return Ty::Unknown;
+                    }
+                }
+                if comparison {
+                    boolean
+                } else {
+                    lhs
+                }
+            }
+        }
+    }
+
+    fn call(&mut self, callee: &'a Expr, args: &'a [Expr], span: Span) -> Ty {
+        let name = match &callee.kind {
+            ExprKind::Variable(name) => name.as_str(),
+            ExprKind::Field { expr, name } => return self.method_call(expr, name, args, span),
+            _ => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+                return Ty::Unknown;
+            }
+        };
+
+        let method = self.methods.as_ref().and_then(|methods| methods.get(name));
+        let Some(function) = method.or(self.functions.get(name)).copied() else {
+            for arg in args {
+                self.expr(arg);
+            }
+            if !matches!(name, "print" | "println") {
+                return Ty::Unknown;
+            }
+            // `println()` prints an empty line.
+            if args.len() > 1 || (args.is_empty() && name == "print") {
+                self.error(
+                    TypeErrorKind::WrongArgumentCount {
+                        name: name.to_string(),
+                        expected: 1,
+                        found: args.len(),
+                    },
+                    span,
+                );
+            }
+            return Ty::Known(Type::Void);
+        };
+
+        self.args(function, args, span)
+    }
+
+    /// `value.name(args)`, a call of a method of the type of `value`.
+    fn method_call(&mut self, value: &'a Expr, name: &str, args: &'a [Expr], span: Span) -> Ty {
+        let ty = self.expr(value);
+        let ty = self.table.known_or_number(&ty);
+        let method = match &ty {
+            Some(Type::Named(ty)) => self
+                .impls
+                .get(ty.as_str())
+                .and_then(|methods| methods.get(name))
+                .copied(),
+            _ => None,
+        };
+        match (method, ty) {
+            (Some(method), _) => self.args(method, args, span),
+            (None, ty) => {
+                if let Some(ty) = ty {
+                    let name = name.to_string();
+                    self.error(TypeErrorKind::NoMethod { ty, name }, span);
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+                Ty::Unknown
+            }
+        }
+    }
+
+    /// Checks the `args` of a call of `function`, giving the type it returns.
+    fn args(&mut self, function: &FnDecl, args: &'a [Expr], span: Span) -> Ty {
+        let name = &function.name;
+        if args.len() != function.params.len() {
+            self.error(
+                TypeErrorKind::WrongArgumentCount {
+                    name: name.to_string(),
+                    expected: function.params.len(),
+                    found: args.len(),
+                },
+                span,
+            );
+        }
+        for (index, arg) in args.iter().enumerate() {
+            match function.params.get(index) {
+                Some(param) => self.expect(arg, &Ty::Known(param.ty.clone())),
+                None => {
+                    self.expr(arg);
+                }
+            }
+        }
+        Ty::Known(function.ret.clone())
+    }
+
+    /// Defaults the literals nothing constrained and checks what needed the types inferred.
+    fn finish(mut self) -> Result<Inferred, Vec<TypeError>> {
+        self.table.default_numbers();
+        for (stmt, name, ty) in &self.lets {
+            if self.table.is_free(ty) {
+                let kind = TypeErrorKind::CannotInfer(name.to_string());
+                self.errors.push(TypeError {
+                    kind,
+                    span: stmt.span,
+                });
+            }
+        }
+
+        let mut inferred = Inferred::default();
+        for (expr, ty) in std::mem::take(&mut self.literals) {
+            if let Some(ty) = self.table.known(&ty) {
+                self.literal_range(expr, &ty);
+                let literal = match &expr.kind {
+                    ExprKind::Unary { expr, .. } => expr,
+                    _ => expr,
+                };
+                inferred
+                    .literals
+                    .insert((literal.span.file, literal.span.start), ty);
+            }
+        }
+        for (stmt, _, ty) in &self.lets {
+            if let Some(ty) = self.table.known(ty) {
+                inferred.lets.insert((stmt.span.file, stmt.span.start), ty);
+            }
+        }
+        for operation in std::mem::take(&mut self.operations) {
+            match operation {
+                Operation::Unary { op, ty, span } => {
+                    if let Some(ty) = self.table.known(&ty) {
+                        if unary_type(op, &ty).is_none() {
+                            self.error(TypeErrorKind::InvalidOperand { op, ty }, span);
+                        }
+                    }
+                }
+                Operation::Binary { op, lhs, rhs, span } => {
+                    if let (Some(lhs), Some(rhs)) = (self.table.known(&lhs), self.table.known(&rhs))
+                    {
+                        if binary_type(op, &lhs, &rhs).is_none() {
+                            self.error(TypeErrorKind::InvalidOperands { op, lhs, rhs }, span);
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(inferred)
+        } else {
+            // In the order of the source, `main` being checked first.
+            self.errors
+                .sort_by_key(|error| (error.span.file, error.span.start));
+            Err(self.errors)
+        }
+    }
+
+    fn error(&mut self, kind: TypeErrorKind, span: Span) {
+        self.errors.push(TypeError { kind, span });
+    }
+}
+
+/// Checks whether `stmt` is a `let` without a type.
+fn ty_missing(stmt: &Stmt) -> bool {
+    matches!(stmt.kind, StmtKind::Let { ty: None, .. })
+}
+
+/// The literal of a number literal, possibly negated, as `number_literal` takes them.
+fn number(expr: &Expr) -> Option<&Literal> {
+    let literal = match &expr.kind {
+        ExprKind::Literal(literal) => literal,
+        ExprKind::Unary {
+            op: UnaryOp::Neg,
+            expr: inner,
+        } => match &inner.kind {
+            ExprKind::Literal(literal) => literal,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    matches!(literal, Literal::Int { .. } | Literal::Float { .. }).then_some(literal)
+}
+
+/// The type of `op` applied to a `ty`, `None` when `op` does not take it. As `value::unary`.
+fn unary_type(op: UnaryOp, ty: &Type) -> Option<Type> {
+    let signed = int_range(ty).is_some_and(|(min, _)| min < 0);
+    let takes = match op {
+        UnaryOp::Neg => signed || matches!(ty, Type::F32 | Type::F64),
+        UnaryOp::Bang => *ty == Type::Bool || int_range(ty).is_some(),
+        UnaryOp::Not => *ty == Type::Bool,
+    };
+    takes.then(|| ty.clone())
+}
+
+/// The type of `lhs op rhs`, `None` when `op` does not take them. As `value::binary`.
+fn binary_type(op: BinaryOp, lhs: &Type, rhs: &Type) -> Option<Type> {
+    let int = int_range(lhs).is_some();
+    if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+        return (int && int_range(rhs).is_some()).then(|| lhs.clone());
+    }
+    if lhs != rhs {
+        return None;
+    }
+
+    let takes = match op {
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            return Some(Type::Bool)
+        }
+        BinaryOp::Add => int || matches!(lhs, Type::F32 | Type::F64 | Type::String),
+        BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem | BinaryOp::Pow => {
+            int || matches!(lhs, Type::F32 | Type::F64)
+        }
+        BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor => int || *lhs == Type::Bool,
+        BinaryOp::And | BinaryOp::Or => *lhs == Type::Bool,
+        BinaryOp::Shl | BinaryOp::Shr => unreachable!("shifts are checked above"),
+    };
+    takes.then(|| lhs.clone())
+}
+
+/// Checks whether running `block` always ends in a `return`, or never ends.
+fn block_returns(block: &Block) -> bool {
+    block.stmts.iter().any(stmt_returns)
+}
+
+fn stmt_returns(stmt: &Stmt) -> bool {
+    match &stmt.kind {
+        StmtKind::Return(_) => true,
+        StmtKind::If {
+            then,
+            otherwise: Some(otherwise),
+            ..
+        } => {
+            block_returns(then)
+                && match otherwise {
+                    Else::If(stmt) => stmt_returns(stmt),
+                    Else::Block(block) => block_returns(block),
+                }
+        }
+        // The arms after a `_` are never reached.
+        StmtKind::Match { arms, .. } => match arms
+            .iter()
+            .position(|arm| matches!(arm.pattern, Pattern::Wildcard))
+        {
+            Some(wildcard) => arms[..=wildcard].iter().all(|arm| block_returns(&arm.body)),
+            None => false,
+        },
+        // `while true` without a `break` only ends by returning.
+        StmtKind::While { cond, body } => is_true(cond) && !block_breaks(body),
+        _ => false,
+    }
+}
+
+fn is_true(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Literal(Literal::Bool(value)) => *value,
+        ExprKind::Group(inner) => is_true(inner),
+        _ => false,
+    }
+}
+
+/// Checks whether `block` has a `break` out of the loop it is the body of.
+fn block_breaks(block: &Block) -> bool {
+    block.stmts.iter().any(stmt_breaks)
+}
+
+fn stmt_breaks(stmt: &Stmt) -> bool {
+    match &stmt.kind {
+        StmtKind::Break => true,
+        StmtKind::If {
+            then, otherwise, ..
+        } => {
+            block_breaks(then)
+                || match otherwise {
+                    Some(Else::If(stmt)) => stmt_breaks(stmt),
+                    Some(Else::Block(block)) => block_breaks(block),
+                    None => false,
+                }
+        }
+        StmtKind::Match { arms, .. } => arms.iter().any(|arm| block_breaks(&arm.body)),
+        // A `break` in a nested `while` ends that loop.
+        _ => false,
+    }
+}
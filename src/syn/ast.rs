@@ -0,0 +1,442 @@
+use crate::lex::Span;
+
+/// `ProgramFile` => `ImportDeclList` `DeclList` `MainFn` `DeclList`
+#[derive(Debug, Clone)]
+pub struct ProgramFile {
+    /// Lines of the `//!` doc comments at the start of the file.
+    pub docs: Vec<String>,
+    pub imports: Vec<ImportDecl>,
+    /// The `DeclList` before `MainFn`.
+    pub pre_main: Vec<Decl>,
+    pub main: MainFn,
+    /// The `DeclList` after `MainFn`.
+    pub post_main: Vec<Decl>,
+    pub span: Span,
+}
+
+/// `ImportDecl` => import `ModulePath` `ImportTail`;
+#[derive(Debug, Clone)]
+pub struct ImportDecl {
+    /// `math.vector` as `["math", "vector"]`.
+    pub path: Vec<String>,
+    /// `vec` in `import math.vector as vec;`.
+    pub alias: Option<String>,
+    /// The items of `import math.vector.{dot, Vec2 as V};`, `None` when the module itself is
+    /// imported.
+    pub items: Option<Vec<ImportItem>>,
+    pub span: Span,
+}
+
+impl ImportDecl {
+    /// The name the module is known by in the importing file: its alias, or the last part of its
+    /// path.
+    pub fn name(&self) -> &str {
+        self.alias
+            .as_deref()
+            .unwrap_or_else(|| self.path.last().map_or("", String::as_str))
+    }
+}
+
+/// `ImportItem` => `ID` | `ID` as `ID`
+#[derive(Debug, Clone)]
+pub struct ImportItem {
+    pub name: String,
+    pub alias: Option<String>,
+    pub span: Span,
+}
+
+impl ImportItem {
+    /// The name the item is known by in the importing file.
+    pub fn local_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// `Decl` => `StructDecl` | `EnumDecl` | `ImplDecl` | `FnDecl`
+#[derive(Debug, Clone)]
+pub enum Decl {
+    Struct(StructDecl),
+    Enum(EnumDecl),
+    Impl(ImplDecl),
+    Fn(FnDecl),
+}
+
+/// `StructDecl` => `AccessDecl` struct `ID` { `StructTypeDeclList` }
+#[derive(Debug, Clone)]
+pub struct StructDecl {
+    /// Lines of the `///` doc comments in front of it.
+    pub docs: Vec<String>,
+    pub public: bool,
+    pub name: String,
+    pub fields: Vec<StructField>,
+    pub span: Span,
+}
+
+/// `StructTypeDecl` => `AccessDecl` `Type` `ID`
+#[derive(Debug, Clone)]
+pub struct StructField {
+    /// Lines of the `///` doc comments in front of it.
+    pub docs: Vec<String>,
+    pub public: bool,
+    pub ty: Type,
+    pub name: String,
+    pub span: Span,
+}
+
+/// `EnumDecl` => `AccessDecl` enum `ID` { `EnumTypeDeclList` }
+#[derive(Debug, Clone)]
+pub struct EnumDecl {
+    /// Lines of the `///` doc comments in front of it.
+    pub docs: Vec<String>,
+    pub public: bool,
+    pub name: String,
+    pub variants: Vec<EnumVariant>,
+    pub span: Span,
+}
+
+/// A single `ID` of the `EnumTypeDeclList`.
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    /// Lines of the `///` doc comments in front of it.
+    pub docs: Vec<String>,
+    pub name: String,
+    pub span: Span,
+}
+
+/// `ImplDecl` => impl `ID` { `FnDeclList` }
+#[derive(Debug, Clone)]
+pub struct ImplDecl {
+    /// Lines of the `///` doc comments in front of it.
+    pub docs: Vec<String>,
+    pub name: String,
+    pub fns: Vec<FnDecl>,
+    pub span: Span,
+}
+
+/// `FnDecl` => `AccessDecl` `Type` `ID` (`FnTypeDeclList`){ `CompoundDeclList` }
+#[derive(Debug, Clone)]
+pub struct FnDecl {
+    /// Lines of the `///` doc comments in front of it.
+    pub docs: Vec<String>,
+    pub public: bool,
+    pub ret: Type,
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// `FnTypeDecl` => `Type` `ID`
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub ty: Type,
+    pub name: String,
+    pub span: Span,
+}
+
+/// `MainFn` => `AccessDecl` void main(){  `CompoundDeclList` }
+#[derive(Debug, Clone)]
+pub struct MainFn {
+    /// Lines of the `///` doc comments in front of it.
+    pub docs: Vec<String>,
+    pub public: bool,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// `Type` => `PrimitiveType` | void | `ID`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Type {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bool,
+    Byte,
+    Char,
+    String,
+    #[default]
+    Void,
+    /// A `struct` or `enum` referred to by name.
+    Named(String),
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Type::I8 => write!(f, "i8"),
+            Type::I16 => write!(f, "i16"),
+            Type::I32 => write!(f, "i32"),
+            Type::I64 => write!(f, "i64"),
+            Type::U8 => write!(f, "u8"),
+            Type::U16 => write!(f, "u16"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
+            Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
+            Type::Bool => write!(f, "bool"),
+            Type::Byte => write!(f, "byte"),
+            Type::Char => write!(f, "char"),
+            Type::String => write!(f, "String"),
+            Type::Void => write!(f, "void"),
+            Type::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// `CompoundDeclList` wrapped in `{` `}`.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub span: Span,
+}
+
+/// `VarConLoopDecl`
+#[derive(Debug, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
+    /// `VariableDecl` and `VariableInit`, `let` `MutDecl` `ID` (: `Type`)? (<- `Expression`)?;
+    Let {
+        mutable: bool,
+        name: String,
+        ty: Option<Type>,
+        value: Option<Expr>,
+    },
+    /// `Place` <- `Expression`; or a compound assignment such as `Place` += `Expression`;
+    Assign {
+        target: Expr,
+        /// The `+` of `+=`, `None` for `<-`.
+        op: Option<BinaryOp>,
+        value: Expr,
+    },
+    /// if `Expression` { `CompoundDeclList` } `ElseStm`
+    If {
+        cond: Expr,
+        then: Block,
+        otherwise: Option<Else>,
+    },
+    /// match `Expression` { `MatchArmList` }
+    Match {
+        scrutinee: Expr,
+        arms: Vec<MatchArm>,
+    },
+    /// while `Expression` { `CompoundDeclList` }
+    While { cond: Expr, body: Block },
+    /// return `Expression`?;
+    Return(Option<Expr>),
+    /// break;
+    Break,
+    /// continue;
+    Continue,
+    /// `Expression`;
+    Expr(Expr),
+}
+
+/// `ElseStm` => else `ConditionalStm` | else { `CompoundDeclList` }
+#[derive(Debug, Clone)]
+pub enum Else {
+    If(Box<Stmt>),
+    Block(Block),
+}
+
+/// `MatchArm` => `Pattern` => { `CompoundDeclList` }
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// `Pattern` => _ | `Expression`
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Wildcard,
+    /// Matches when the scrutinee is equal to the expression.
+    Expr(Expr),
+}
+
+/// `Expression`
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    Literal(Literal),
+    /// `variableName`
+    Variable(String),
+    /// ( `Expression` )
+    Group(Box<Expr>),
+    /// [ `ExpressionList` ]
+    Array(Vec<Expr>),
+    /// `UnaryOp` `Expression`
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    /// `Expression` `BinaryOp` `Expression`
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    /// `Expression` ( `ExpressionList` )
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// `Expression` . `ID`
+    Field {
+        expr: Box<Expr>,
+        name: String,
+    },
+    /// `Expression` [ `Expression` ]
+    Index {
+        expr: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// `"total: {a + b}"`
+    Interpolated(Vec<InterpolatedPart>),
+}
+
+/// A piece of an interpolated string.
+#[derive(Debug, Clone)]
+pub enum InterpolatedPart {
+    /// Text, with `{{` and `}}` already turned into `{` and `}`.
+    Str { value: String, span: Span },
+    /// An expression between `{` and `}`.
+    Expr(Expr),
+}
+
+impl Expr {
+    /// Checks whether the expression can appear on the left of `<-`.
+    pub fn is_place(&self) -> bool {
+        match &self.kind {
+            ExprKind::Variable(_) => true,
+            ExprKind::Field { expr, .. } | ExprKind::Index { expr, .. } => expr.is_place(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `-`
+    Neg,
+    /// `!`, logical not on `bool` and bitwise not on integers.
+    Bang,
+    /// `not`, logical not.
+    Not,
+}
+
+impl std::fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            UnaryOp::Neg => write!(f, "-"),
+            UnaryOp::Bang => write!(f, "!"),
+            UnaryOp::Not => write!(f, "not"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Rem,
+    /// `**`
+    Pow,
+    /// `&`
+    BitAnd,
+    /// `|`
+    BitOr,
+    /// `^`
+    BitXor,
+    /// `<<`
+    Shl,
+    /// `>>`
+    Shr,
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `and` or `&&`
+    And,
+    /// `or` or `||`
+    Or,
+}
+
+impl std::fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BinaryOp::Add => write!(f, "+"),
+            BinaryOp::Sub => write!(f, "-"),
+            BinaryOp::Mul => write!(f, "*"),
+            BinaryOp::Div => write!(f, "/"),
+            BinaryOp::Rem => write!(f, "%"),
+            BinaryOp::Pow => write!(f, "**"),
+            BinaryOp::BitAnd => write!(f, "&"),
+            BinaryOp::BitOr => write!(f, "|"),
+            BinaryOp::BitXor => write!(f, "^"),
+            BinaryOp::Shl => write!(f, "<<"),
+            BinaryOp::Shr => write!(f, ">>"),
+            BinaryOp::Eq => write!(f, "=="),
+            BinaryOp::Ne => write!(f, "!="),
+            BinaryOp::Lt => write!(f, "<"),
+            BinaryOp::Le => write!(f, "<="),
+            BinaryOp::Gt => write!(f, ">"),
+            BinaryOp::Ge => write!(f, ">="),
+            BinaryOp::And => write!(f, "and"),
+            BinaryOp::Or => write!(f, "or"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    /// `255`, `0xFF`, `255u8`
+    Int {
+        value: u64,
+        suffix: Option<Type>,
+    },
+    /// `1.5`, `2e10`, `1.5f32`
+    Float {
+        value: f64,
+        suffix: Option<Type>,
+    },
+    Str(String),
+    Char(char),
+    /// `b'c'`
+    Byte(u8),
+    /// `b"bytes"`
+    ByteStr(Vec<u8>),
+    Bool(bool),
+}
@@ -0,0 +1,311 @@
+use super::ast::*;
+use crate::json;
+use std::io::{self, Write};
+
+/// Writes `program` as an S-expression, without spans or doc comments.
+///
+/// Declarations and statements are written a line each, indented by how deep they are, and
+/// expressions on the line of their statement, so that the output grows with the source:
+///
+/// ```text
+/// (program
+///   (fn i32 add ((i32 a) (i32 b))
+///     (return (+ a b)))
+///   (main
+///     (call println (call add 1 2))))
+/// ```
+pub fn write_tree(out: &mut impl Write, program: &ProgramFile) -> io::Result<()> {
+    let mut writer = TreeWriter { out, indent: 0 };
+    writer.program(program)?;
+    writeln!(writer.out)
+}
+
+struct TreeWriter<'a, W: Write> {
+    out: &'a mut W,
+    /// How many levels deep the current line is.
+    indent: usize,
+}
+
+impl<W: Write> TreeWriter<'_, W> {
+    /// Starts a line with `(` and `head`, the children of which are indented one level deeper.
+    fn open(&mut self, head: &str) -> io::Result<()> {
+        if self.indent > 0 {
+            writeln!(self.out)?;
+        }
+        write!(self.out, "{}({}", "  ".repeat(self.indent), head)?;
+        self.indent += 1;
+        Ok(())
+    }
+
+    /// Closes the parenthesis of the last `open`.
+    fn close(&mut self) -> io::Result<()> {
+        self.indent -= 1;
+        write!(self.out, ")")
+    }
+
+    fn program(&mut self, program: &ProgramFile) -> io::Result<()> {
+        self.open("program")?;
+        for import in &program.imports {
+            self.import(import)?;
+        }
+        for decl in &program.pre_main {
+            self.decl(decl)?;
+        }
+        self.open(&format!("main{}", public(program.main.public)))?;
+        self.stmts(&program.main.body)?;
+        self.close()?;
+        for decl in &program.post_main {
+            self.decl(decl)?;
+        }
+        self.close()
+    }
+
+    fn import(&mut self, import: &ImportDecl) -> io::Result<()> {
+        self.open(&format!("import {}", import.path.join(".")))?;
+        if let Some(alias) = &import.alias {
+            write!(self.out, " as {}", alias)?;
+        }
+        if let Some(items) = &import.items {
+            write!(self.out, " (")?;
+            for (i, item) in items.iter().enumerate() {
+                let separator = if i == 0 { "" } else { " " };
+                match &item.alias {
+                    Some(alias) => write!(self.out, "{}({} as {})", separator, item.name, alias)?,
+                    None => write!(self.out, "{}{}", separator, item.name)?,
+                }
+            }
+            write!(self.out, ")")?;
+        }
+        self.close()
+    }
+
+    fn decl(&mut self, decl: &Decl) -> io::Result<()> {
+        match decl {
+            Decl::Struct(decl) => {
+                self.open(&format!("struct{} {}", public(decl.public), decl.name))?;
+                for field in &decl.fields {
+                    self.open(&format!(
+                        "field{} {} {}",
+                        public(field.public),
+                        field.ty,
+                        field.name
+                    ))?;
+                    self.close()?;
+                }
+                self.close()
+            }
+            Decl::Enum(decl) => {
+                self.open(&format!("enum{} {}", public(decl.public), decl.name))?;
+                for variant in &decl.variants {
+                    write!(self.out, " {}", variant.name)?;
+                }
+                self.close()
+            }
+            Decl::Impl(decl) => {
+                self.open(&format!("impl {}", decl.name))?;
+                for function in &decl.fns {
+                    self.function(function)?;
+                }
+                self.close()
+            }
+            Decl::Fn(function) => self.function(function),
+        }
+    }
+
+    fn function(&mut self, function: &FnDecl) -> io::Result<()> {
+        let params = function
+            .params
+            .iter()
+            .map(|param| format!("({} {})", param.ty, param.name))
+            .collect::<Vec<_>>();
+        self.open(&format!(
+            "fn{} {} {} ({})",
+            public(function.public),
+            function.ret,
+            function.name,
+            params.join(" ")
+        ))?;
+        self.stmts(&function.body)?;
+        self.close()
+    }
+
+    fn stmts(&mut self, block: &Block) -> io::Result<()> {
+        for stmt in &block.stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, block: &Block) -> io::Result<()> {
+        self.open("block")?;
+        self.stmts(block)?;
+        self.close()
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> io::Result<()> {
+        match &stmt.kind {
+            StmtKind::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => {
+                let mutable = if *mutable { " mut" } else { "" };
+                self.open(&format!("let{} {}", mutable, name))?;
+                if let Some(ty) = ty {
+                    write!(self.out, " (: {})", ty)?;
+                }
+                if let Some(value) = value {
+                    self.expr_after(value)?;
+                }
+                self.close()
+            }
+            StmtKind::Assign { target, op, value } => {
+                let op = op.map_or_else(|| "<-".to_string(), |op| format!("{}=", op));
+                self.open(&op)?;
+                self.expr_after(target)?;
+                self.expr_after(value)?;
+                self.close()
+            }
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.open("if")?;
+                self.expr_after(cond)?;
+                self.block(then)?;
+                match otherwise {
+                    Some(Else::If(stmt)) => self.stmt(stmt)?,
+                    Some(Else::Block(block)) => self.block(block)?,
+                    None => {}
+                }
+                self.close()
+            }
+            StmtKind::Match { scrutinee, arms } => {
+                self.open("match")?;
+                self.expr_after(scrutinee)?;
+                for arm in arms {
+                    self.open("arm")?;
+                    match &arm.pattern {
+                        Pattern::Wildcard => write!(self.out, " _")?,
+                        Pattern::Expr(pattern) => self.expr_after(pattern)?,
+                    }
+                    self.block(&arm.body)?;
+                    self.close()?;
+                }
+                self.close()
+            }
+            StmtKind::While { cond, body } => {
+                self.open("while")?;
+                self.expr_after(cond)?;
+                self.block(body)?;
+                self.close()
+            }
+            StmtKind::Return(value) => {
+                self.open("return")?;
+                if let Some(value) = value {
+                    self.expr_after(value)?;
+                }
+                self.close()
+            }
+            StmtKind::Break => {
+                self.open("break")?;
+                self.close()
+            }
+            StmtKind::Continue => {
+                self.open("continue")?;
+                self.close()
+            }
+            StmtKind::Expr(expr) => {
+                writeln!(self.out)?;
+                write!(self.out, "{}", "  ".repeat(self.indent))?;
+                self.expr(expr)
+            }
+        }
+    }
+
+    /// Writes `expr` after a space, on the current line.
+    fn expr_after(&mut self, expr: &Expr) -> io::Result<()> {
+        write!(self.out, " ")?;
+        self.expr(expr)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> io::Result<()> {
+        match &expr.kind {
+            ExprKind::Literal(literal) => self.literal(literal),
+            ExprKind::Variable(name) => write!(self.out, "{}", name),
+            ExprKind::Group(inner) => self.list("paren", [&**inner]),
+            ExprKind::Array(items) => self.list("array", items),
+            ExprKind::Unary { op, expr } => self.list(&op.to_string(), [&**expr]),
+            ExprKind::Binary { op, lhs, rhs } => self.list(&op.to_string(), [&**lhs, &**rhs]),
+            ExprKind::Call { callee, args } => {
+                self.list("call", std::iter::once(&**callee).chain(args))
+            }
+            ExprKind::Field { expr, name } => {
+                write!(self.out, "(. ")?;
+                self.expr(expr)?;
+                write!(self.out, " {})", name)
+            }
+            ExprKind::Index { expr, index } => self.list("index", [&**expr, &**index]),
+            ExprKind::Interpolated(parts) => {
+                write!(self.out, "(interpolate")?;
+                for part in parts {
+                    match part {
+                        InterpolatedPart::Str { value, .. } => {
+                            write!(self.out, " {}", json::string(value))?
+                        }
+                        InterpolatedPart::Expr(expr) => self.expr_after(expr)?,
+                    }
+                }
+                write!(self.out, ")")
+            }
+        }
+    }
+
+    /// Writes `(head expr…)`.
+    fn list<'e>(
+        &mut self,
+        head: &str,
+        exprs: impl IntoIterator<Item = &'e Expr>,
+    ) -> io::Result<()> {
+        write!(self.out, "({}", head)?;
+        for expr in exprs {
+            self.expr_after(expr)?;
+        }
+        write!(self.out, ")")
+    }
+
+    fn literal(&mut self, literal: &Literal) -> io::Result<()> {
+        match literal {
+            Literal::Int { value, suffix } => {
+                write!(self.out, "{}", value)?;
+                suffix.iter().try_for_each(|ty| write!(self.out, "{}", ty))
+            }
+            Literal::Float { value, suffix } => {
+                write!(self.out, "{:?}", value)?;
+                suffix.iter().try_for_each(|ty| write!(self.out, "{}", ty))
+            }
+            Literal::Str(value) => write!(self.out, "{}", json::string(value)),
+            Literal::Char(value) => write!(self.out, "{:?}", value),
+            Literal::Byte(value) => write!(self.out, "(byte {})", value),
+            Literal::ByteStr(bytes) => {
+                write!(self.out, "(bytes")?;
+                for byte in bytes {
+                    write!(self.out, " {}", byte)?;
+                }
+                write!(self.out, ")")
+            }
+            Literal::Bool(value) => write!(self.out, "{}", value),
+        }
+    }
+}
+
+/// ` pub` after the keyword of a public declaration.
+fn public(public: bool) -> &'static str {
+    if public {
+        " pub"
+    } else {
+        ""
+    }
+}
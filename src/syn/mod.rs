@@ -0,0 +1,6 @@
+pub mod ast;
+mod dump;
+mod parse;
+
+pub use dump::write_tree;
+pub use parse::{ParseError, Parser};
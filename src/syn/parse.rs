@@ -0,0 +1,1075 @@
+use super::ast::*;
+use crate::error::{Diagnostic, Label, ToDiagnostic};
+use crate::lex::{LexError, Span, Token, TokenStream, TokenType};
+
+/// Recursive descent parser for the grammar in `GRAMMAR.md`, pulling the tokens from the
+/// `Lexer` as it needs them.
+///
+/// Every function is named after the production it parses.
+#[derive(Debug)]
+pub struct Parser {
+    tokens: TokenStream,
+    /// The lexical errors passed over, reported instead of any syntax error.
+    lex_errors: Vec<LexError>,
+    /// Where the last token consumed ends.
+    end: usize,
+    /// The expressions and blocks being parsed, each inside the one before.
+    depth: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    /// What the parser was looking for, shown under the offending token.
+    pub label: String,
+    /// The token at which parsing failed, boxed to keep the `Result`s of the parser small.
+    pub token: Box<Token>,
+}
+
+impl ToDiagnostic for ParseError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(&self.message)
+            .with_code("E0100")
+            .with_label(Label::primary(self.token.span, &self.label))
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.message, self.token.span.line)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+impl Parser {
+    pub fn new(tokens: TokenStream) -> Self {
+        Self {
+            tokens,
+            lex_errors: Vec::new(),
+            end: 0,
+            depth: 0,
+        }
+    }
+
+    /// Parses the whole source.
+    ///
+    /// The lexical errors come first: when there are any, all of them are reported, lexing
+    /// carrying on to the end of the source, and no syntax error is. Otherwise parsing stops at
+    /// the first syntax error.
+    pub fn parse(&mut self) -> Result<ProgramFile, Vec<Diagnostic>> {
+        let program = self.program_file();
+        for result in self.tokens.by_ref() {
+            if let Err(error) = result {
+                self.lex_errors.push(error);
+            }
+        }
+        if !self.lex_errors.is_empty() {
+            return Err(self
+                .lex_errors
+                .iter()
+                .map(ToDiagnostic::to_diagnostic)
+                .collect());
+        }
+        program.map_err(|error| vec![error.to_diagnostic()])
+    }
+
+    /// `ProgramFile` => `ImportDeclList` `DeclList` `MainFn` `DeclList`
+    fn program_file(&mut self) -> ParseResult<ProgramFile> {
+        let start = self.peek().span;
+        let docs = self.inner_docs();
+        let imports = self.import_decl_list()?;
+        let pre_main = self.decl_list()?;
+        let main = self.main_fn()?;
+        let post_main = self.decl_list()?;
+        if !self.check(TokenType::Eof) {
+            return Err(self.error("expected a declaration"));
+        }
+
+        Ok(ProgramFile {
+            docs,
+            imports,
+            pre_main,
+            main,
+            post_main,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `ImportDeclList` => `ImportDecl` `ImportDeclList` | `null`
+    fn import_decl_list(&mut self) -> ParseResult<Vec<ImportDecl>> {
+        let mut imports = Vec::new();
+        while self.check(TokenType::Import) {
+            imports.push(self.import_decl()?);
+        }
+        Ok(imports)
+    }
+
+    /// `ImportDecl` => import `ModulePath` `ImportTail`;
+    ///
+    /// `ModulePath` => `ID` | `ModulePath` . `ID`
+    ///
+    /// `ImportTail` => as `ID` | . { `ImportItemList` } | `null`
+    fn import_decl(&mut self) -> ParseResult<ImportDecl> {
+        let start = self.peek().span;
+        self.consume(TokenType::Import, "expected `import`")?;
+        let mut path = vec![self.identifier("expected a module name after `import`")?];
+        let mut alias = None;
+        let mut items = None;
+        while self.matches(TokenType::Dot) {
+            if self.check(TokenType::LeftBrace) {
+                items = Some(self.import_item_list()?);
+                break;
+            }
+            path.push(self.identifier("expected a module name or `{` after `.`")?);
+        }
+        if items.is_none() && self.matches(TokenType::As) {
+            alias = Some(self.identifier("expected a name after `as`")?);
+        }
+        self.consume(TokenType::Semicolon, "expected `;` after import")?;
+
+        Ok(ImportDecl {
+            path,
+            alias,
+            items,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `ImportItemList` => `ImportItem`, `ImportItemList` | `ImportItem`, wrapped in `{` `}`.
+    fn import_item_list(&mut self) -> ParseResult<Vec<ImportItem>> {
+        self.consume(TokenType::LeftBrace, "expected `{`")?;
+        let mut items = Vec::new();
+        loop {
+            // `ImportItem` => `ID` | `ID` as `ID`
+            let start = self.peek().span;
+            let name = self.identifier("expected the name of an item to import")?;
+            let alias = if self.matches(TokenType::As) {
+                Some(self.identifier("expected a name after `as`")?)
+            } else {
+                None
+            };
+            items.push(ImportItem {
+                name,
+                alias,
+                span: self.span_from(start),
+            });
+            if !self.matches(TokenType::Comma) || self.check(TokenType::RightBrace) {
+                break;
+            }
+        }
+        self.consume(
+            TokenType::RightBrace,
+            "expected `,` or `}` after imported item",
+        )?;
+        Ok(items)
+    }
+
+    /// `DeclList` => `Decl` `DeclList` | `null`
+    ///
+    /// Stops at `MainFn` or at anything that can not start a `Decl`.
+    fn decl_list(&mut self) -> ParseResult<Vec<Decl>> {
+        let mut decls = Vec::new();
+        loop {
+            let mut offset = self.doc_count();
+            if self.peek_nth(offset).token_type == TokenType::Pub {
+                offset += 1;
+            }
+            let public = offset > self.doc_count();
+            let token_type = self.peek_nth(offset).token_type;
+            let decl = match token_type {
+                TokenType::Void if self.peek_nth(offset + 1).token_type == TokenType::Main => break,
+                TokenType::Struct => Decl::Struct(self.struct_decl()?),
+                TokenType::Enum => Decl::Enum(self.enum_decl()?),
+                TokenType::Impl if !public => Decl::Impl(self.impl_decl()?),
+                _ if self.starts_fn_decl(offset) => Decl::Fn(self.fn_decl()?),
+                _ => break,
+            };
+            decls.push(decl);
+        }
+        Ok(decls)
+    }
+
+    /// `AccessDecl` => pub | `null`
+    fn access_decl(&mut self) -> bool {
+        self.matches(TokenType::Pub)
+    }
+
+    /// `StructDecl` => `AccessDecl` struct `ID` { `StructTypeDeclList` }
+    fn struct_decl(&mut self) -> ParseResult<StructDecl> {
+        let docs = self.docs()?;
+        let start = self.peek().span;
+        let public = self.access_decl();
+        self.consume(TokenType::Struct, "expected `struct`")?;
+        let name = self.identifier("expected struct name")?;
+        self.consume(TokenType::LeftBrace, "expected `{` after struct name")?;
+
+        // `StructTypeDeclList` => `AccessDecl` `Type` `ID`, `StructTypeDeclList` | `null`
+        let mut fields = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            let docs = self.docs()?;
+            let start = self.peek().span;
+            let public = self.access_decl();
+            let ty = self.ty()?;
+            let name = self.identifier("expected field name")?;
+            fields.push(StructField {
+                docs,
+                public,
+                ty,
+                name,
+                span: self.span_from(start),
+            });
+            if !self.matches(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "expected `}` after struct fields")?;
+
+        Ok(StructDecl {
+            docs,
+            public,
+            name,
+            fields,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `EnumDecl` =>  `AccessDecl` enum `ID` { `EnumTypeDeclList` }
+    fn enum_decl(&mut self) -> ParseResult<EnumDecl> {
+        let docs = self.docs()?;
+        let start = self.peek().span;
+        let public = self.access_decl();
+        self.consume(TokenType::Enum, "expected `enum`")?;
+        let name = self.identifier("expected enum name")?;
+        self.consume(TokenType::LeftBrace, "expected `{` after enum name")?;
+
+        // `EnumTypeDeclList` => `ID`, `EnumTypeDeclList` | `null`
+        let mut variants = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            let docs = self.docs()?;
+            let start = self.peek().span;
+            let name = self.identifier("expected enum variant")?;
+            variants.push(EnumVariant {
+                docs,
+                name,
+                span: self.span_from(start),
+            });
+            if !self.matches(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "expected `}` after enum variants")?;
+
+        Ok(EnumDecl {
+            docs,
+            public,
+            name,
+            variants,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `ImplDecl` => impl `ID` { `FnDeclList` }
+    fn impl_decl(&mut self) -> ParseResult<ImplDecl> {
+        let docs = self.docs()?;
+        let start = self.peek().span;
+        self.consume(TokenType::Impl, "expected `impl`")?;
+        let name = self.identifier("expected type name after `impl`")?;
+        self.consume(TokenType::LeftBrace, "expected `{` after impl name")?;
+
+        // `FnDeclList` => `FnDecl` `FnDeclList` | `null`
+        let mut fns = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.check(TokenType::Eof) {
+            fns.push(self.fn_decl()?);
+        }
+        self.consume(TokenType::RightBrace, "expected `}` after impl body")?;
+
+        Ok(ImplDecl {
+            docs,
+            name,
+            fns,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `FnDecl` => `AccessDecl` `Type` `ID` (`FnTypeDeclList`){ `CompoundDeclList` }
+    fn fn_decl(&mut self) -> ParseResult<FnDecl> {
+        let docs = self.docs()?;
+        let start = self.peek().span;
+        let public = self.access_decl();
+        let ret = self.ty()?;
+        let name = self.identifier("expected function name")?;
+        self.consume(TokenType::LeftParen, "expected `(` after function name")?;
+
+        // `FnTypeDeclList` => `FnTypeDecl`, `FnTypeDeclList` | `null`
+        let mut params = Vec::new();
+        while !self.check(TokenType::RightParen) {
+            let start = self.peek().span;
+            let ty = self.ty()?;
+            let name = self.identifier("expected parameter name")?;
+            params.push(Param {
+                ty,
+                name,
+                span: self.span_from(start),
+            });
+            if !self.matches(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightParen, "expected `)` after parameters")?;
+        let body = self.block()?;
+
+        Ok(FnDecl {
+            docs,
+            public,
+            ret,
+            name,
+            params,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `MainFn` => `AccessDecl` void main(){  `CompoundDeclList` }
+    fn main_fn(&mut self) -> ParseResult<MainFn> {
+        let docs = self.docs()?;
+        let start = self.peek().span;
+        let public = self.access_decl();
+        self.consume(TokenType::Void, "expected `void main()`")?;
+        self.consume(TokenType::Main, "expected `main`")?;
+        self.consume(TokenType::LeftParen, "expected `(` after `main`")?;
+        self.consume(
+            TokenType::RightParen,
+            "expected `)`, `main` takes no parameters",
+        )?;
+        let body = self.block()?;
+
+        Ok(MainFn {
+            docs,
+            public,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `Type` => `PrimitiveType` | void | `ID`
+    fn ty(&mut self) -> ParseResult<Type> {
+        let ty = match self.peek().token_type {
+            TokenType::I8 => Type::I8,
+            TokenType::I16 => Type::I16,
+            TokenType::I32 => Type::I32,
+            TokenType::I64 => Type::I64,
+            TokenType::U8 => Type::U8,
+            TokenType::U16 => Type::U16,
+            TokenType::U32 => Type::U32,
+            TokenType::U64 => Type::U64,
+            TokenType::F32 => Type::F32,
+            TokenType::F64 => Type::F64,
+            TokenType::Bool => Type::Bool,
+            TokenType::Byte => Type::Byte,
+            TokenType::Char => Type::Char,
+            TokenType::String => Type::String,
+            TokenType::Void => Type::Void,
+            TokenType::Identifier => Type::Named(self.peek().lexeme.clone()),
+            _ => return Err(self.error("expected a type")),
+        };
+        self.advance();
+        Ok(ty)
+    }
+
+    /// `{` `CompoundDeclList` `}`
+    ///
+    /// `CompoundDeclList` => `VarConLoopDecl` `CompoundDeclList` | `null`
+    fn block(&mut self) -> ParseResult<Block> {
+        self.nest()?;
+        let block = self.block_body();
+        self.depth -= 1;
+        block
+    }
+
+    fn block_body(&mut self) -> ParseResult<Block> {
+        let start = self.peek().span;
+        self.consume(TokenType::LeftBrace, "expected `{`")?;
+        let mut stmts = Vec::new();
+        loop {
+            // Doc comments document declarations, inside a block they are plain comments.
+            while self.matches(TokenType::DocComment) {}
+            if self.check(TokenType::RightBrace) || self.check(TokenType::Eof) {
+                break;
+            }
+            stmts.push(self.statement()?);
+        }
+        self.consume(TokenType::RightBrace, "expected `}` to close the block")?;
+
+        Ok(Block {
+            stmts,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `VarConLoopDecl` => `VariableDecl` | `VariableInit` | `AssignStm` | `ConditionalStm` |
+    /// `LoopStm` | `ReturnStm` | break; | continue; | `Expression`;
+    fn statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.peek().span;
+        let kind = match self.peek().token_type {
+            TokenType::Let => self.variable_decl()?,
+            TokenType::If => self.if_stmt()?,
+            TokenType::Match => self.match_stmt()?,
+            TokenType::While => self.while_stmt()?,
+            TokenType::Return => {
+                self.advance();
+                let value = if self.check(TokenType::Semicolon) {
+                    None
+                } else {
+                    Some(self.expression()?)
+                };
+                self.consume(TokenType::Semicolon, "expected `;` after return")?;
+                StmtKind::Return(value)
+            }
+            TokenType::Break => {
+                self.advance();
+                self.consume(TokenType::Semicolon, "expected `;` after `break`")?;
+                StmtKind::Break
+            }
+            TokenType::Continue => {
+                self.advance();
+                self.consume(TokenType::Semicolon, "expected `;` after `continue`")?;
+                StmtKind::Continue
+            }
+            _ => {
+                let expr = self.expression()?;
+                let assign = self.peek().token_type;
+                if let Some(op) = assign_op(assign) {
+                    let token = self.advance().clone();
+                    if !expr.is_place() {
+                        return Err(ParseError {
+                            message: format!("invalid left-hand side of `{}`", token.lexeme),
+                            label: String::from("can not assign to this expression"),
+                            token: Box::new(token),
+                        });
+                    }
+                    let value = self.expression()?;
+                    self.consume(TokenType::Semicolon, "expected `;` after assignment")?;
+                    StmtKind::Assign {
+                        target: expr,
+                        op,
+                        value,
+                    }
+                } else {
+                    self.consume(TokenType::Semicolon, "expected `;` after expression")?;
+                    StmtKind::Expr(expr)
+                }
+            }
+        };
+
+        Ok(Stmt {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `VariableDecl` => let `MutDecl` `ID` `TypeAnnot`;
+    ///
+    /// `VariableInit` => let `MutDecl` `ID` `TypeAnnot` `ArrowAsign` `Expression`;
+    fn variable_decl(&mut self) -> ParseResult<StmtKind> {
+        self.consume(TokenType::Let, "expected `let`")?;
+        // `MutDecl` => mut | `null`
+        let mutable = self.matches(TokenType::Mut);
+        let name = self.identifier("expected variable name after `let`")?;
+
+        let ty = if self.matches(TokenType::Colon) {
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        let value = if self.matches(TokenType::LeftAssign) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        if ty.is_none() && value.is_none() && !self.check(TokenType::Semicolon) {
+            return Err(self.error("expected `:`, `<-` or `;` after variable name"));
+        }
+        self.consume(
+            TokenType::Semicolon,
+            "expected `;` after variable declaration",
+        )?;
+
+        Ok(StmtKind::Let {
+            mutable,
+            name,
+            ty,
+            value,
+        })
+    }
+
+    /// `ConditionalStm` => if `Expression` { `CompoundDeclList` } `ElseStm`
+    ///
+    /// `ElseStm` => else `ConditionalStm` | else { `CompoundDeclList` } | `null`
+    fn if_stmt(&mut self) -> ParseResult<StmtKind> {
+        self.consume(TokenType::If, "expected `if`")?;
+        let cond = self.expression()?;
+        let then = self.block()?;
+
+        let otherwise = if self.matches(TokenType::Else) {
+            if self.check(TokenType::If) {
+                let start = self.peek().span;
+                self.nest()?;
+                let kind = self.if_stmt();
+                self.depth -= 1;
+                Some(Else::If(Box::new(Stmt {
+                    kind: kind?,
+                    span: self.span_from(start),
+                })))
+            } else {
+                Some(Else::Block(self.block()?))
+            }
+        } else {
+            None
+        };
+
+        Ok(StmtKind::If {
+            cond,
+            then,
+            otherwise,
+        })
+    }
+
+    /// `ConditionalStm` => match `Expression` { `MatchArmList` }
+    ///
+    /// `MatchArmList` => `Pattern` => { `CompoundDeclList` } `MatchArmList` | `null`
+    fn match_stmt(&mut self) -> ParseResult<StmtKind> {
+        self.consume(TokenType::Match, "expected `match`")?;
+        let scrutinee = self.expression()?;
+        self.consume(TokenType::LeftBrace, "expected `{` after match expression")?;
+
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.check(TokenType::Eof) {
+            let start = self.peek().span;
+            let pattern = self.pattern()?;
+            self.consume(TokenType::Arrow, "expected `=>` after pattern")?;
+            let body = self.block()?;
+            arms.push(MatchArm {
+                pattern,
+                body,
+                span: self.span_from(start),
+            });
+            self.matches(TokenType::Comma);
+        }
+        self.consume(TokenType::RightBrace, "expected `}` after match arms")?;
+
+        Ok(StmtKind::Match { scrutinee, arms })
+    }
+
+    /// `Pattern` => _ | `Expression`
+    fn pattern(&mut self) -> ParseResult<Pattern> {
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "_" {
+            self.advance();
+            return Ok(Pattern::Wildcard);
+        }
+        Ok(Pattern::Expr(self.expression()?))
+    }
+
+    /// `LoopStm` => while `Expression` { `CompoundDeclList` }
+    fn while_stmt(&mut self) -> ParseResult<StmtKind> {
+        self.consume(TokenType::While, "expected `while`")?;
+        let cond = self.expression()?;
+        let body = self.block()?;
+
+        Ok(StmtKind::While { cond, body })
+    }
+
+    /// `Expression`, parsed by precedence climbing over the table in `infix_binding_power`.
+    fn expression(&mut self) -> ParseResult<Expr> {
+        self.expression_bp(0)
+    }
+
+    /// Parses an `Expression` whose infix operators all bind at least as tight as `min_bp`.
+    fn expression_bp(&mut self, min_bp: u8) -> ParseResult<Expr> {
+        let depth = self.depth;
+        self.nest()?;
+        let expr = self.climb(min_bp);
+        self.depth = depth;
+        expr
+    }
+
+    /// The operators applied to `lhs` in the loop each nest it one level deeper.
+    fn climb(&mut self, min_bp: u8) -> ParseResult<Expr> {
+        let mut lhs = self.prefix()?;
+
+        loop {
+            let token_type = self.peek().token_type;
+            if let Some(bp) = postfix_binding_power(token_type) {
+                if bp < min_bp {
+                    break;
+                }
+                self.nest()?;
+                lhs = self.postfix(lhs)?;
+                continue;
+            }
+
+            let Some((op, lbp, rbp)) = infix_binding_power(token_type) else {
+                break;
+            };
+            if lbp < min_bp {
+                break;
+            }
+            self.nest()?;
+            self.advance();
+            let rhs = self.expression_bp(rbp)?;
+            let start = lhs.span;
+            lhs = Expr {
+                kind: ExprKind::Binary {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+                span: self.span_from(start),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    /// Parses the operand of an `Expression`, a `Literal`, `ID`, ( `Expression` ),
+    /// [ `ExpressionList` ] or a prefix `UnaryOp` applied to an operand.
+    fn prefix(&mut self) -> ParseResult<Expr> {
+        let token = self.peek();
+        let start = token.span;
+        let op = match token.token_type {
+            TokenType::Minus => Some(UnaryOp::Neg),
+            TokenType::Bang => Some(UnaryOp::Bang),
+            TokenType::Not => Some(UnaryOp::Not),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.advance();
+            let expr = self.expression_bp(PREFIX_BP)?;
+            return Ok(Expr {
+                kind: ExprKind::Unary {
+                    op,
+                    expr: Box::new(expr),
+                },
+                span: self.span_from(start),
+            });
+        }
+
+        let kind = match token.token_type {
+            TokenType::IntLiteral | TokenType::FloatLiteral => {
+                // The lexer leaves the value in decimal followed by the type suffix, if any.
+                let split = token
+                    .lexeme
+                    .find(|c: char| c.is_ascii_alphabetic())
+                    .unwrap_or(token.lexeme.len());
+                let (value, suffix) = token.lexeme.split_at(split);
+                let suffix = suffix_type(suffix);
+                let literal = if token.token_type == TokenType::FloatLiteral {
+                    value
+                        .parse()
+                        .map(|value| Literal::Float { value, suffix })
+                        .ok()
+                } else {
+                    value
+                        .parse()
+                        .map(|value| Literal::Int { value, suffix })
+                        .ok()
+                };
+                match literal {
+                    Some(literal) => ExprKind::Literal(literal),
+                    None => return Err(self.error("malformed number literal")),
+                }
+            }
+            TokenType::StringLiteral => ExprKind::Literal(Literal::Str(token.lexeme.clone())),
+            TokenType::CharLiteral => match token.lexeme.chars().next() {
+                Some(c) => ExprKind::Literal(Literal::Char(c)),
+                None => return Err(self.error("empty character literal")),
+            },
+            // The lexer keeps every byte as the `char` of the same value.
+            TokenType::ByteLiteral => match token.lexeme.chars().next() {
+                Some(c) => ExprKind::Literal(Literal::Byte(c as u8)),
+                None => return Err(self.error("empty byte literal")),
+            },
+            TokenType::ByteStringLiteral => ExprKind::Literal(Literal::ByteStr(
+                token.lexeme.chars().map(|c| c as u8).collect(),
+            )),
+            TokenType::True => ExprKind::Literal(Literal::Bool(true)),
+            TokenType::False => ExprKind::Literal(Literal::Bool(false)),
+            TokenType::Identifier => ExprKind::Variable(token.lexeme.clone()),
+            TokenType::LeftParen => {
+                self.advance();
+                let inner = self.expression()?;
+                self.consume(TokenType::RightParen, "expected `)` after expression")?;
+                return Ok(Expr {
+                    kind: ExprKind::Group(Box::new(inner)),
+                    span: self.span_from(start),
+                });
+            }
+            TokenType::StringStart => return self.interpolated_string(),
+            TokenType::LeftBracket => {
+                self.advance();
+                let elements = self.expression_list(TokenType::RightBracket)?;
+                self.consume(TokenType::RightBracket, "expected `]` after array elements")?;
+                return Ok(Expr {
+                    kind: ExprKind::Array(elements),
+                    span: self.span_from(start),
+                });
+            }
+            _ => return Err(self.error("expected an expression")),
+        };
+        self.advance();
+
+        Ok(Expr {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses the tokens of a string such as `"total: {a + b}"` into its text and expressions.
+    fn interpolated_string(&mut self) -> ParseResult<Expr> {
+        let start = self.advance().span;
+        let mut parts = Vec::new();
+        loop {
+            let token = self.advance();
+            match token.token_type {
+                TokenType::StringPart => parts.push(InterpolatedPart::Str {
+                    value: token.lexeme.clone(),
+                    span: token.span,
+                }),
+                TokenType::InterpolationStart => {
+                    parts.push(InterpolatedPart::Expr(self.expression()?));
+                    self.consume(
+                        TokenType::InterpolationEnd,
+                        "expected `}` after interpolated expression",
+                    )?;
+                }
+                TokenType::StringEnd => break,
+                _ => return Err(self.error("expected the end of the string")),
+            }
+        }
+
+        Ok(Expr {
+            kind: ExprKind::Interpolated(parts),
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses a call `(` `ExpressionList` `)`, a field access `.` `ID` or an index
+    /// `[` `Expression` `]` applied to `expr`.
+    fn postfix(&mut self, expr: Expr) -> ParseResult<Expr> {
+        let start = expr.span;
+        let kind = match self.advance().token_type {
+            TokenType::LeftParen => {
+                let args = self.expression_list(TokenType::RightParen)?;
+                self.consume(TokenType::RightParen, "expected `)` after arguments")?;
+                ExprKind::Call {
+                    callee: Box::new(expr),
+                    args,
+                }
+            }
+            TokenType::Dot => {
+                let name = self.identifier("expected field name after `.`")?;
+                ExprKind::Field {
+                    expr: Box::new(expr),
+                    name,
+                }
+            }
+            _ => {
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "expected `]` after index")?;
+                ExprKind::Index {
+                    expr: Box::new(expr),
+                    index: Box::new(index),
+                }
+            }
+        };
+
+        Ok(Expr {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `ExpressionList` => `Expression`, `ExpressionList` | `null`
+    ///
+    /// Stops in front of `end`, which is left for the caller to consume.
+    fn expression_list(&mut self, end: TokenType) -> ParseResult<Vec<Expr>> {
+        let mut exprs = Vec::new();
+        while !self.check(end) {
+            exprs.push(self.expression()?);
+            if !self.matches(TokenType::Comma) {
+                break;
+            }
+        }
+        Ok(exprs)
+    }
+
+    /// `//!` doc comments, only allowed at the start of the file.
+    fn inner_docs(&mut self) -> Vec<String> {
+        let mut docs = Vec::new();
+        while self.check(TokenType::DocComment) && self.peek().lexeme.starts_with("//!") {
+            docs.push(doc_text(&self.advance().lexeme));
+        }
+        docs
+    }
+
+    /// `///` doc comments in front of a declaration.
+    fn docs(&mut self) -> ParseResult<Vec<String>> {
+        let mut docs = Vec::new();
+        while self.check(TokenType::DocComment) {
+            if self.peek().lexeme.starts_with("//!") {
+                return Err(
+                    self.error("`//!` doc comments are only allowed at the start of the file")
+                );
+            }
+            docs.push(doc_text(&self.advance().lexeme));
+        }
+        Ok(docs)
+    }
+
+    /// Number of `DocComment` tokens starting at the current token.
+    fn doc_count(&mut self) -> usize {
+        let mut n = 0;
+        while self.peek_nth(n).token_type == TokenType::DocComment {
+            n += 1;
+        }
+        n
+    }
+
+    /// Checks whether the tokens `offset` places ahead are `Type` `ID` (, the start of a `FnDecl`.
+    fn starts_fn_decl(&mut self, offset: usize) -> bool {
+        let is_type = matches!(
+            self.peek_nth(offset).token_type,
+            TokenType::I8
+                | TokenType::I16
+                | TokenType::I32
+                | TokenType::I64
+                | TokenType::U8
+                | TokenType::U16
+                | TokenType::U32
+                | TokenType::U64
+                | TokenType::F32
+                | TokenType::F64
+                | TokenType::Bool
+                | TokenType::Byte
+                | TokenType::Char
+                | TokenType::String
+                | TokenType::Void
+                | TokenType::Identifier
+        );
+        is_type
+            && self.peek_nth(offset + 1).token_type == TokenType::Identifier
+            && self.peek_nth(offset + 2).token_type == TokenType::LeftParen
+    }
+
+    /// Consumes an `ID` and returns its name.
+    fn identifier(&mut self, message: &str) -> ParseResult<String> {
+        let name = self.consume(TokenType::Identifier, message)?.lexeme.clone();
+        Ok(name)
+    }
+
+    /// The `Span` from `start` to the end of the last token consumed.
+    fn span_from(&self, start: Span) -> Span {
+        Span {
+            end: self.end.max(start.start),
+            ..start
+        }
+    }
+
+    /// Returns the current token.
+    fn peek(&mut self) -> &Token {
+        self.peek_nth(0)
+    }
+
+    /// Returns the token `n` places after the current one, stopping at `Eof`. The lexical
+    /// errors in between are passed over.
+    fn peek_nth(&mut self, n: usize) -> &Token {
+        let mut tokens = 0;
+        let mut last = 0;
+        let mut i = 0;
+        while let Some(result) = self.tokens.peek_nth(i) {
+            if result.is_ok() {
+                last = i;
+                if tokens == n {
+                    break;
+                }
+                tokens += 1;
+            }
+            i += 1;
+        }
+        match self.tokens.peek_nth(last) {
+            Some(Ok(token)) => token,
+            _ => unreachable!("the lexer ends with `Eof`"),
+        }
+    }
+
+    /// `advance` `fn` is used to move forward by 1 token, never past `Eof`.
+    fn advance(&mut self) -> Token {
+        if self.check(TokenType::Eof) {
+            return self.peek().clone();
+        }
+        loop {
+            match self.tokens.next() {
+                Some(Ok(token)) => {
+                    self.end = token.span.end;
+                    return token;
+                }
+                Some(Err(error)) => self.lex_errors.push(error),
+                None => unreachable!("`Eof` is never consumed"),
+            }
+        }
+    }
+
+    /// Checks the type of the current token without consuming it.
+    fn check(&mut self, token_type: TokenType) -> bool {
+        self.peek().token_type == token_type
+    }
+
+    /// Consumes the current token if it is of `token_type`.
+    fn matches(&mut self, token_type: TokenType) -> bool {
+        if self.check(token_type) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+
+    /// Consumes the current token if it is of `token_type`, errors with `message` otherwise.
+    fn consume(&mut self, token_type: TokenType, message: &str) -> ParseResult<Token> {
+        if self.check(token_type) {
+            return Ok(self.advance());
+        }
+        Err(self.error(message))
+    }
+
+    /// Enters an expression, a block or an `else if`, or applies an operator to an expression,
+    /// failing past `MAX_DEPTH` levels of nesting rather than overflowing the stack. Every level
+    /// of the tree is counted, so that neither the parser nor the passes walking the tree after
+    /// it recurse deeper than `MAX_DEPTH` times a few calls.
+    fn nest(&mut self) -> ParseResult<()> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError {
+                message: format!("nesting deeper than {} levels", MAX_DEPTH),
+                label: String::from("nested too deeply"),
+                token: Box::new(self.peek().clone()),
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Builds a `ParseError` pointing at the current token.
+    fn error(&mut self, message: &str) -> ParseError {
+        let token = self.peek();
+        let found = match token.token_type {
+            TokenType::Eof => String::from("end of file"),
+            _ => format!("`{}`", token.lexeme),
+        };
+        ParseError {
+            message: format!("{}, found {}", message, found),
+            label: message.to_string(),
+            token: Box::new(token.clone()),
+        }
+    }
+}
+
+/// The `Type` named by the suffix of a number literal, as in `255u8`.
+fn suffix_type(suffix: &str) -> Option<Type> {
+    let ty = match suffix {
+        "i8" => Type::I8,
+        "i16" => Type::I16,
+        "i32" => Type::I32,
+        "i64" => Type::I64,
+        "u8" => Type::U8,
+        "u16" => Type::U16,
+        "u32" => Type::U32,
+        "u64" => Type::U64,
+        "f32" => Type::F32,
+        "f64" => Type::F64,
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// Strips the `///` or `//!` and the single space after it from a doc comment.
+fn doc_text(lexeme: &str) -> String {
+    let text = &lexeme[3..];
+    text.strip_prefix(' ').unwrap_or(text).to_string()
+}
+
+/// How deep expressions, blocks and `else if`s can nest, together, an operator applied to an
+/// expression being a level too.
+const MAX_DEPTH: usize = 200;
+
+/// Binding power of the prefix `-`, `!` and `not` operators.
+const PREFIX_BP: u8 = 19;
+
+/// Binding power and associativity of the infix operators, the higher binds tighter.
+///
+/// | Operators                     | Left bp | Right bp | Associativity |
+/// |-------------------------------|---------|----------|---------------|
+/// | `or` `\|\|`                   | 1       | 2        | left          |
+/// | `and` `&&`                    | 3       | 4        | left          |
+/// | `==` `!=` `<` `<=` `>` `>=`   | 5       | 6        | left          |
+/// | `\|`                          | 7       | 8        | left          |
+/// | `^`                           | 9       | 10       | left          |
+/// | `&`                           | 11      | 12       | left          |
+/// | `<<` `>>`                     | 13      | 14       | left          |
+/// | `+` `-`                       | 15      | 16       | left          |
+/// | `*` `/` `%`                   | 17      | 18       | left          |
+/// | prefix `-` `!` `not`          |         | 19       | right         |
+/// | `**`                          | 22      | 21       | right         |
+/// | postfix call `()` `.` `[]`    | 23      |          | left          |
+///
+/// `**` binds tighter than the prefix operators, so `-2 ** 2` is `-(2 ** 2)`.
+fn infix_binding_power(token_type: TokenType) -> Option<(BinaryOp, u8, u8)> {
+    let (op, lbp, rbp) = match token_type {
+        TokenType::Or | TokenType::PipePipe => (BinaryOp::Or, 1, 2),
+        TokenType::And | TokenType::AmpersandAmpersand => (BinaryOp::And, 3, 4),
+        TokenType::EqualEqual => (BinaryOp::Eq, 5, 6),
+        TokenType::BangEqual => (BinaryOp::Ne, 5, 6),
+        TokenType::Less => (BinaryOp::Lt, 5, 6),
+        TokenType::LessEqual => (BinaryOp::Le, 5, 6),
+        TokenType::Greater => (BinaryOp::Gt, 5, 6),
+        TokenType::GreaterEqual => (BinaryOp::Ge, 5, 6),
+        TokenType::Pipe => (BinaryOp::BitOr, 7, 8),
+        TokenType::Caret => (BinaryOp::BitXor, 9, 10),
+        TokenType::Ampersand => (BinaryOp::BitAnd, 11, 12),
+        TokenType::LeftShift => (BinaryOp::Shl, 13, 14),
+        TokenType::RightShift => (BinaryOp::Shr, 13, 14),
+        TokenType::Plus => (BinaryOp::Add, 15, 16),
+        TokenType::Minus => (BinaryOp::Sub, 15, 16),
+        TokenType::Star => (BinaryOp::Mul, 17, 18),
+        TokenType::Slash => (BinaryOp::Div, 17, 18),
+        TokenType::Percent => (BinaryOp::Rem, 17, 18),
+        TokenType::Power => (BinaryOp::Pow, 22, 21),
+        _ => return None,
+    };
+    Some((op, lbp, rbp))
+}
+
+/// The operator combined with the assignment for `+=` and the like, `None` for `<-`.
+fn assign_op(token_type: TokenType) -> Option<Option<BinaryOp>> {
+    let op = match token_type {
+        TokenType::LeftAssign => None,
+        TokenType::PlusEqual => Some(BinaryOp::Add),
+        TokenType::MinusEqual => Some(BinaryOp::Sub),
+        TokenType::StarEqual => Some(BinaryOp::Mul),
+        TokenType::SlashEqual => Some(BinaryOp::Div),
+        TokenType::PercentEqual => Some(BinaryOp::Rem),
+        TokenType::PowerEqual => Some(BinaryOp::Pow),
+        TokenType::LeftShiftEqual => Some(BinaryOp::Shl),
+        TokenType::RightShiftEqual => Some(BinaryOp::Shr),
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// Binding power of the postfix call, field and index operators, see `infix_binding_power`.
+fn postfix_binding_power(token_type: TokenType) -> Option<u8> {
+    match token_type {
+        TokenType::LeftParen | TokenType::Dot | TokenType::LeftBracket => Some(23),
+        _ => None,
+    }
+}
@@ -0,0 +1,141 @@
+use super::op::Op;
+use crate::eval::Value;
+use crate::lex::Span;
+use crate::syn::ast::Type;
+
+/// A compiled program: its functions and the constants and types their instructions refer to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// The source files the module was compiled from, by the `file` of the spans, named in the
+    /// errors of a module loaded from a `.vyc` file. Empty unless set by the caller of `compile`.
+    pub files: Vec<String>,
+    /// `main` first, then the other functions in the order they are declared.
+    pub functions: Vec<Function>,
+    pub constants: Vec<Value>,
+    /// The types checked by `Op::Check`.
+    pub types: Vec<Type>,
+}
+
+/// The bytecode of a function.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Function {
+    pub name: String,
+    /// The parameters are the first locals.
+    pub params: usize,
+    pub ret: Type,
+    /// The name of the variable in each local slot.
+    pub locals: Vec<String>,
+    pub code: Vec<u8>,
+    /// The offset of every instruction in `code` with the span of the source it comes from,
+    /// sorted by offset.
+    pub spans: Vec<(usize, Span)>,
+    pub span: Span,
+}
+
+impl Function {
+    /// The span of the instruction at `offset`.
+    pub fn span_at(&self, offset: usize) -> Span {
+        let index = self
+            .spans
+            .partition_point(|(start, _)| *start <= offset)
+            .saturating_sub(1);
+        self.spans.get(index).map_or(self.span, |(_, span)| *span)
+    }
+
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.code[offset], self.code[offset + 1]])
+    }
+}
+
+impl std::fmt::Display for Module {
+    /// The disassembly of every function, as printed by `vid disasm`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            self.disassemble(function, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Module {
+    /// Writes `function` with an instruction per line: its offset, the source line it comes
+    /// from or `|` when that is the line of the previous instruction, the instruction and what
+    /// its operand refers to.
+    fn disassemble(&self, function: &Function, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "fn {} ({} params, locals: {}) -> {}",
+            function.name,
+            function.params,
+            if function.locals.is_empty() {
+                String::from("none")
+            } else {
+                function.locals.join(", ")
+            },
+            function.ret
+        )?;
+
+        let mut offset = 0;
+        let mut last_line = 0;
+        while offset < function.code.len() {
+            let line = function.span_at(offset).line;
+            let line_text = if line == last_line {
+                String::from("|")
+            } else {
+                line.to_string()
+            };
+            last_line = line;
+
+            let Some(op) = Op::from_byte(function.code[offset]) else {
+                writeln!(
+                    f,
+                    "{:04}  {:>4}  <invalid {:#04x}>",
+                    offset, line_text, function.code[offset]
+                )?;
+                offset += 1;
+                continue;
+            };
+            if !op.has_operand() {
+                writeln!(f, "{:04}  {:>4}  {}", offset, line_text, op)?;
+                offset += 1;
+                continue;
+            }
+
+            let operand = function.read_u16(offset + 1) as usize;
+            let comment = match op {
+                Op::Const => self.constants.get(operand).map(constant),
+                Op::Load | Op::Store | Op::StoreOnce | Op::Clear => {
+                    function.locals.get(operand).cloned()
+                }
+                Op::Check => self.types.get(operand).map(Type::to_string),
+                Op::Call => self.functions.get(operand).map(|f| f.name.clone()),
+                _ => None,
+            };
+            write!(
+                f,
+                "{:04}  {:>4}  {:<16}{:>5}",
+                offset, line_text, op, operand
+            )?;
+            match comment {
+                Some(comment) => writeln!(f, "  ; {}", comment)?,
+                None => writeln!(f)?,
+            }
+            offset += 3;
+        }
+        Ok(())
+    }
+}
+
+/// A constant as written in the source, strings and characters quoted.
+fn constant(value: &Value) -> String {
+    match value {
+        Value::Int { value, ty } => format!("{}{}", value, ty),
+        Value::Float { ty, .. } => format!("{}{}", value, ty),
+        Value::Str(text) => format!("{:?}", text),
+        Value::Char(c) => format!("{:?}", c),
+        _ => value.to_string(),
+    }
+}
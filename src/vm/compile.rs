@@ -0,0 +1,632 @@
+use super::chunk::{Function, Module};
+use super::op::Op;
+use crate::eval::{
+    error, is_untyped_literal, number_literal, operand_hint, RuntimeError, RuntimeErrorKind, Value,
+};
+use crate::lex::Span;
+use crate::syn::ast::*;
+use std::collections::HashMap;
+
+type CompileResult<T> = Result<T, RuntimeError>;
+
+/// Compiles `program` to bytecode for the `Vm`.
+///
+/// The program runs the same as in the `Interpreter`, with the same errors. Those the compiler
+/// can see, such as an unknown variable or a `break` outside of a loop, are reported before
+/// anything runs.
+pub fn compile(program: &ProgramFile) -> CompileResult<Module> {
+    let mut decls = Vec::new();
+    let mut signatures = HashMap::new();
+    for decl in program.pre_main.iter().chain(&program.post_main) {
+        if let Decl::Fn(function) = decl {
+            if !signatures.contains_key(function.name.as_str()) {
+                // `main` is function 0.
+                let index = u16::try_from(decls.len() + 1)
+                    .map_err(|_| error(RuntimeErrorKind::TooLarge("functions"), function.span))?;
+                signatures.insert(function.name.as_str(), (index, function));
+                decls.push(function);
+            }
+        }
+    }
+
+    let mut compiler = Compiler {
+        signatures,
+        module: Module {
+            files: Vec::new(),
+            functions: Vec::new(),
+            constants: Vec::new(),
+            types: Vec::new(),
+        },
+        constants: HashMap::new(),
+        function: Function::default(),
+        scope: Vec::new(),
+        loops: Vec::new(),
+    };
+    let main = &program.main;
+    compiler.function("main", &[], &Type::Void, &main.body, main.span)?;
+    for decl in decls {
+        compiler.function(&decl.name, &decl.params, &decl.ret, &decl.body, decl.span)?;
+    }
+    Ok(compiler.module)
+}
+
+struct Compiler<'a> {
+    /// The index and declaration of every function by name.
+    signatures: HashMap<&'a str, (u16, &'a FnDecl)>,
+    module: Module,
+    /// The index of every constant by its `Debug` text, to add each constant once.
+    constants: HashMap<String, u16>,
+    /// The function being compiled.
+    function: Function,
+    /// The variables in scope, innermost last.
+    scope: Vec<Local>,
+    /// The loops around the code being compiled, innermost last.
+    loops: Vec<Loop>,
+}
+
+struct Local {
+    name: String,
+    slot: u16,
+    mutable: bool,
+    /// Whether the `let` gave it a value. Only then is assigning to a variable without `mut` an
+    /// error known before running.
+    initialized: bool,
+    /// The declared type, or the type of the first value.
+    ty: Option<Type>,
+}
+
+struct Loop {
+    /// Where the condition starts, the target of `continue`.
+    start: usize,
+    /// The `Jump`s of the `break`s, patched at the end of the loop.
+    breaks: Vec<usize>,
+}
+
+impl<'a> Compiler<'a> {
+    fn function(
+        &mut self,
+        name: &str,
+        params: &[Param],
+        ret: &Type,
+        body: &Block,
+        span: Span,
+    ) -> CompileResult<()> {
+        self.function = Function {
+            name: name.to_string(),
+            params: params.len(),
+            ret: ret.clone(),
+            span,
+            ..Function::default()
+        };
+        self.scope.clear();
+        for param in params {
+            self.declare(&param.name, false, true, Some(param.ty.clone()), param.span)?;
+        }
+
+        self.block(body)?;
+        if *ret == Type::Void {
+            let void = self.constant(Value::Void, span)?;
+            self.emit_with(Op::Const, void, span);
+            self.emit(Op::Return, span);
+        } else {
+            self.emit(Op::MissingReturn, span);
+        }
+
+        let function = std::mem::take(&mut self.function);
+        self.module.functions.push(function);
+        Ok(())
+    }
+
+    fn block(&mut self, block: &Block) -> CompileResult<()> {
+        let scope = self.scope.len();
+        for stmt in &block.stmts {
+            self.stmt(stmt)?;
+        }
+        self.scope.truncate(scope);
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> CompileResult<()> {
+        match &stmt.kind {
+            StmtKind::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => match value {
+                Some(value) => {
+                    // Compiled before declaring the variable, `let x <- x + 1` reads the `x`
+                    // declared before.
+                    self.expr(value, ty.as_ref())?;
+                    if let Some(ty) = ty {
+                        self.check(ty, value.span)?;
+                    }
+                    let ty = ty.clone().or_else(|| self.static_type(value, None));
+                    let slot = self.declare(name, *mutable, true, ty, stmt.span)?;
+                    self.emit_with(Op::Store, slot, stmt.span);
+                }
+                None => {
+                    let slot = self.declare(name, *mutable, false, ty.clone(), stmt.span)?;
+                    self.emit_with(Op::Clear, slot, stmt.span);
+                }
+            },
+            StmtKind::Assign { target, op, value } => self.assign(target, *op, value)?,
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.expr(cond, Some(&Type::Bool))?;
+                let skip_then = self.emit_jump(Op::JumpIfFalse, cond.span);
+                self.block(then)?;
+                match otherwise {
+                    Some(otherwise) => {
+                        let skip_else = self.emit_jump(Op::Jump, stmt.span);
+                        self.patch(skip_then, cond.span)?;
+                        match otherwise {
+                            Else::If(stmt) => self.stmt(stmt)?,
+                            Else::Block(block) => self.block(block)?,
+                        }
+                        self.patch(skip_else, stmt.span)?;
+                    }
+                    None => self.patch(skip_then, cond.span)?,
+                }
+            }
+            StmtKind::Match { scrutinee, arms } => {
+                self.expr(scrutinee, None)?;
+                let ty = self.static_type(scrutinee, None);
+                let slot = self.slot("(match)", scrutinee.span)?;
+                self.emit_with(Op::Store, slot, scrutinee.span);
+
+                let mut ends = Vec::new();
+                for arm in arms {
+                    let next = match &arm.pattern {
+                        Pattern::Wildcard => None,
+                        Pattern::Expr(pattern) => {
+                            self.emit_with(Op::Load, slot, pattern.span);
+                            self.expr(pattern, ty.as_ref())?;
+                            self.emit(Op::Eq, pattern.span);
+                            Some(self.emit_jump(Op::JumpIfFalse, pattern.span))
+                        }
+                    };
+                    self.block(&arm.body)?;
+                    ends.push(self.emit_jump(Op::Jump, arm.span));
+                    if let Some(next) = next {
+                        self.patch(next, arm.span)?;
+                    }
+                }
+                for end in ends {
+                    self.patch(end, stmt.span)?;
+                }
+            }
+            StmtKind::While { cond, body } => {
+                let start = self.function.code.len();
+                self.expr(cond, Some(&Type::Bool))?;
+                let exit = self.emit_jump(Op::JumpIfFalse, cond.span);
+                self.loops.push(Loop {
+                    start,
+                    breaks: Vec::new(),
+                });
+                self.block(body)?;
+                self.emit_with(Op::Jump, self.offset(start, stmt.span)?, stmt.span);
+                self.patch(exit, stmt.span)?;
+                if let Some(lp) = self.loops.pop() {
+                    for jump in lp.breaks {
+                        self.patch(jump, stmt.span)?;
+                    }
+                }
+            }
+            StmtKind::Return(value) => {
+                let ret = self.function.ret.clone();
+                match value {
+                    Some(value) => {
+                        self.expr(value, Some(&ret))?;
+                        self.check(&ret, value.span)?;
+                    }
+                    None if ret == Type::Void => {
+                        let void = self.constant(Value::Void, stmt.span)?;
+                        self.emit_with(Op::Const, void, stmt.span);
+                    }
+                    None => {
+                        return Err(error(
+                            RuntimeErrorKind::MismatchedTypes {
+                                expected: ret,
+                                found: Type::Void,
+                            },
+                            stmt.span,
+                        ))
+                    }
+                }
+                self.emit(Op::Return, stmt.span);
+            }
+            StmtKind::Break => {
+                if self.loops.is_empty() {
+                    return Err(error(RuntimeErrorKind::OutsideLoop("break"), stmt.span));
+                }
+                let jump = self.emit_jump(Op::Jump, stmt.span);
+                if let Some(lp) = self.loops.last_mut() {
+                    lp.breaks.push(jump);
+                }
+            }
+            StmtKind::Continue => {
+                let start = match self.loops.last() {
+                    Some(lp) => lp.start,
+                    None => {
+                        return Err(error(RuntimeErrorKind::OutsideLoop("continue"), stmt.span))
+                    }
+                };
+                self.emit_with(Op::Jump, self.offset(start, stmt.span)?, stmt.span);
+            }
+            StmtKind::Expr(expr) => {
+                self.expr(expr, None)?;
+                self.emit(Op::Pop, stmt.span);
+            }
+        }
+        Ok(())
+    }
+
+    /// `target <- value` or `target op= value`.
+    fn assign(&mut self, target: &Expr, op: Option<BinaryOp>, value: &Expr) -> CompileResult<()> {
+        let name = match &target.kind {
+            ExprKind::Variable(name) => name,
+            ExprKind::Group(inner) => return self.assign(inner, op, value),
+            _ => {
+                return Err(error(
+                    RuntimeErrorKind::Unsupported("assignments to fields and elements"),
+                    target.span,
+                ))
+            }
+        };
+        let (slot, mutable, ty) = {
+            let local = self.local(name, target.span)?;
+            if !local.mutable && local.initialized {
+                return Err(error(
+                    RuntimeErrorKind::AssignToImmutable(name.clone()),
+                    target.span,
+                ));
+            }
+            (local.slot, local.mutable, local.ty.clone())
+        };
+
+        let ty = match op {
+            Some(op) => {
+                let span = target.span.to(value.span);
+                self.emit_with(Op::Load, slot, target.span);
+                self.expr(value, ty.as_ref())?;
+                self.emit(Op::binary(op), span);
+                if let Some(ty) = &ty {
+                    self.check(ty, span)?;
+                }
+                ty
+            }
+            None => {
+                self.expr(value, ty.as_ref())?;
+                match ty {
+                    Some(ty) => {
+                        self.check(&ty, value.span)?;
+                        Some(ty)
+                    }
+                    None => self.static_type(value, None),
+                }
+            }
+        };
+        let store = if mutable { Op::Store } else { Op::StoreOnce };
+        self.emit_with(store, slot, target.span);
+
+        if let Some(local) = self
+            .scope
+            .iter_mut()
+            .rev()
+            .find(|local| local.name == *name)
+        {
+            local.ty = ty;
+        }
+        Ok(())
+    }
+
+    /// Compiles `expr`, giving the number literals without suffix the type `hint` when it is a
+    /// number type, as `Interpreter::expr_with_hint` does.
+    fn expr(&mut self, expr: &Expr, hint: Option<&Type>) -> CompileResult<()> {
+        if let Some(value) = number_literal(expr, hint)? {
+            let index = self.constant(value, expr.span)?;
+            self.emit_with(Op::Const, index, expr.span);
+            return Ok(());
+        }
+
+        match &expr.kind {
+            ExprKind::Literal(literal) => {
+                let value = match literal {
+                    Literal::Str(value) => Value::Str(value.clone()),
+                    Literal::Char(value) => Value::Char(*value),
+                    Literal::Byte(value) => Value::Int {
+                        value: *value as i128,
+                        ty: Type::Byte,
+                    },
+                    Literal::Bool(value) => Value::Bool(*value),
+                    Literal::ByteStr(_) => {
+                        return Err(error(
+                            RuntimeErrorKind::Unsupported("byte strings"),
+                            expr.span,
+                        ))
+                    }
+                    Literal::Int { .. } | Literal::Float { .. } => {
+                        unreachable!("number literals are compiled by `number_literal`")
+                    }
+                };
+                let index = self.constant(value, expr.span)?;
+                self.emit_with(Op::Const, index, expr.span);
+            }
+            ExprKind::Variable(name) => {
+                let slot = self.local(name, expr.span)?.slot;
+                self.emit_with(Op::Load, slot, expr.span);
+            }
+            ExprKind::Group(inner) => self.expr(inner, hint)?,
+            ExprKind::Unary { op, expr: operand } => {
+                self.expr(operand, hint)?;
+                let op = match op {
+                    UnaryOp::Neg => Op::Neg,
+                    UnaryOp::Bang => Op::Bang,
+                    UnaryOp::Not => Op::Not,
+                };
+                self.emit(op, expr.span);
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                let hint = operand_hint(*op, hint);
+                if is_untyped_literal(lhs) && !is_untyped_literal(rhs) {
+                    let rhs_type = self.static_type(rhs, hint);
+                    self.expr(lhs, rhs_type.as_ref())?;
+                    self.expr(rhs, hint)?;
+                    self.emit(Op::binary(*op), expr.span);
+                    return Ok(());
+                }
+
+                self.expr(lhs, hint)?;
+                let short_circuit = match op {
+                    BinaryOp::And => Some(self.emit_jump(Op::JumpIfFalseKeep, expr.span)),
+                    BinaryOp::Or => Some(self.emit_jump(Op::JumpIfTrueKeep, expr.span)),
+                    _ => None,
+                };
+                let lhs_type = self.static_type(lhs, hint);
+                self.expr(rhs, lhs_type.as_ref())?;
+                self.emit(Op::binary(*op), expr.span);
+                if let Some(jump) = short_circuit {
+                    self.patch(jump, expr.span)?;
+                }
+            }
+            ExprKind::Call { callee, args } => self.call(callee, args, expr.span)?,
+            ExprKind::Interpolated(parts) => {
+                for part in parts {
+                    match part {
+                        InterpolatedPart::Str { value, span } => {
+                            let index = self.constant(Value::Str(value.clone()), *span)?;
+                            self.emit_with(Op::Const, index, *span);
+                        }
+                        InterpolatedPart::Expr(expr) => self.expr(expr, None)?,
+                    }
+                }
+                let count = u16::try_from(parts.len())
+                    .map_err(|_| error(RuntimeErrorKind::TooLarge("string parts"), expr.span))?;
+                self.emit_with(Op::Format, count, expr.span);
+            }
+            ExprKind::Array(_) | ExprKind::Index { .. } => {
+                return Err(error(RuntimeErrorKind::Unsupported("arrays"), expr.span))
+            }
+            ExprKind::Field { .. } => {
+                return Err(error(
+                    RuntimeErrorKind::Unsupported("structs and enums"),
+                    expr.span,
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr], span: Span) -> CompileResult<()> {
+        let name = match &callee.kind {
+            ExprKind::Variable(name) => name.as_str(),
+            ExprKind::Field { .. } => {
+                return Err(error(RuntimeErrorKind::Unsupported("methods"), callee.span))
+            }
+            _ => {
+                return Err(error(
+                    RuntimeErrorKind::Unsupported("calls of expressions"),
+                    callee.span,
+                ))
+            }
+        };
+        let (index, function) = match self.signatures.get(name) {
+            Some(signature) => *signature,
+            None if matches!(name, "print" | "println") => return self.print(name, args, span),
+            None => {
+                return Err(error(
+                    RuntimeErrorKind::UndefinedFunction(name.to_string()),
+                    callee.span,
+                ))
+            }
+        };
+        if args.len() != function.params.len() {
+            return Err(error(
+                RuntimeErrorKind::WrongArgumentCount {
+                    name: name.to_string(),
+                    expected: function.params.len(),
+                    found: args.len(),
+                },
+                span,
+            ));
+        }
+
+        for (param, arg) in function.params.iter().zip(args) {
+            self.expr(arg, Some(&param.ty))?;
+            self.check(&param.ty, arg.span)?;
+        }
+        self.emit_with(Op::Call, index, span);
+        Ok(())
+    }
+
+    /// The built-in `print(value)` and `println(value)`, `println()` printing an empty line.
+    fn print(&mut self, name: &str, args: &[Expr], span: Span) -> CompileResult<()> {
+        let newline = name == "println";
+        match args {
+            [] if newline => {
+                let empty = self.constant(Value::Str(String::new()), span)?;
+                self.emit_with(Op::Const, empty, span);
+            }
+            [arg] => self.expr(arg, None)?,
+            _ => {
+                return Err(error(
+                    RuntimeErrorKind::WrongArgumentCount {
+                        name: name.to_string(),
+                        expected: 1,
+                        found: args.len(),
+                    },
+                    span,
+                ))
+            }
+        }
+        self.emit(if newline { Op::Println } else { Op::Print }, span);
+        Ok(())
+    }
+
+    /// The type `expr` evaluates to, as far as it can be known without running it. Only used
+    /// to type the number literals next to it.
+    fn static_type(&self, expr: &Expr, hint: Option<&Type>) -> Option<Type> {
+        if let Ok(Some(value)) = number_literal(expr, hint) {
+            return Some(value.ty());
+        }
+        match &expr.kind {
+            ExprKind::Literal(Literal::Str(_)) | ExprKind::Interpolated(_) => Some(Type::String),
+            ExprKind::Literal(Literal::Char(_)) => Some(Type::Char),
+            ExprKind::Literal(Literal::Byte(_)) => Some(Type::Byte),
+            ExprKind::Literal(Literal::Bool(_)) => Some(Type::Bool),
+            ExprKind::Variable(name) => self
+                .scope
+                .iter()
+                .rev()
+                .find(|local| local.name == *name)
+                .and_then(|local| local.ty.clone()),
+            ExprKind::Group(inner) => self.static_type(inner, hint),
+            ExprKind::Unary {
+                op: UnaryOp::Not, ..
+            } => Some(Type::Bool),
+            ExprKind::Unary { expr, .. } => self.static_type(expr, hint),
+            ExprKind::Binary { op, lhs, rhs } => match op {
+                BinaryOp::Eq
+                | BinaryOp::Ne
+                | BinaryOp::Lt
+                | BinaryOp::Le
+                | BinaryOp::Gt
+                | BinaryOp::Ge
+                | BinaryOp::And
+                | BinaryOp::Or => Some(Type::Bool),
+                _ if is_untyped_literal(lhs) && !is_untyped_literal(rhs) => {
+                    self.static_type(rhs, hint)
+                }
+                _ => self.static_type(lhs, hint),
+            },
+            ExprKind::Call { callee, .. } => match &callee.kind {
+                ExprKind::Variable(name) => match self.signatures.get(name.as_str()) {
+                    Some((_, function)) => Some(function.ret.clone()),
+                    None => Some(Type::Void),
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn local(&self, name: &str, span: Span) -> CompileResult<&Local> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|local| local.name == name)
+            .ok_or_else(|| error(RuntimeErrorKind::UndefinedVariable(name.to_string()), span))
+    }
+
+    /// Adds a variable to the scope in a new slot.
+    fn declare(
+        &mut self,
+        name: &str,
+        mutable: bool,
+        initialized: bool,
+        ty: Option<Type>,
+        span: Span,
+    ) -> CompileResult<u16> {
+        let slot = self.slot(name, span)?;
+        self.scope.push(Local {
+            name: name.to_string(),
+            slot,
+            mutable,
+            initialized,
+            ty,
+        });
+        Ok(slot)
+    }
+
+    /// A new local slot. Every `let` gets its own so that the slot names the variable.
+    fn slot(&mut self, name: &str, span: Span) -> CompileResult<u16> {
+        let slot = u16::try_from(self.function.locals.len())
+            .map_err(|_| error(RuntimeErrorKind::TooLarge("local variables"), span))?;
+        self.function.locals.push(name.to_string());
+        Ok(slot)
+    }
+
+    fn constant(&mut self, value: Value, span: Span) -> CompileResult<u16> {
+        let key = format!("{:?}", value);
+        if let Some(index) = self.constants.get(&key) {
+            return Ok(*index);
+        }
+        let index = u16::try_from(self.module.constants.len())
+            .map_err(|_| error(RuntimeErrorKind::TooLarge("constants"), span))?;
+        self.module.constants.push(value);
+        self.constants.insert(key, index);
+        Ok(index)
+    }
+
+    /// Emits a `Check` of the value on top of the stack against `ty`.
+    fn check(&mut self, ty: &Type, span: Span) -> CompileResult<()> {
+        let index = match self.module.types.iter().position(|known| known == ty) {
+            Some(index) => index,
+            None => {
+                self.module.types.push(ty.clone());
+                self.module.types.len() - 1
+            }
+        };
+        let index =
+            u16::try_from(index).map_err(|_| error(RuntimeErrorKind::TooLarge("types"), span))?;
+        self.emit_with(Op::Check, index, span);
+        Ok(())
+    }
+
+    fn emit(&mut self, op: Op, span: Span) {
+        self.function.spans.push((self.function.code.len(), span));
+        self.function.code.push(op as u8);
+    }
+
+    fn emit_with(&mut self, op: Op, operand: u16, span: Span) {
+        self.emit(op, span);
+        self.function.code.extend(operand.to_le_bytes());
+    }
+
+    /// Emits a jump to be patched, returning where its operand is.
+    fn emit_jump(&mut self, op: Op, span: Span) -> usize {
+        self.emit_with(op, u16::MAX, span);
+        self.function.code.len() - 2
+    }
+
+    /// Makes the jump whose operand is at `at` go to the end of the code.
+    fn patch(&mut self, at: usize, span: Span) -> CompileResult<()> {
+        let target = self.offset(self.function.code.len(), span)?;
+        self.function.code[at..at + 2].copy_from_slice(&target.to_le_bytes());
+        Ok(())
+    }
+
+    /// `offset` as a jump operand.
+    fn offset(&self, offset: usize, span: Span) -> CompileResult<u16> {
+        u16::try_from(offset).map_err(|_| {
+            error(
+                RuntimeErrorKind::TooLarge("bytes of code in a function"),
+                span,
+            )
+        })
+    }
+}
@@ -0,0 +1,230 @@
+mod chunk;
+mod compile;
+mod op;
+mod verify;
+mod vyc;
+
+pub use chunk::{Function, Module};
+pub use compile::compile;
+pub use op::Op;
+pub use vyc::{VycError, MAGIC, VERSION};
+
+use crate::eval::value;
+use crate::eval::{error, RuntimeError, RuntimeErrorKind, Value, MAX_CALL_DEPTH};
+use crate::syn::ast::{Type, UnaryOp};
+use std::io::Write;
+
+type VmResult<T> = Result<T, RuntimeError>;
+
+/// Runs a `Module` compiled by `compile` with a stack of values, local slots and call frames.
+///
+/// The `Interpreter` and the `Vm` run a program the same and stop on the same errors.
+#[derive(Debug)]
+pub struct Vm<'m, W: Write> {
+    module: &'m Module,
+    stack: Vec<Value>,
+    /// The local slots of every call, the innermost last. `None` until assigned.
+    locals: Vec<Option<Value>>,
+    frames: Vec<Frame>,
+    out: W,
+}
+
+/// A call being run.
+#[derive(Debug)]
+struct Frame {
+    function: usize,
+    /// Where to continue in the function when the call it made returns.
+    ip: usize,
+    /// Where the local slots of the call start in `locals`.
+    base: usize,
+}
+
+impl<'m, W: Write> Vm<'m, W> {
+    pub fn new(module: &'m Module, out: W) -> Self {
+        Self {
+            module,
+            stack: Vec::new(),
+            locals: Vec::new(),
+            frames: Vec::new(),
+            out,
+        }
+    }
+
+    /// Runs `main`, then flushes the output.
+    pub fn run(&mut self) -> VmResult<()> {
+        let result = self.execute();
+        let flushed = self.out.flush().map_err(|e| {
+            let span = self
+                .module
+                .functions
+                .first()
+                .map(|f| f.span)
+                .unwrap_or_default();
+            error(RuntimeErrorKind::Output(e.kind()), span)
+        });
+        result.and(flushed)
+    }
+
+    /// The dispatch loop.
+    fn execute(&mut self) -> VmResult<()> {
+        let module = self.module;
+        let Some(main) = module.functions.first() else {
+            return Ok(());
+        };
+        self.locals.resize(main.locals.len(), None);
+        self.frames.push(Frame {
+            function: 0,
+            ip: 0,
+            base: 0,
+        });
+
+        // The current frame, kept out of `frames` while it runs.
+        let mut function = main;
+        let mut ip = 0;
+        let mut base = 0;
+        loop {
+            let start = ip;
+            let op = Op::from_byte(function.code[ip]).expect("invalid instruction");
+            ip += 1;
+            let operand = if op.has_operand() {
+                ip += 2;
+                function.read_u16(ip - 2) as usize
+            } else {
+                0
+            };
+            let fail = |kind: RuntimeErrorKind| error(kind, function.span_at(start));
+
+            match op {
+                Op::Const => self.stack.push(module.constants[operand].clone()),
+                Op::Load => match &self.locals[base + operand] {
+                    Some(value) => self.stack.push(value.clone()),
+                    None => {
+                        let name = function.locals[operand].clone();
+                        return Err(fail(RuntimeErrorKind::Uninitialized(name)));
+                    }
+                },
+                Op::Store => self.locals[base + operand] = Some(self.pop()),
+                Op::StoreOnce => {
+                    if self.locals[base + operand].is_some() {
+                        let name = function.locals[operand].clone();
+                        return Err(fail(RuntimeErrorKind::AssignToImmutable(name)));
+                    }
+                    self.locals[base + operand] = Some(self.pop());
+                }
+                Op::Clear => self.locals[base + operand] = None,
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Check => {
+                    let expected = &module.types[operand];
+                    let found = self.stack.last().map_or(Type::Void, Value::ty);
+                    if found != *expected {
+                        return Err(fail(RuntimeErrorKind::MismatchedTypes {
+                            expected: expected.clone(),
+                            found,
+                        }));
+                    }
+                }
+                Op::Neg | Op::Bang | Op::Not => {
+                    let op = match op {
+                        Op::Neg => UnaryOp::Neg,
+                        Op::Bang => UnaryOp::Bang,
+                        _ => UnaryOp::Not,
+                    };
+                    let value = self.pop();
+                    self.stack.push(value::unary(op, value).map_err(fail)?);
+                }
+                Op::Jump => ip = operand,
+                Op::JumpIfFalse => match self.pop() {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => ip = operand,
+                    value => {
+                        return Err(fail(RuntimeErrorKind::MismatchedTypes {
+                            expected: Type::Bool,
+                            found: value.ty(),
+                        }))
+                    }
+                },
+                Op::JumpIfFalseKeep => {
+                    if self.stack.last() == Some(&Value::Bool(false)) {
+                        ip = operand;
+                    }
+                }
+                Op::JumpIfTrueKeep => {
+                    if self.stack.last() == Some(&Value::Bool(true)) {
+                        ip = operand;
+                    }
+                }
+                Op::Call => {
+                    if self.frames.len() >= MAX_CALL_DEPTH {
+                        return Err(fail(RuntimeErrorKind::StackOverflow));
+                    }
+                    let callee = &module.functions[operand];
+                    if let Some(frame) = self.frames.last_mut() {
+                        frame.ip = ip;
+                    }
+
+                    base = self.locals.len();
+                    let args = self.stack.len() - callee.params;
+                    self.locals.extend(self.stack.drain(args..).map(Some));
+                    self.locals.resize(base + callee.locals.len(), None);
+                    self.frames.push(Frame {
+                        function: operand,
+                        ip: 0,
+                        base,
+                    });
+                    function = callee;
+                    ip = 0;
+                }
+                Op::Print | Op::Println => {
+                    let value = self.pop();
+                    let written = if op == Op::Println {
+                        writeln!(self.out, "{}", value)
+                    } else {
+                        write!(self.out, "{}", value)
+                    };
+                    written.map_err(|e| fail(RuntimeErrorKind::Output(e.kind())))?;
+                    self.stack.push(Value::Void);
+                }
+                Op::Format => {
+                    let parts = self.stack.len() - operand;
+                    let text = self.stack.drain(parts..).map(|part| part.to_string());
+                    let value = Value::Str(text.collect());
+                    self.stack.push(value);
+                }
+                Op::Return => {
+                    let value = self.pop();
+                    self.locals.truncate(base);
+                    self.frames.pop();
+                    let Some(frame) = self.frames.last() else {
+                        return Ok(());
+                    };
+                    function = &module.functions[frame.function];
+                    ip = frame.ip;
+                    base = frame.base;
+                    self.stack.push(value);
+                }
+                Op::MissingReturn => {
+                    return Err(fail(RuntimeErrorKind::MissingReturn {
+                        name: function.name.clone(),
+                        ty: function.ret.clone(),
+                    }))
+                }
+                _ => {
+                    let op = op
+                        .binary_op()
+                        .expect("every other instruction is a binary operator");
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+                    self.stack.push(value::binary(op, lhs, rhs).map_err(fail)?);
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("the compiler keeps the stack balanced")
+    }
+}
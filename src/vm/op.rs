@@ -0,0 +1,196 @@
+use crate::syn::ast::BinaryOp;
+
+/// An instruction of the bytecode, one byte followed by its operand if it has one.
+///
+/// Operands are `u16` in little endian. The `Vm` keeps a stack of values the instructions
+/// pop their inputs from and push their result to, and the local slots of every call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Op {
+    /// `Const index` pushes the constant `index` of the `Module`.
+    Const,
+    /// `Load slot` pushes the local `slot`, failing when it has no value yet.
+    Load,
+    /// `Store slot` pops a value into the local `slot`.
+    Store,
+    /// `Store slot` for a variable without `mut`, failing when the slot has a value already.
+    StoreOnce,
+    /// `Clear slot` removes the value of the local `slot`, for a `let` without a value.
+    Clear,
+    Pop,
+    /// `Check type` fails when the value on top of the stack is not of the type `type` of the
+    /// `Module`.
+    Check,
+    Neg,
+    Bang,
+    Not,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `and` of two `bool`s, after `JumpIfFalseKeep` skipped it when the left one is `false`.
+    And,
+    /// `or` of two `bool`s, after `JumpIfTrueKeep` skipped it when the left one is `true`.
+    Or,
+    /// `Jump offset` continues at `offset` in the function.
+    Jump,
+    /// `JumpIfFalse offset` pops a `bool` and jumps when it is `false`.
+    JumpIfFalse,
+    /// `JumpIfFalseKeep offset` jumps when the top of the stack is `false`, leaving it there.
+    JumpIfFalseKeep,
+    /// `JumpIfTrueKeep offset` jumps when the top of the stack is `true`, leaving it there.
+    JumpIfTrueKeep,
+    /// `Call function` pops the arguments of the function `function` of the `Module` and runs
+    /// it, its return value being pushed.
+    Call,
+    /// Pops and writes a value, then pushes `void`.
+    Print,
+    /// Pops and writes a value and a new line, then pushes `void`.
+    Println,
+    /// `Format count` pops `count` values and pushes them written one after the other as a
+    /// `String`.
+    Format,
+    /// Pops the return value and returns to the caller.
+    Return,
+    /// Fails as the end of a function that is not `void` was reached.
+    MissingReturn,
+}
+
+impl Op {
+    const ALL: [Op; 39] = [
+        Op::Const,
+        Op::Load,
+        Op::Store,
+        Op::StoreOnce,
+        Op::Clear,
+        Op::Pop,
+        Op::Check,
+        Op::Neg,
+        Op::Bang,
+        Op::Not,
+        Op::Add,
+        Op::Sub,
+        Op::Mul,
+        Op::Div,
+        Op::Rem,
+        Op::Pow,
+        Op::BitAnd,
+        Op::BitOr,
+        Op::BitXor,
+        Op::Shl,
+        Op::Shr,
+        Op::Eq,
+        Op::Ne,
+        Op::Lt,
+        Op::Le,
+        Op::Gt,
+        Op::Ge,
+        Op::And,
+        Op::Or,
+        Op::Jump,
+        Op::JumpIfFalse,
+        Op::JumpIfFalseKeep,
+        Op::JumpIfTrueKeep,
+        Op::Call,
+        Op::Print,
+        Op::Println,
+        Op::Format,
+        Op::Return,
+        Op::MissingReturn,
+    ];
+
+    pub fn from_byte(byte: u8) -> Option<Op> {
+        Op::ALL.get(byte as usize).copied()
+    }
+
+    /// Checks whether a `u16` operand follows the instruction.
+    pub fn has_operand(self) -> bool {
+        matches!(
+            self,
+            Op::Const
+                | Op::Load
+                | Op::Store
+                | Op::StoreOnce
+                | Op::Clear
+                | Op::Check
+                | Op::Jump
+                | Op::JumpIfFalse
+                | Op::JumpIfFalseKeep
+                | Op::JumpIfTrueKeep
+                | Op::Call
+                | Op::Format
+        )
+    }
+
+    /// The instruction of a binary operator.
+    pub fn binary(op: BinaryOp) -> Op {
+        match op {
+            BinaryOp::Add => Op::Add,
+            BinaryOp::Sub => Op::Sub,
+            BinaryOp::Mul => Op::Mul,
+            BinaryOp::Div => Op::Div,
+            BinaryOp::Rem => Op::Rem,
+            BinaryOp::Pow => Op::Pow,
+            BinaryOp::BitAnd => Op::BitAnd,
+            BinaryOp::BitOr => Op::BitOr,
+            BinaryOp::BitXor => Op::BitXor,
+            BinaryOp::Shl => Op::Shl,
+            BinaryOp::Shr => Op::Shr,
+            BinaryOp::Eq => Op::Eq,
+            BinaryOp::Ne => Op::Ne,
+            BinaryOp::Lt => Op::Lt,
+            BinaryOp::Le => Op::Le,
+            BinaryOp::Gt => Op::Gt,
+            BinaryOp::Ge => Op::Ge,
+            BinaryOp::And => Op::And,
+            BinaryOp::Or => Op::Or,
+        }
+    }
+
+    /// The binary operator of the instruction, `None` when it is not one.
+    pub fn binary_op(self) -> Option<BinaryOp> {
+        let op = match self {
+            Op::Add => BinaryOp::Add,
+            Op::Sub => BinaryOp::Sub,
+            Op::Mul => BinaryOp::Mul,
+            Op::Div => BinaryOp::Div,
+            Op::Rem => BinaryOp::Rem,
+            Op::Pow => BinaryOp::Pow,
+            Op::BitAnd => BinaryOp::BitAnd,
+            Op::BitOr => BinaryOp::BitOr,
+            Op::BitXor => BinaryOp::BitXor,
+            Op::Shl => BinaryOp::Shl,
+            Op::Shr => BinaryOp::Shr,
+            Op::Eq => BinaryOp::Eq,
+            Op::Ne => BinaryOp::Ne,
+            Op::Lt => BinaryOp::Lt,
+            Op::Le => BinaryOp::Le,
+            Op::Gt => BinaryOp::Gt,
+            Op::Ge => BinaryOp::Ge,
+            Op::And => BinaryOp::And,
+            Op::Or => BinaryOp::Or,
+            _ => return None,
+        };
+        Some(op)
+    }
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // `pad` so that the disassembler can align the instructions with `{:<16}`.
+        f.pad(&format!("{:?}", self))
+    }
+}
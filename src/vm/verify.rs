@@ -0,0 +1,136 @@
+use super::chunk::{Function, Module};
+use super::op::Op;
+
+/// Checks that the `Vm` can run `module` without reading out of bounds: every instruction is
+/// known and its operand refers to something that exists, jumps land on instructions, and the
+/// stack never underflows and holds the same number of values whichever way an instruction
+/// is reached.
+///
+/// `compile` only makes valid modules, this is for those loaded from a `.vyc` file.
+pub(super) fn verify(module: &Module) -> Result<(), String> {
+    match module.functions.first() {
+        None => return Err(String::from("there is no `main` function")),
+        Some(main) if main.params > 0 => {
+            return Err(String::from("`main` must not have parameters"))
+        }
+        Some(_) => {}
+    }
+    for function in &module.functions {
+        verify_function(module, function).map_err(|e| format!("in `{}`, {}", function.name, e))?;
+    }
+    Ok(())
+}
+
+fn verify_function(module: &Module, function: &Function) -> Result<(), String> {
+    if function.locals.len() < function.params {
+        return Err(String::from("there are fewer locals than parameters"));
+    }
+    if !function.spans.windows(2).all(|pair| pair[0].0 <= pair[1].0) {
+        return Err(String::from("the line table is not sorted"));
+    }
+
+    // The instructions and where they start, to check the jumps.
+    let code = &function.code;
+    let mut instructions = Vec::new();
+    let mut starts = vec![false; code.len()];
+    let mut offset = 0;
+    while offset < code.len() {
+        let op = Op::from_byte(code[offset])
+            .ok_or_else(|| format!("unknown instruction {:#04x} at {:04}", code[offset], offset))?;
+        let operand = if op.has_operand() {
+            if offset + 3 > code.len() {
+                return Err(format!(
+                    "the operand of `{}` at {:04} is cut off",
+                    op, offset
+                ));
+            }
+            function.read_u16(offset + 1) as usize
+        } else {
+            0
+        };
+        let count = match op {
+            Op::Const => Some(module.constants.len()),
+            Op::Load | Op::Store | Op::StoreOnce | Op::Clear => Some(function.locals.len()),
+            Op::Check => Some(module.types.len()),
+            Op::Call => Some(module.functions.len()),
+            _ => None,
+        };
+        if count.is_some_and(|count| operand >= count) {
+            return Err(format!(
+                "`{} {}` at {:04} is out of range",
+                op, operand, offset
+            ));
+        }
+        starts[offset] = true;
+        instructions.push((offset, op, operand));
+        offset += if op.has_operand() { 3 } else { 1 };
+    }
+
+    // The number of values on the stack before each instruction, found by following every
+    // path through the function.
+    let mut depths: Vec<Option<usize>> = vec![None; code.len()];
+    let index_of = |offset: usize| instructions.partition_point(|(start, _, _)| *start < offset);
+    let mut pending = vec![(0, 0)];
+    while let Some((offset, depth)) = pending.pop() {
+        if offset >= code.len() {
+            return Err(String::from("the code runs past its end"));
+        }
+        if !starts[offset] {
+            return Err(format!(
+                "a jump to {:04} lands inside an instruction",
+                offset
+            ));
+        }
+        match depths[offset] {
+            Some(known) if known == depth => continue,
+            Some(known) => {
+                return Err(format!(
+                    "the stack holds {} or {} values at {:04}",
+                    known, depth, offset
+                ))
+            }
+            None => depths[offset] = Some(depth),
+        }
+
+        let (_, op, operand) = instructions[index_of(offset)];
+        let next = offset + if op.has_operand() { 3 } else { 1 };
+        let (pops, pushes) = match op {
+            Op::Const | Op::Load => (0, 1),
+            Op::Store | Op::StoreOnce | Op::Pop | Op::JumpIfFalse => (1, 0),
+            Op::Clear | Op::Jump | Op::MissingReturn => (0, 0),
+            Op::Check
+            | Op::Neg
+            | Op::Bang
+            | Op::Not
+            | Op::JumpIfFalseKeep
+            | Op::JumpIfTrueKeep
+            | Op::Print
+            | Op::Println => (1, 1),
+            Op::Call => (module.functions[operand].params, 1),
+            Op::Format => (operand, 1),
+            Op::Return => (1, 0),
+            _ => (2, 1),
+        };
+        if depth < pops {
+            return Err(format!("`{}` at {:04} pops an empty stack", op, offset));
+        }
+        let after = depth - pops + pushes;
+
+        match op {
+            Op::Return if depth != 1 => {
+                return Err(format!(
+                    "the stack holds {} values at the return at {:04}",
+                    depth, offset
+                ))
+            }
+            Op::Return | Op::MissingReturn => {}
+            Op::Jump => pending.push((operand, after)),
+            Op::JumpIfFalse | Op::JumpIfFalseKeep | Op::JumpIfTrueKeep => {
+                pending.push((operand, after));
+                pending.push((next, after));
+            }
+            _ => pending.push((next, after)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,424 @@
+//! The `.vyc` file format of a compiled `Module`.
+//!
+//! All integers are little endian. A `str` is its length as a `u32` followed by its UTF-8
+//! bytes, a list is its length as a `u32` followed by its items.
+//!
+//! ```text
+//! magic      b"VYC\0"
+//! version    u16, `VERSION`
+//! files      list of str, the source files the module was compiled from
+//! constants  list of Value
+//! types      list of Type
+//! functions  list of Function, `main` first
+//! checksum   u32, CRC-32 of every byte before it
+//!
+//! Function   name str, params u16, ret Type, locals list of str, span Span,
+//!            code list of u8, line table list of (offset u32, Span)
+//! Span       start u32, end u32, line u32, column u32, file u32
+//! Type       u8 tag, 0 to 14 for `i8` `i16` `i32` `i64` `u8` `u16` `u32` `u64` `f32` `f64`
+//!            `bool` `byte` `char` `String` `void`, 15 followed by a str for a named type
+//! Value      u8 tag, 0 void, 1 Type and i128 integer, 2 Type and f64 bits as u64,
+//!            3 u8 bool, 4 u32 char, 5 str
+//! ```
+//!
+//! The line table maps every instruction back to the source for the errors it raises.
+
+use super::chunk::{Function, Module};
+use super::verify::verify;
+use crate::error::{Diagnostic, ToDiagnostic};
+use crate::eval::value::{int_range, is_float};
+use crate::eval::Value;
+use crate::lex::Span;
+use crate::syn::ast::Type;
+
+pub const MAGIC: [u8; 4] = *b"VYC\0";
+
+/// The version of the format written, the only one read. Bumped on every change to the
+/// format or to the meaning of the instructions.
+pub const VERSION: u16 = 2;
+
+/// Why a `.vyc` file could not be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VycError {
+    /// The file does not start with `MAGIC`.
+    NotVyc,
+    /// The file was written for another version of the format.
+    Version(u16),
+    /// The checksum does not match the content, the file is corrupt.
+    Checksum { expected: u32, found: u32 },
+    /// The file ends in the middle of the module.
+    Truncated,
+    /// The content does not describe a valid module, such as an unknown instruction or a
+    /// constant out of the range of its type.
+    Invalid(String),
+}
+
+impl std::fmt::Display for VycError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VycError::NotVyc => write!(f, "not a compiled vidyut file"),
+            VycError::Version(version) => write!(
+                f,
+                "compiled for format version {}, this vid reads version {}",
+                version, VERSION
+            ),
+            VycError::Checksum { expected, found } => write!(
+                f,
+                "corrupt file, checksum {:08x} does not match the content {:08x}",
+                expected, found
+            ),
+            VycError::Truncated => write!(f, "corrupt file, it ends too early"),
+            VycError::Invalid(reason) => write!(f, "invalid compiled file: {}", reason),
+        }
+    }
+}
+
+impl VycError {
+    /// The error code reported with the `Diagnostic`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            VycError::NotVyc => "E0300",
+            VycError::Version(_) => "E0301",
+            VycError::Checksum { .. } => "E0302",
+            VycError::Truncated => "E0303",
+            VycError::Invalid(_) => "E0304",
+        }
+    }
+}
+
+impl ToDiagnostic for VycError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string()).with_code(self.code());
+        match self {
+            VycError::NotVyc => diagnostic.with_help("`.vyc` files are written by `vid build`"),
+            _ => diagnostic.with_help("compile the source again with `vid build`"),
+        }
+    }
+}
+
+impl std::error::Error for VycError {}
+
+impl Module {
+    /// Serializes the module to the `.vyc` format.
+    pub fn to_vyc(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(MAGIC);
+        out.extend(VERSION.to_le_bytes());
+        write_len(&mut out, self.files.len());
+        for file in &self.files {
+            write_str(&mut out, file);
+        }
+
+        write_len(&mut out, self.constants.len());
+        for constant in &self.constants {
+            write_value(&mut out, constant);
+        }
+        write_len(&mut out, self.types.len());
+        for ty in &self.types {
+            write_type(&mut out, ty);
+        }
+        write_len(&mut out, self.functions.len());
+        for function in &self.functions {
+            write_str(&mut out, &function.name);
+            out.extend((function.params as u16).to_le_bytes());
+            write_type(&mut out, &function.ret);
+            write_len(&mut out, function.locals.len());
+            for local in &function.locals {
+                write_str(&mut out, local);
+            }
+            write_span(&mut out, function.span);
+            write_len(&mut out, function.code.len());
+            out.extend(&function.code);
+            write_len(&mut out, function.spans.len());
+            for (offset, span) in &function.spans {
+                write_len(&mut out, *offset);
+                write_span(&mut out, *span);
+            }
+        }
+
+        let checksum = crc32(&out);
+        out.extend(checksum.to_le_bytes());
+        out
+    }
+
+    /// Loads a module written by `to_vyc`, checking that it is intact and that its bytecode is
+    /// valid so that the `Vm` can run it.
+    pub fn from_vyc(bytes: &[u8]) -> Result<Module, VycError> {
+        if !bytes.starts_with(&MAGIC) {
+            return Err(VycError::NotVyc);
+        }
+        let version = bytes
+            .get(4..6)
+            .map(|v| u16::from_le_bytes([v[0], v[1]]))
+            .ok_or(VycError::Truncated)?;
+        if version != VERSION {
+            return Err(VycError::Version(version));
+        }
+        if bytes.len() < 10 {
+            return Err(VycError::Truncated);
+        }
+        let (content, checksum) = bytes.split_at(bytes.len() - 4);
+        let expected = u32::from_le_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+        let found = crc32(content);
+        if expected != found {
+            return Err(VycError::Checksum { expected, found });
+        }
+
+        let mut reader = Reader {
+            bytes: content,
+            position: 6,
+        };
+        let mut files = Vec::new();
+        for _ in 0..reader.u32()? {
+            files.push(reader.str()?);
+        }
+        let mut constants = Vec::new();
+        for _ in 0..reader.u32()? {
+            constants.push(reader.value()?);
+        }
+        let mut types = Vec::new();
+        for _ in 0..reader.u32()? {
+            types.push(reader.ty()?);
+        }
+        let mut functions = Vec::new();
+        for _ in 0..reader.u32()? {
+            functions.push(reader.function()?);
+        }
+        if reader.position != content.len() {
+            return Err(VycError::Invalid(String::from(
+                "unexpected bytes after the functions",
+            )));
+        }
+
+        let module = Module {
+            files,
+            functions,
+            constants,
+            types,
+        };
+        verify(&module).map_err(VycError::Invalid)?;
+        Ok(module)
+    }
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) {
+    out.extend((len as u32).to_le_bytes());
+}
+
+fn write_str(out: &mut Vec<u8>, text: &str) {
+    write_len(out, text.len());
+    out.extend(text.as_bytes());
+}
+
+fn write_span(out: &mut Vec<u8>, span: Span) {
+    for value in [span.start, span.end, span.line, span.column, span.file] {
+        write_len(out, value);
+    }
+}
+
+const TYPES: [Type; 15] = [
+    Type::I8,
+    Type::I16,
+    Type::I32,
+    Type::I64,
+    Type::U8,
+    Type::U16,
+    Type::U32,
+    Type::U64,
+    Type::F32,
+    Type::F64,
+    Type::Bool,
+    Type::Byte,
+    Type::Char,
+    Type::String,
+    Type::Void,
+];
+
+fn write_type(out: &mut Vec<u8>, ty: &Type) {
+    match ty {
+        Type::Named(name) => {
+            out.push(TYPES.len() as u8);
+            write_str(out, name);
+        }
+        _ => out.push(
+            TYPES
+                .iter()
+                .position(|known| known == ty)
+                .unwrap_or_default() as u8,
+        ),
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Void => out.push(0),
+        Value::Int { value, ty } => {
+            out.push(1);
+            write_type(out, ty);
+            out.extend(value.to_le_bytes());
+        }
+        Value::Float { value, ty } => {
+            out.push(2);
+            write_type(out, ty);
+            out.extend(value.to_bits().to_le_bytes());
+        }
+        Value::Bool(value) => out.extend([3, *value as u8]),
+        Value::Char(value) => {
+            out.push(4);
+            out.extend((*value as u32).to_le_bytes());
+        }
+        Value::Str(value) => {
+            out.push(5);
+            write_str(out, value);
+        }
+    }
+}
+
+/// Reads the content of a `.vyc` file, failing with `Truncated` past its end.
+struct Reader<'b> {
+    bytes: &'b [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], VycError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position.saturating_add(len))
+            .ok_or(VycError::Truncated)?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, VycError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, VycError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, VycError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn usize(&mut self) -> Result<usize, VycError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn str(&mut self) -> Result<String, VycError> {
+        let len = self.usize()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| VycError::Invalid(String::from("a string is not valid UTF-8")))
+    }
+
+    fn span(&mut self) -> Result<Span, VycError> {
+        Ok(Span {
+            start: self.usize()?,
+            end: self.usize()?,
+            line: self.usize()?,
+            column: self.usize()?,
+            file: self.usize()?,
+        })
+    }
+
+    fn ty(&mut self) -> Result<Type, VycError> {
+        let tag = self.u8()?;
+        match TYPES.get(tag as usize) {
+            Some(ty) => Ok(ty.clone()),
+            None if tag as usize == TYPES.len() => Ok(Type::Named(self.str()?)),
+            None => Err(VycError::Invalid(format!("unknown type tag {}", tag))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, VycError> {
+        let value = match self.u8()? {
+            0 => Value::Void,
+            1 => {
+                let ty = self.ty()?;
+                let bytes = self.take(16)?;
+                let value = i128::from_le_bytes(bytes.try_into().unwrap_or_default());
+                if int_range(&ty).is_none() {
+                    return Err(VycError::Invalid(format!(
+                        "integer constant of type `{}`",
+                        ty
+                    )));
+                }
+                Value::int(value, ty.clone()).ok_or_else(|| {
+                    VycError::Invalid(format!("constant {} is out of range for `{}`", value, ty))
+                })?
+            }
+            2 => {
+                let ty = self.ty()?;
+                let bytes = self.take(8)?;
+                let value =
+                    f64::from_bits(u64::from_le_bytes(bytes.try_into().unwrap_or_default()));
+                if !is_float(&ty) {
+                    return Err(VycError::Invalid(format!(
+                        "float constant of type `{}`",
+                        ty
+                    )));
+                }
+                Value::float(value, ty)
+            }
+            3 => match self.u8()? {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                byte => return Err(VycError::Invalid(format!("bool constant {}", byte))),
+            },
+            4 => {
+                let code = self.u32()?;
+                let c = char::from_u32(code).ok_or_else(|| {
+                    VycError::Invalid(format!("char constant {:#x} is not a unicode scalar", code))
+                })?;
+                Value::Char(c)
+            }
+            5 => Value::Str(self.str()?),
+            tag => return Err(VycError::Invalid(format!("unknown constant tag {}", tag))),
+        };
+        Ok(value)
+    }
+
+    fn function(&mut self) -> Result<Function, VycError> {
+        let name = self.str()?;
+        let params = self.u16()? as usize;
+        let ret = self.ty()?;
+        let mut locals = Vec::new();
+        for _ in 0..self.u32()? {
+            locals.push(self.str()?);
+        }
+        let span = self.span()?;
+        let len = self.usize()?;
+        let code = self.take(len)?.to_vec();
+        let mut spans = Vec::new();
+        for _ in 0..self.u32()? {
+            spans.push((self.usize()?, self.span()?));
+        }
+
+        Ok(Function {
+            name,
+            params,
+            ret,
+            locals,
+            code,
+            spans,
+            span,
+        })
+    }
+}
+
+/// The CRC-32 of `bytes`, as used by zip and PNG.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::vid;
+
+#[test]
+fn a_missing_command_is_a_usage_error() {
+    let output = vid(&[], "");
+    assert_eq!(output.status, 2);
+    assert!(output.stderr.contains("Usage: vid"), "{}", output.stderr);
+
+    let output = vid(&["parse"], "");
+    assert_eq!(output.status, 2);
+    assert!(
+        output.stderr.contains("`vid parse` expects a file"),
+        "{}",
+        output.stderr
+    );
+}
+
+#[test]
+fn parse_prints_the_tree_without_spans() {
+    let source = "\
+import math.vector as vec;
+struct Point { pub i32 x, i32 y, }
+void main() {
+    let mut p: Point <- origin();
+    p.x += 1;
+    if p.x == 1 { println(\"one {p.x}\"); } else if true { } else { return; }
+    while f(p)[0] { break; }
+}
+i32 f(Point p, i32 n) { return -p.x * (n + 1); }
+";
+    let output = vid(&["parse", "-"], source);
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    assert_eq!(
+        output.stdout,
+        "\
+(program
+  (import math.vector as vec)
+  (struct Point
+    (field pub i32 x)
+    (field i32 y))
+  (main
+    (let mut p (: Point) (call origin))
+    (+= (. p x) 1)
+    (if (== (. p x) 1)
+      (block
+        (call println (interpolate \"one \" (. p x))))
+      (if true
+        (block)
+        (block
+          (return))))
+    (while (index (call f p) 0)
+      (block
+        (break))))
+  (fn i32 f ((Point p) (i32 n))
+    (return (* (- (. p x)) (paren (+ n 1))))))
+"
+    );
+}
+
+#[test]
+fn parse_output_grows_with_the_source() {
+    let terms = vec!["1"; 190].join(" + ");
+    let output = vid(
+        &["parse", "-"],
+        &format!("void main() {{ println({}); }}", terms),
+    );
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    assert!(output.stdout.len() < 2000, "{} bytes", output.stdout.len());
+}
@@ -0,0 +1,75 @@
+//! Runs the `vid` binary on programs written in the tests, as a user would.
+
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// What a run of `vid` printed, and its exit status.
+#[derive(Debug)]
+pub struct Output {
+    pub status: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs `vid` with `args` in the directory `dir`, giving it `stdin`. The errors are reported
+/// in the short format, without color.
+pub fn vid_in(dir: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vidyut"))
+        .args(["--color=never", "--error-format=short"])
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run vid");
+    // `vid` may exit before reading all of it, as on a usage error.
+    let _ = child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(stdin.as_bytes());
+    let output = child.wait_with_output().expect("failed to wait for vid");
+    Output {
+        status: output.status.code().unwrap_or(-1),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    }
+}
+
+/// Runs `vid` with `args`, giving it `stdin`.
+pub fn vid(args: &[&str], stdin: &str) -> Output {
+    vid_in(&std::env::temp_dir(), args, stdin)
+}
+
+/// The diagnostics `vid check` reports for `source`, one per line as
+/// `line:column: error[code]: message`.
+pub fn check(source: &str) -> Vec<String> {
+    diagnostics(&vid(&["check", "-"], source))
+}
+
+/// The diagnostics of `output`, without the `<stdin>:` in front of them.
+pub fn diagnostics(output: &Output) -> Vec<String> {
+    output
+        .stderr
+        .lines()
+        .map(|line| line.strip_prefix("<stdin>:").unwrap_or(line).to_string())
+        .collect()
+}
+
+/// Writes `files`, pairs of a path and its content, into a new directory named after `name`
+/// and returns it.
+pub fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("vidyut-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().expect("a file is in a directory"))
+            .expect("failed to create the project directory");
+        std::fs::write(path, content).expect("failed to write a project file");
+    }
+    dir
+}
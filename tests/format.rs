@@ -0,0 +1,86 @@
+mod common;
+
+use common::vid;
+
+/// What `vid fmt` prints for `source`, checking that formatting it again changes nothing.
+fn format(source: &str) -> String {
+    let output = vid(&["fmt", "-"], source);
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    let again = vid(&["fmt", "-"], &output.stdout);
+    assert_eq!(again.stdout, output.stdout, "formatting again changed it");
+    output.stdout
+}
+
+#[test]
+fn code_is_laid_out_in_the_canonical_style() {
+    assert_eq!(
+        format("i32 add(i32 a,i32 b){return a+b;}\nvoid main(){let x<-add(1,2);\n\n\nprintln(x);}"),
+        "\
+i32 add(i32 a, i32 b) {
+    return a + b;
+}
+
+void main() {
+    let x <- add(1, 2);
+
+    println(x);
+}
+"
+    );
+}
+
+#[test]
+fn comments_between_statements_stay_on_their_lines() {
+    let source = "\
+//! A module.
+
+/// Adds.
+i32 add(i32 a, i32 b) {
+    // Sum.
+    return a + b; // done
+}
+
+void main() {
+    println(add(1, 2));
+    // left over
+}
+";
+    assert_eq!(format(source), source);
+}
+
+#[test]
+fn comments_inside_expressions_stay_in_place() {
+    let source = "\
+void main() {
+    add(1, /* one */ 2);
+    let x <- add(1 /* one */, 2, // two
+        3);
+    let y <- [1, /* a */ 2 /* b */];
+    while x > /* zero */ 0 {
+        println(x);
+    }
+}
+";
+    assert_eq!(format(source), source);
+}
+
+#[test]
+fn comments_inside_parameter_lists_stay_in_place() {
+    let source = "\
+i32 add(i32 a /* the first */, i32 b, // the second
+    i32 c) {
+    return a + b + c;
+}
+
+void main() {}
+";
+    assert_eq!(format(source), source);
+}
+
+#[test]
+fn check_reports_unformatted_input() {
+    let output = vid(&["fmt", "--check", "-"], "void main(){}");
+    assert_eq!(output.status, 1);
+    let output = vid(&["fmt", "--check", "-"], "void main() {}\n");
+    assert_eq!(output.status, 0, "{}", output.stderr);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{check, vid};
+
+#[test]
+fn immutable_variables_are_assigned_once() {
+    assert_eq!(
+        check("void main() { let x <- 1; x <- 2; println(x); }"),
+        ["1:27: error[E0600]: cannot assign twice to immutable variable `x`"]
+    );
+    assert_eq!(
+        check("void main() { let x <- 1; x += 1; }"),
+        ["1:27: error[E0600]: cannot assign twice to immutable variable `x`"]
+    );
+    assert_eq!(
+        check("void main() { let x; x <- 1; x <- 2; println(x); }"),
+        ["1:30: error[E0600]: cannot assign twice to immutable variable `x`"]
+    );
+}
+
+#[test]
+fn a_variable_declared_without_a_value_is_assigned_once_on_each_path() {
+    assert_eq!(
+        check("void main() { let x; if true { x <- 1; } else { x <- 2; } println(x); }"),
+        Vec::<String>::new()
+    );
+    // The loop can assign it again.
+    assert_eq!(
+        check("void main() { let x; while true { x <- 1; } }"),
+        ["1:35: error[E0600]: cannot assign twice to immutable variable `x`"]
+    );
+}
+
+#[test]
+fn parameters_and_parts_of_immutable_variables_are_not_assigned() {
+    assert_eq!(
+        check("i32 f(i32 a) { a <- 2; return a; }\nvoid main() {}"),
+        ["1:16: error[E0601]: cannot assign to parameter `a`"]
+    );
+    assert_eq!(
+        check("struct P { i32 x, }\nvoid main() { let p: P; p.x <- 1; }"),
+        ["2:25: error[E0602]: cannot assign to a part of immutable variable `p`"]
+    );
+}
+
+#[test]
+fn mutable_variables_are_assigned_freely() {
+    let output = vid(
+        &["run", "-"],
+        "void main() { let mut x <- 1; x <- 2; x += 1; println(x); }",
+    );
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    assert_eq!(output.stdout, "3\n");
+
+    // A shadowing `let mut` makes a new, mutable variable.
+    let output = vid(
+        &["run", "-"],
+        "void main() { let x <- 1; let mut x <- x + 1; x += 1; println(x); }",
+    );
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    assert_eq!(output.stdout, "3\n");
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::{check, diagnostics, vid};
+
+/// `main` with `body` in it.
+fn main_with(body: &str) -> String {
+    format!("void main() {{\n{}\n}}\n", body)
+}
+
+/// `println(1 + 1 + …)` with `terms` terms.
+fn sum(terms: usize) -> String {
+    main_with(&format!("println({});", vec!["1"; terms].join(" + ")))
+}
+
+/// An `if` followed by `branches` `else if`s.
+fn else_ifs(branches: usize) -> String {
+    let mut body = String::from("let x <- 1;\nif x == 0 { println(0); }");
+    for i in 1..=branches {
+        body.push_str(&format!(" else if x == {} {{ println({}); }}", i, i));
+    }
+    main_with(&body)
+}
+
+/// Checks that `source` is reported as nested too deeply by every command reading it, and
+/// that none of them overflows its stack.
+fn assert_too_deep(source: &str) {
+    for args in [
+        &["check", "-"][..],
+        &["run", "-"],
+        &["run", "--vm", "-"],
+        &["parse", "-"],
+        &["fmt", "-"],
+    ] {
+        let output = vid(args, source);
+        assert_eq!(output.status, 1, "vid {:?}: {}", args, output.stderr);
+        let errors = diagnostics(&output);
+        assert_eq!(errors.len(), 1, "vid {:?}: {:?}", args, errors);
+        assert!(
+            errors[0].ends_with("error[E0100]: nesting deeper than 200 levels"),
+            "vid {:?}: {:?}",
+            args,
+            errors
+        );
+    }
+}
+
+#[test]
+fn nested_parentheses_are_limited() {
+    let depth = 5000;
+    assert_too_deep(&main_with(&format!(
+        "println({}1{});",
+        "(".repeat(depth),
+        ")".repeat(depth)
+    )));
+}
+
+#[test]
+fn nested_blocks_are_limited() {
+    let depth = 5000;
+    assert_too_deep(&main_with(&format!(
+        "{}{}",
+        "while true { ".repeat(depth),
+        "} ".repeat(depth)
+    )));
+}
+
+#[test]
+fn nested_unary_operators_are_limited() {
+    assert_too_deep(&main_with(&format!("println({}1);", "-".repeat(5000))));
+}
+
+#[test]
+fn binary_chains_are_limited() {
+    assert_too_deep(&sum(5000));
+}
+
+#[test]
+fn call_chains_are_limited() {
+    assert_too_deep(&main_with(&format!("f{};", "()".repeat(5000))));
+}
+
+#[test]
+fn field_chains_are_limited() {
+    assert_too_deep(&main_with(&format!("let x <- a{};", ".b".repeat(5000))));
+}
+
+#[test]
+fn index_chains_are_limited() {
+    assert_too_deep(&main_with(&format!("let x <- a{};", "[0]".repeat(5000))));
+}
+
+#[test]
+fn else_if_chains_are_limited() {
+    assert_too_deep(&else_ifs(5000));
+    assert_too_deep(&else_ifs(20000));
+}
+
+#[test]
+fn nesting_below_the_limit_runs() {
+    let source = sum(190);
+    for args in [&["run", "-"][..], &["run", "--vm", "-"]] {
+        let output = vid(args, &source);
+        assert_eq!(output.status, 0, "vid {:?}: {}", args, output.stderr);
+        assert_eq!(output.stdout, "190\n");
+    }
+
+    let output = vid(&["run", "-"], &else_ifs(150));
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    assert_eq!(output.stdout, "1\n");
+
+    let output = vid(&["fmt", "-"], &sum(190));
+    assert_eq!(output.status, 0, "{}", output.stderr);
+
+    let output = vid(&["parse", "-"], &else_ifs(150));
+    assert_eq!(output.status, 0, "{}", output.stderr);
+}
+
+#[test]
+fn syntax_errors_point_at_the_unexpected_token() {
+    assert_eq!(
+        check("void main() { let x <- 1 }"),
+        ["1:26: error[E0100]: expected `;` after variable declaration, found `}`"]
+    );
+    assert_eq!(
+        check("void main() { println(1 +); }"),
+        ["1:26: error[E0100]: expected an expression, found `)`"]
+    );
+    assert_eq!(
+        check("void main() { let x <- (1 + 2; }"),
+        ["1:30: error[E0100]: expected `)` after expression, found `;`"]
+    );
+    assert_eq!(
+        check("i32 f( { }"),
+        ["1:8: error[E0100]: expected a type, found `{`"]
+    );
+}
+
+#[test]
+fn end_of_file_is_named_in_syntax_errors() {
+    assert_eq!(
+        check("void main() {"),
+        ["1:14: error[E0100]: expected `}` to close the block, found end of file"]
+    );
+    assert_eq!(
+        check(""),
+        ["1:1: error[E0100]: expected `void main()`, found end of file"]
+    );
+}
+
+#[test]
+fn lexical_errors_are_reported_instead_of_syntax_errors() {
+    // The `$` and `#` would make syntax errors too.
+    assert_eq!(
+        check("void main() { let y <- 1 $ 2; let z <- 3 # 4; }"),
+        [
+            "1:26: error[E0001]: unexpected character `$`",
+            "1:42: error[E0001]: unexpected character `#`",
+        ]
+    );
+}
+
+#[test]
+fn only_the_first_syntax_error_is_reported() {
+    let output = vid(&["check", "-"], "void main() { let x <- ; let y <- ; }");
+    assert_eq!(output.status, 1);
+    assert_eq!(
+        diagnostics(&output),
+        ["1:24: error[E0100]: expected an expression, found `;`"]
+    );
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{check, project, vid, vid_in};
+
+/// The errors `vid check` reports for `source`, in the human format with their labels and
+/// notes.
+fn report(source: &str) -> String {
+    let output = vid(&["--error-format=human", "check", "-"], source);
+    assert_eq!(output.status, 1, "{}", output.stderr);
+    output.stderr
+}
+
+#[test]
+fn undefined_names_are_reported() {
+    assert_eq!(
+        check("void main() { println(total); }"),
+        ["1:23: error[E0500]: cannot find variable `total` in this scope"]
+    );
+    assert_eq!(
+        check("void main() { lenght(1); }"),
+        ["1:15: error[E0501]: cannot find function `lenght` in this scope"]
+    );
+    assert_eq!(
+        check("enum Color { Red, Green }\nvoid main() { let c <- Color.Rde; }"),
+        ["2:24: error[E0508]: no variant `Rde` in enum `Color`"]
+    );
+}
+
+#[test]
+fn misspelled_names_get_a_suggestion() {
+    // A swap of two letters is one edit.
+    let errors = report("void main() { let count <- 1; println(cuont); println(coutn); }");
+    assert_eq!(
+        errors.matches("help: did you mean `count`?").count(),
+        2,
+        "{}",
+        errors
+    );
+
+    let errors = report("void main() { let count <- 1; println(coun); }");
+    assert!(errors.contains("help: did you mean `count`?"), "{}", errors);
+
+    let errors = report("void main() { let count <- 1; println(total); }");
+    assert!(!errors.contains("did you mean"), "{}", errors);
+}
+
+#[test]
+fn variables_are_used_after_their_declaration() {
+    assert_eq!(
+        check("void main() { println(x); let x <- 1; }"),
+        ["1:23: error[E0503]: variable `x` is used before its declaration"]
+    );
+    // The variable is not in scope in its own value.
+    assert_eq!(
+        check("void main() { let x <- x + 1; }"),
+        ["1:24: error[E0503]: variable `x` is used before its declaration"]
+    );
+    let output = vid(
+        &["run", "-"],
+        "void main() { let x <- 1; if true { let x <- x + 1; println(x); } println(x); }",
+    );
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    assert_eq!(output.stdout, "2\n1\n");
+}
+
+#[test]
+fn names_are_declared_once() {
+    assert_eq!(
+        check("i32 f() { return 1; }\ni32 f() { return 2; }\nvoid main() {}"),
+        ["2:1: error[E0504]: the function `f` is declared more than once"]
+    );
+    assert_eq!(
+        check("i32 f(i32 a, i32 a) { return a; }\nvoid main() {}"),
+        ["1:14: error[E0504]: the parameter `a` is declared more than once"]
+    );
+}
+
+#[test]
+fn an_item_declared_as_an_import_is_reported_at_the_item() {
+    let dir = project(
+        "duplicate-import",
+        &[
+            ("m.vy", "pub i32 dot(i32 a) { return a; }\nvoid main() {}\n"),
+            (
+                "main.vy",
+                "import m.{dot};\nvoid main() {}\ni32 dot() { return 1; }\n",
+            ),
+        ],
+    );
+    let output = vid_in(&dir, &["--error-format=human", "check", "main.vy"], "");
+    assert_eq!(output.status, 1);
+    let first = output.stderr.find("first declared here").expect("a label");
+    let again = output.stderr.find("declared again here").expect("a label");
+    assert!(first < again, "{}", output.stderr);
+    assert!(
+        output.stderr.contains("--> main.vy:3:1"),
+        "{}",
+        output.stderr
+    );
+}
+
+#[test]
+fn thousands_of_variables_resolve() {
+    let lets = (0..20_000)
+        .map(|i| format!("let x{} <- {};", i, i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let output = vid(&["check", "-"], &format!("void main() {{\n{}\n}}\n", lets));
+    assert_eq!(output.status, 0, "{}", output.stderr);
+}
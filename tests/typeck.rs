@@ -0,0 +1,128 @@
+mod common;
+
+use common::{check, vid};
+
+/// What `vid run` prints for `source`, which must run without errors.
+fn run(source: &str) -> String {
+    let output = vid(&["run", "-"], source);
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    output.stdout
+}
+
+#[test]
+fn values_must_have_the_type_expected() {
+    assert_eq!(
+        check("i32 f(i32 a) { return a; }\nvoid main() { let x: i64 <- 1; f(x); }"),
+        ["2:34: error[E0400]: mismatched types: expected `i32`, found `i64`"]
+    );
+    assert_eq!(
+        check("void main() { let x: bool <- 1; }"),
+        ["1:30: error[E0400]: mismatched types: expected `bool`, found `i32`"]
+    );
+}
+
+#[test]
+fn operators_must_take_their_operand_types() {
+    assert_eq!(
+        check("void main() { let x <- true + 1; }"),
+        ["1:24: error[E0401]: cannot apply `+` to `bool` and `i32`"]
+    );
+    assert_eq!(
+        check("void main() { let x: u8 <- 1; let y <- -x; }"),
+        ["1:40: error[E0402]: cannot apply `-` to `u8`"]
+    );
+}
+
+#[test]
+fn calls_and_returns_are_checked() {
+    assert_eq!(
+        check("i32 f(i32 a) { return a; }\nvoid main() { f(1, 2); }"),
+        ["2:15: error[E0404]: `f` takes 1 argument but 2 were given"]
+    );
+    assert_eq!(
+        check("i32 f(i32 a) { if a > 0 { return 1; } }\nvoid main() {}"),
+        ["1:1: error[E0405]: `f` can end without returning a value"]
+    );
+    assert_eq!(
+        check("void g() {}\nvoid main() { let x <- g(); }"),
+        ["2:24: error[E0410]: `x` is given the result of a `void` function"]
+    );
+}
+
+#[test]
+fn fields_methods_and_indexes_are_checked() {
+    assert_eq!(
+        check("struct P { i32 x, }\nvoid main() { let p: P; let y <- p.z; }"),
+        ["2:34: error[E0407]: no field `z` on type `P`"]
+    );
+    assert_eq!(
+        check("void main() { let x: i32 <- 1; x.foo(); }"),
+        ["1:32: error[E0408]: no method `foo` on type `i32`"]
+    );
+    assert_eq!(
+        check("void main() { let x <- 1; let y <- x[0]; }"),
+        ["1:36: error[E0409]: cannot index into a value of type `i32`"]
+    );
+}
+
+#[test]
+fn numbers_without_a_type_have_no_fields_nor_methods() {
+    assert_eq!(
+        check("void main() { let x <- 1; x.foo(); let y <- 2.5; let z <- y.w; }"),
+        [
+            "1:27: error[E0408]: no method `foo` on type `i32`",
+            "1:59: error[E0407]: no field `w` on type `f64`",
+        ]
+    );
+}
+
+#[test]
+fn literals_must_fit_their_type() {
+    assert_eq!(
+        check("void main() { let x: u8 <- 300; }"),
+        ["1:28: error[E0403]: literal `300` is out of range for `u8`"]
+    );
+    // Without anything giving it a type, an integer is an `i32`.
+    assert_eq!(
+        check("void main() { let x <- 3000000000; println(x); }"),
+        ["1:24: error[E0403]: literal `3000000000` is out of range for `i32`"]
+    );
+}
+
+#[test]
+fn variables_without_a_type_are_inferred() {
+    assert_eq!(
+        check("void main() { let x; }"),
+        ["1:15: error[E0406]: cannot infer the type of `x`"]
+    );
+    // `x` is an `i64` from its use.
+    assert_eq!(
+        run("void main() { let x <- 7; let y: i64 <- x; println(y * 1000000000000); }"),
+        "7000000000000\n"
+    );
+    assert_eq!(run("void main() { let x; x <- 2; println(x); }"), "2\n");
+}
+
+#[test]
+fn numbers_default_to_i32_and_f64() {
+    let output = vid(
+        &["run", "-"],
+        "void main() { let x <- 2147483647; let y <- x + 1; println(y); }",
+    );
+    assert_eq!(output.status, 1);
+    assert!(
+        output
+            .stderr
+            .contains("error[E0203]: `+` overflows the range of `i32`"),
+        "{}",
+        output.stderr
+    );
+    assert_eq!(
+        check("void main() { let x <- 1.5; println(x / 2); }"),
+        ["1:37: error[E0401]: cannot apply `/` to `f64` and `i32`"]
+    );
+    assert_eq!(
+        run("void main() { let x <- 1.5; println(x / 2.0); }"),
+        "0.75\n"
+    );
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{project, vid_in};
+
+const SHAPES: &str = "\
+pub struct Point {
+    pub i32 x,
+    i32 y,
+}
+
+struct Secret {
+    i32 s,
+}
+
+pub enum Color { Red, Green }
+
+pub i32 area(Point p) { return p.x * p.y; }
+i32 hidden() { return 1; }
+pub i32 unused() { return 2; }
+void main() {}
+";
+
+/// The errors and warnings `vid check` reports for `main`, the main file of a project next
+/// to the module `geo.shapes`.
+fn check(name: &str, main: &str) -> Vec<String> {
+    let dir = project(name, &[("geo/shapes.vy", SHAPES), ("main.vy", main)]);
+    let output = vid_in(&dir, &["check", "main.vy"], "");
+    output.stderr.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn public_items_and_fields_are_used_from_other_modules() {
+    assert_eq!(
+        check(
+            "pub-used",
+            "\
+import geo.shapes;
+import geo.shapes.{Point, Color};
+i32 f(Point p) { return p.x + shapes.area(p) + shapes.unused(); }
+void main() { let c <- Color.Red; }
+",
+        ),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn private_items_are_not_used_from_other_modules() {
+    assert_eq!(
+        check(
+            "private-fn",
+            "import geo.shapes;\nvoid main() { println(shapes.hidden()); }\n",
+        ),
+        ["main.vy:2:23: error[E0506]: function `hidden` is private"]
+    );
+    assert_eq!(
+        check(
+            "private-import",
+            "import geo.shapes.{Point, hidden};\nvoid main() {}\n"
+        ),
+        ["main.vy:1:27: error[E0506]: function `hidden` is private"]
+    );
+    assert_eq!(
+        check(
+            "private-struct",
+            "import geo.shapes.{Secret};\nvoid main() {}\n"
+        ),
+        ["main.vy:1:20: error[E0506]: struct `Secret` is private"]
+    );
+}
+
+#[test]
+fn private_fields_are_not_used_from_other_modules() {
+    let diagnostics = check(
+        "private-field",
+        "import geo.shapes.{Point};\ni32 f(Point p) { return p.x + p.y; }\nvoid main() {}\n",
+    );
+    // After the warnings of the `pub` items `main.vy` does not use.
+    assert_eq!(
+        diagnostics.last().map(String::as_str),
+        Some("main.vy:2:31: error[E0506]: field `y` of struct `geo.shapes.Point` is private")
+    );
+}
+
+#[test]
+fn public_items_no_other_module_uses_are_reported() {
+    let diagnostics = check(
+        "pub-unused",
+        "import geo.shapes.{Point};\ni32 f(Point p) { return p.x; }\nvoid main() {}\n",
+    );
+    assert_eq!(
+        diagnostics,
+        [
+            "geo/shapes.vy:10:1: warning: enum `Color` is `pub` but no other module uses it",
+            "geo/shapes.vy:12:1: warning: function `area` is `pub` but no other module uses it",
+            "geo/shapes.vy:14:1: warning: function `unused` is `pub` but no other module uses it",
+        ]
+    );
+}
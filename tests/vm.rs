@@ -0,0 +1,112 @@
+mod common;
+
+use common::{diagnostics, vid};
+
+/// Runs `source` with the interpreter and with the VM, checking that both print the same
+/// output and errors and exit the same way, and returns what they printed.
+fn run_both(source: &str) -> (i32, String, Vec<String>) {
+    let interpreted = vid(&["run", "-"], source);
+    let compiled = vid(&["run", "--vm", "-"], source);
+    assert_eq!(interpreted.stdout, compiled.stdout, "{}", source);
+    assert_eq!(interpreted.stderr, compiled.stderr, "{}", source);
+    assert_eq!(interpreted.status, compiled.status, "{}", source);
+    (
+        interpreted.status,
+        interpreted.stdout.clone(),
+        diagnostics(&interpreted),
+    )
+}
+
+#[test]
+fn control_flow_runs_the_same() {
+    let source = "\
+i32 fib(i32 n) {
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+void main() {
+    let mut i <- 0;
+    while i < 10 {
+        i += 1;
+        if i % 2 == 0 {
+            continue;
+        } else if i > 7 {
+            break;
+        }
+        print(\"{i}:{fib(i)} \");
+    }
+    println(\"\");
+    match i {
+        8 => { println(\"eight\"); }
+        9 => { println(\"nine\"); }
+        _ => { println(\"other\"); }
+    }
+}
+";
+    let (status, stdout, _) = run_both(source);
+    assert_eq!(status, 0);
+    assert_eq!(stdout, "1:1 3:2 5:5 7:13 \nnine\n");
+}
+
+#[test]
+fn operators_compute_the_same() {
+    let source = "\
+void main() {
+    let x: u8 <- 200;
+    let y: i64 <- -5;
+    println(\"{x / 3} {y % 3} {y ** 3} {7 >> 1} {1 << 4} {6 & 3} {6 | 3} {6 ^ 3} {!5}\");
+    println(\"{1.5 * 2.0} {10.0 / 4.0} {2.0 ** 0.5} {1.0 / 3.0}\");
+    println(\"{true and not false} {1 < 2 or false} {'c'} {\"s\"}\");
+    println(b'a');
+    println(0xFF);
+}
+";
+    let (status, stdout, _) = run_both(source);
+    assert_eq!(status, 0);
+    assert_eq!(
+        stdout,
+        "66 -2 -125 3 16 2 7 5 -6\n3.0 2.5 1.4142135623730951 0.3333333333333333\n\
+         true true c s\n97\n255\n"
+    );
+}
+
+#[test]
+fn runtime_errors_are_the_same() {
+    for (source, error) in [
+        (
+            "void main() { let x <- 0; println(1 / x); }",
+            "1:35: error[E0204]: division by zero",
+        ),
+        (
+            "void main() { let x: u8 <- 255; println(x + 1); }",
+            "1:41: error[E0203]: `+` overflows the range of `u8`",
+        ),
+        (
+            "void main() { let x: i32 <- 1; println(x << 40); }",
+            "1:40: error[E0203]: `<<` overflows the range of `i32`",
+        ),
+        (
+            "i32 f(i32 n) { return f(n + 1); }\nvoid main() { println(f(0)); }",
+            "1:23: error[E0214]: stack overflow, more than 10000 nested calls",
+        ),
+    ] {
+        let (status, _, errors) = run_both(source);
+        assert_eq!(status, 1);
+        assert_eq!(errors, [error]);
+    }
+}
+
+#[test]
+fn output_before_an_error_is_kept() {
+    let (status, stdout, errors) =
+        run_both("void main() { println(1); let x <- -2147483647 - 1; println(-x); }");
+    assert_eq!(status, 1);
+    assert_eq!(stdout, "1\n");
+    assert_eq!(
+        errors,
+        ["1:61: error[E0203]: `-` overflows the range of `i32`"]
+    );
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use common::{project, vid_in, Output};
+use std::path::{Path, PathBuf};
+use vidyut::vm::{Module, VycError, MAGIC, VERSION};
+
+const PROGRAM: &str = "\
+i32 square(i32 n) {
+    return n * n;
+}
+
+void main() {
+    let mut i <- 1;
+    while i <= 3 {
+        println(\"{i} {square(i)} {1.5 * 2.0} {'c'}\");
+        i += 1;
+    }
+}
+";
+
+/// Builds `source` as `main.vy` of a new project named after `name`, returning the project
+/// and the `.vyc` file written.
+fn build(name: &str, source: &str) -> (PathBuf, Vec<u8>) {
+    let dir = project(name, &[("main.vy", source)]);
+    let output = vid_in(&dir, &["build", "main.vy"], "");
+    assert_eq!(output.status, 0, "{}", output.stderr);
+    let bytes = std::fs::read(dir.join("main.vyc")).expect("`vid build` writes main.vyc");
+    (dir, bytes)
+}
+
+/// Writes `bytes` to `damaged.vyc` in `dir` and runs it.
+fn run_vyc(dir: &Path, bytes: &[u8]) -> Output {
+    std::fs::write(dir.join("damaged.vyc"), bytes).expect("failed to write damaged.vyc");
+    vid_in(dir, &["run", "damaged.vyc"], "")
+}
+
+/// Checks that running `bytes` fails with the error `code` and runs nothing.
+fn assert_refused(dir: &Path, bytes: &[u8], code: &str) {
+    let output = run_vyc(dir, bytes);
+    assert_eq!(output.status, 1, "{}", output.stderr);
+    assert_eq!(output.stdout, "");
+    assert!(
+        output
+            .stderr
+            .starts_with(&format!("damaged.vyc:1:1: error[{}]: could not load", code)),
+        "{}",
+        output.stderr
+    );
+}
+
+/// The CRC-32 the format ends with.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[test]
+fn a_built_program_runs_as_its_source() {
+    let (dir, bytes) = build("vyc-round-trip", PROGRAM);
+    assert!(bytes.starts_with(&MAGIC));
+    assert_eq!(bytes[4..6], VERSION.to_le_bytes());
+
+    let source = vid_in(&dir, &["run", "main.vy"], "");
+    let compiled = vid_in(&dir, &["run", "main.vyc"], "");
+    assert_eq!(compiled.status, 0, "{}", compiled.stderr);
+    assert_eq!(compiled.stdout, source.stdout);
+    assert_eq!(compiled.stdout, "1 1 3.0 c\n2 4 3.0 c\n3 9 3.0 c\n");
+}
+
+#[test]
+fn a_module_is_written_back_as_it_was_read() {
+    let (_, bytes) = build("vyc-rewrite", PROGRAM);
+    let module = Module::from_vyc(&bytes).expect("the file `vid build` wrote loads");
+    assert_eq!(module.to_vyc(), bytes);
+}
+
+#[test]
+fn runtime_errors_point_at_the_source() {
+    let (dir, _) = build(
+        "vyc-error",
+        "void main() {\n    let x <- 0;\n    println(1 / x);\n}\n",
+    );
+    let output = vid_in(&dir, &["run", "main.vyc"], "");
+    assert_eq!(output.status, 1);
+    assert_eq!(
+        output.stderr,
+        "main.vy:3:13: error[E0204]: division by zero\n"
+    );
+}
+
+#[test]
+fn other_files_are_refused() {
+    let (dir, _) = build("vyc-not-vyc", PROGRAM);
+    assert_refused(&dir, b"void main() {}\n", "E0300");
+    assert_refused(&dir, b"", "E0300");
+    assert_eq!(Module::from_vyc(b"VYC").unwrap_err(), VycError::NotVyc);
+}
+
+#[test]
+fn other_versions_are_refused() {
+    let (dir, mut bytes) = build("vyc-version", PROGRAM);
+    bytes[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert_refused(&dir, &bytes, "E0301");
+}
+
+#[test]
+fn corrupt_files_are_refused() {
+    let (dir, bytes) = build("vyc-corrupt", PROGRAM);
+    for position in [6, bytes.len() / 2, bytes.len() - 5, bytes.len() - 1] {
+        let mut corrupt = bytes.clone();
+        corrupt[position] ^= 0x10;
+        assert_refused(&dir, &corrupt, "E0302");
+    }
+}
+
+#[test]
+fn truncated_files_are_refused() {
+    let (dir, bytes) = build("vyc-truncated", PROGRAM);
+    assert_refused(&dir, &bytes[..5], "E0303");
+    assert_refused(&dir, &bytes[..8], "E0303");
+    // The checksum is not where it is expected any more.
+    assert_refused(&dir, &bytes[..bytes.len() / 2], "E0302");
+    assert_refused(&dir, &bytes[..bytes.len() - 1], "E0302");
+    // Cut in the middle of the functions, with a checksum matching what is left.
+    let mut cut = bytes[..bytes.len() - 10].to_vec();
+    cut.extend(crc32(&cut).to_le_bytes());
+    assert_refused(&dir, &cut, "E0303");
+}
+
+#[test]
+fn invalid_modules_are_refused() {
+    let (dir, bytes) = build("vyc-invalid", PROGRAM);
+    // A byte after the functions, with a checksum matching it.
+    let mut invalid = bytes[..bytes.len() - 4].to_vec();
+    invalid.push(0);
+    invalid.extend(crc32(&invalid).to_le_bytes());
+    assert_refused(&dir, &invalid, "E0304");
+    assert_eq!(
+        Module::from_vyc(&invalid).unwrap_err(),
+        VycError::Invalid(String::from("unexpected bytes after the functions"))
+    );
+}